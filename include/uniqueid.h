@@ -0,0 +1,30 @@
+#ifndef UNIQUEID_H
+#define UNIQUEID_H
+
+/* C bindings for the unique_id crate, built with
+ * `cargo rustc --release --features ffi --crate-type cdylib` (or `staticlib`). */
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* An ID generator. Not safe to use from two threads at once. */
+typedef struct IdGenerator IdGenerator;
+
+/* Create a generator, or return NULL if an id doesn't fit its bit field. */
+IdGenerator *uniqueid_new(int32_t machine_id, int32_t server_id);
+
+/* Generate an ID, or return -1 if `id_gen` is NULL or no ID can be generated,
+ * e.g. once the timestamp overflows the layout. */
+int64_t uniqueid_generate(IdGenerator *id_gen);
+
+/* Release a generator; NULL is ignored. */
+void uniqueid_free(IdGenerator *id_gen);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* UNIQUEID_H */
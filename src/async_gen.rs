@@ -0,0 +1,159 @@
+#[cfg(feature = "stream")]
+use crate::Id;
+use crate::{Clock, IdError, IdGenerator, SystemClock};
+#[cfg(feature = "stream")]
+use futures_core::Stream;
+#[cfg(feature = "stream")]
+use std::future::Future;
+#[cfg(feature = "stream")]
+use std::pin::Pin;
+#[cfg(feature = "stream")]
+use std::task::{ready, Context, Poll};
+#[cfg(feature = "stream")]
+use tokio::time::Sleep;
+use tokio::time::{sleep_until, Instant};
+
+/// An [`IdGenerator`] for async code.
+///
+/// When the sequence of the current millisecond is exhausted, or the rate
+/// limit is reached, the generator sleeps on the tokio timer instead of
+/// spinning, so it never blocks the executor.
+///
+/// With the `stream` feature the generator is also an endless `Stream` of
+/// [`Id`](crate::Id)s, which ends where [`AsyncIdGenerator::generate_id`]
+/// fails: once the timestamp overflows the layout, or while the clock reads
+/// earlier than the epoch.
+///
+/// Like an [`IdGenerator`] it isn't `Clone`. Create one from each generator
+/// [`IdGenerator::split`] returns to run several tasks.
+#[derive(Debug)]
+pub struct AsyncIdGenerator<C = SystemClock> {
+    id_gen: IdGenerator<C>,
+    // the timer a polled stream waits on for the next tick
+    #[cfg(feature = "stream")]
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl AsyncIdGenerator {
+    pub fn new(machine_id: i32, server_id: i32) -> Result<Self, IdError> {
+        IdGenerator::new(machine_id, server_id).map(Self::from)
+    }
+}
+
+impl<C: Clock> AsyncIdGenerator<C> {
+    /// generate an ID, sleeping until the next tick when the sequence is
+    /// exhausted and until the rate limit admits it
+    ///
+    /// Fails once the timestamp overflows the layout or while the clock reads
+    /// earlier than the epoch, which waiting doesn't fix.
+    pub async fn generate_id(&mut self) -> Result<i64, IdError> {
+        loop {
+            let delay = match self.id_gen.poll_id() {
+                Ok(Some(id)) => return Ok(id),
+                Ok(None) => self.id_gen.until_next_tick(),
+                Err(IdError::RateLimited) => self.id_gen.rate_delay(1),
+                Err(e) => return Err(e),
+            };
+
+            sleep_until(Instant::now() + delay).await;
+        }
+    }
+}
+
+impl<C> From<IdGenerator<C>> for AsyncIdGenerator<C> {
+    fn from(id_gen: IdGenerator<C>) -> Self {
+        Self {
+            id_gen,
+            #[cfg(feature = "stream")]
+            sleep: None,
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<C: Clock + Unpin> Stream for AsyncIdGenerator<C> {
+    type Item = Id;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Id>> {
+        loop {
+            if let Some(sleep) = &mut self.sleep {
+                ready!(sleep.as_mut().poll(cx));
+                self.sleep = None;
+            }
+
+            // the waits of `generate_id`, which ends the stream where it fails
+            let delay = match self.id_gen.poll_id() {
+                Ok(Some(id)) => return Poll::Ready(Some(Id::from(id as u64))),
+                Ok(None) => self.id_gen.until_next_tick(),
+                Err(IdError::RateLimited) => self.id_gen.rate_delay(1),
+                Err(_) => return Poll::Ready(None),
+            };
+
+            self.sleep = Some(Box::pin(sleep_until(Instant::now() + delay)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::{Layout, MAX_IDS_PER_MILLISECOND};
+
+    #[tokio::test]
+    async fn test_async_generate_across_milliseconds() {
+        let mut id_gen = AsyncIdGenerator::new(1, 2).unwrap();
+        let mut ids = Vec::with_capacity(MAX_IDS_PER_MILLISECOND * 3);
+
+        for _ in 0..MAX_IDS_PER_MILLISECOND * 3 {
+            ids.push(id_gen.generate_id().await.unwrap());
+        }
+
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[tokio::test]
+    async fn test_async_generate_fails_on_overflow() {
+        let clock = MockClock::new(Layout::default().max_timestamp() + 1);
+        let mut id_gen =
+            AsyncIdGenerator::from(IdGenerator::builder().build_with_clock(clock).unwrap());
+
+        assert_eq!(id_gen.generate_id().await, Err(IdError::TimestampOverflow));
+    }
+
+    #[cfg(feature = "stream")]
+    #[tokio::test]
+    async fn test_async_stream_of_ids() {
+        use futures_util::StreamExt;
+
+        let mut id_gen = AsyncIdGenerator::new(1, 2).unwrap();
+
+        let ids: Vec<Id> = id_gen
+            .by_ref()
+            .take(MAX_IDS_PER_MILLISECOND * 3)
+            .collect()
+            .await;
+        let next = id_gen.next().await.unwrap();
+
+        assert_eq!(ids.len(), MAX_IDS_PER_MILLISECOND * 3);
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        assert!(next > ids[ids.len() - 1]);
+        assert!(id_gen.generate_id().await.unwrap() > next.as_u64() as i64);
+    }
+
+    #[cfg(feature = "stream")]
+    #[tokio::test]
+    async fn test_async_stream_ends_before_epoch() {
+        use futures_util::StreamExt;
+        use std::time::{Duration, SystemTime};
+
+        let mut id_gen = IdGenerator::builder()
+            .epoch(SystemTime::UNIX_EPOCH + Duration::from_secs(3_600))
+            .build_with_clock(MockClock::new(1_000))
+            .unwrap();
+        // the next id reads the clock
+        id_gen.index = MAX_IDS_PER_MILLISECOND - 1;
+
+        assert_eq!(AsyncIdGenerator::from(id_gen).next().await, None);
+    }
+}
@@ -0,0 +1,289 @@
+use crate::{IdError, Layout, MAX_IDS_PER_MILLISECOND};
+use std::num::NonZeroU32;
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// the state word holds `timestamp << SEQUENCE_BITS | sequence`
+const SEQUENCE_BITS: u32 = MAX_IDS_PER_MILLISECOND.trailing_zeros();
+const SEQUENCE_MASK: u64 = MAX_IDS_PER_MILLISECOND as u64 - 1;
+
+/// A lock-free generator that can be shared by reference between threads.
+///
+/// The last timestamp and sequence are packed into a single `AtomicU64`
+/// that is advanced with compare-and-swap, so no mutex is needed. Threads
+/// issuing many IDs can take them a block at a time with
+/// [`AtomicIdGenerator::blocks`] instead.
+#[derive(Debug)]
+pub struct AtomicIdGenerator {
+    layout: Layout,
+    machine_id: i32,
+    server_id: i32,
+    state: AtomicU64,
+}
+
+impl AtomicIdGenerator {
+    pub fn new(machine_id: i32, server_id: i32) -> Result<Self, IdError> {
+        let layout = Layout::default();
+        layout.validate_ids(machine_id, server_id)?;
+
+        let timestamp = layout.now() as u64;
+
+        Ok(Self {
+            layout,
+            machine_id,
+            server_id,
+            state: AtomicU64::new(timestamp << SEQUENCE_BITS),
+        })
+    }
+
+    /// generate a unique id
+    ///
+    /// # Panics
+    ///
+    /// Panics once the timestamp overflows the layout, use
+    /// [`AtomicIdGenerator::try_generate_id`] to handle that case.
+    pub fn generate_id(&self) -> i64 {
+        self.try_generate_id().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// generate a unique id, or return [`IdError::TimestampOverflow`] once
+    /// the timestamp no longer fits the layout
+    pub fn try_generate_id(&self) -> Result<i64, IdError> {
+        let mut current = self.state.load(Ordering::Relaxed);
+
+        loop {
+            let timestamp = (current >> SEQUENCE_BITS) as i64;
+            let sequence = current & SEQUENCE_MASK;
+            let now = self.layout.now();
+
+            let next = if now > timestamp {
+                (now as u64) << SEQUENCE_BITS
+            } else if sequence < SEQUENCE_MASK {
+                // same millisecond, or the clock went backwards:
+                // keep the last timestamp so IDs never decrease.
+                current + 1
+            } else {
+                // sequence exhausted, wait for the next millisecond and retry
+                self.layout.wait_next(timestamp);
+                current = self.state.load(Ordering::Relaxed);
+                continue;
+            };
+            self.check_word(next)?;
+
+            match self.state.compare_exchange_weak(
+                current,
+                next,
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Ok(self.pack(next)),
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    /// a handle for one thread that reserves `block` sequence numbers at a
+    /// time, see [`AtomicIdBlocks`]
+    ///
+    /// Blocks are capped at the sequence of one millisecond.
+    pub fn blocks(&self, block: NonZeroU32) -> AtomicIdBlocks<'_> {
+        AtomicIdBlocks {
+            id_gen: self,
+            block: (block.get() as u64).min(MAX_IDS_PER_MILLISECOND as u64),
+            reserved: 0..0,
+        }
+    }
+
+    /// reserve the next `n` state words, packed like `state`
+    fn reserve(&self, n: u64) -> Result<Range<u64>, IdError> {
+        loop {
+            let current = self.state.load(Ordering::Relaxed);
+            let timestamp = (current >> SEQUENCE_BITS) as i64;
+            let now = self.layout.now();
+
+            if now > timestamp {
+                // a new millisecond: the block starts it
+                let start = (now as u64) << SEQUENCE_BITS;
+                self.check_word(start)?;
+                if self
+                    .state
+                    .compare_exchange_weak(
+                        current,
+                        start + n - 1,
+                        Ordering::AcqRel,
+                        Ordering::Relaxed,
+                    )
+                    .is_ok()
+                {
+                    return Ok(start..start + n);
+                }
+            } else if (current & SEQUENCE_MASK) + n <= SEQUENCE_MASK {
+                // the state holds the last issued word, so the block follows
+                // it. Threads racing past the check above carry their blocks
+                // into the next millisecond, which keeps them unique.
+                let last = self.state.fetch_add(n, Ordering::AcqRel);
+                self.check_word(last + n)?;
+                return Ok(last + 1..last + 1 + n);
+            } else {
+                self.layout.wait_next(timestamp);
+            }
+        }
+    }
+
+    /// fail with [`IdError::TimestampOverflow`] if the timestamp of `word`
+    /// doesn't fit the layout, see `IdGenerator::check_timestamp`
+    fn check_word(&self, word: u64) -> Result<(), IdError> {
+        if (word >> SEQUENCE_BITS) as i64 > self.layout.max_timestamp() {
+            return Err(IdError::TimestampOverflow);
+        }
+
+        Ok(())
+    }
+
+    fn pack(&self, word: u64) -> i64 {
+        self.layout.pack(
+            (word >> SEQUENCE_BITS) as i64,
+            self.machine_id,
+            self.server_id,
+            (word & SEQUENCE_MASK) as usize,
+        )
+    }
+}
+
+/// A handle on an [`AtomicIdGenerator`] for one thread, created by
+/// [`AtomicIdGenerator::blocks`].
+///
+/// The handle reserves a block of sequence numbers with a single `fetch_add`
+/// on the shared state and hands them out without touching it again, so the
+/// atomic operation is paid once per block. The IDs of one handle ascend, but
+/// are interleaved with those of other threads, and the IDs left in the block
+/// of a dropped handle are never used.
+#[derive(Debug)]
+pub struct AtomicIdBlocks<'a> {
+    id_gen: &'a AtomicIdGenerator,
+    block: u64,
+    reserved: Range<u64>,
+}
+
+impl AtomicIdBlocks<'_> {
+    /// generate a unique id
+    ///
+    /// # Panics
+    ///
+    /// Panics once the timestamp overflows the layout, use
+    /// [`AtomicIdBlocks::try_generate_id`] to handle that case.
+    pub fn generate_id(&mut self) -> i64 {
+        self.try_generate_id().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// generate a unique id, or return [`IdError::TimestampOverflow`] once
+    /// the timestamp no longer fits the layout
+    pub fn try_generate_id(&mut self) -> Result<i64, IdError> {
+        let word = match self.reserved.next() {
+            Some(word) => word,
+            None => {
+                self.reserved = self.id_gen.reserve(self.block)?;
+                self.reserved.next().expect("blocks are not empty")
+            }
+        };
+
+        Ok(self.id_gen.pack(word))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, SystemTime};
+
+    /// a generator whose epoch is so far back that the timestamp overflowed
+    fn overflowed() -> AtomicIdGenerator {
+        let epoch = SystemTime::UNIX_EPOCH - Duration::from_secs(20 * 365 * 86_400);
+
+        AtomicIdGenerator {
+            layout: Layout::default().with_epoch(epoch),
+            ..AtomicIdGenerator::new(0, 0).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_atomic_generator_unique_across_threads() {
+        let id_gen = Arc::new(AtomicIdGenerator::new(1, 2).unwrap());
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let id_gen = Arc::clone(&id_gen);
+                thread::spawn(move || {
+                    let ids: Vec<i64> = (0..20_000).map(|_| id_gen.generate_id()).collect();
+                    assert!(ids.windows(2).all(|w| w[0] < w[1]));
+                    ids
+                })
+            })
+            .collect();
+
+        let mut ids: Vec<i64> = handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect();
+
+        ids.sort();
+        ids.dedup();
+
+        assert_eq!(ids.len(), 80_000);
+    }
+
+    #[test]
+    fn test_atomic_generator_rollover() {
+        let id_gen = AtomicIdGenerator::new(0, 0).unwrap();
+
+        let ids: Vec<i64> = (0..MAX_IDS_PER_MILLISECOND * 3)
+            .map(|_| id_gen.generate_id())
+            .collect();
+
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_atomic_blocks_unique_across_threads() {
+        let id_gen = AtomicIdGenerator::new(1, 2).unwrap();
+
+        let mut ids: Vec<i64> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut blocks = id_gen.blocks(NonZeroU32::new(100).unwrap());
+                        let ids: Vec<i64> = (0..20_000).map(|_| blocks.generate_id()).collect();
+                        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+                        ids
+                    })
+                })
+                .collect();
+
+            // plain ids from the same state don't collide with the blocks
+            let mut ids: Vec<i64> = (0..20_000).map(|_| id_gen.generate_id()).collect();
+            ids.extend(handles.into_iter().flat_map(|h| h.join().unwrap()));
+            ids
+        });
+
+        ids.sort();
+        ids.dedup();
+
+        assert_eq!(ids.len(), 100_000);
+    }
+
+    #[test]
+    fn test_atomic_generator_timestamp_overflow() {
+        let id_gen = overflowed();
+
+        assert_eq!(id_gen.try_generate_id(), Err(IdError::TimestampOverflow));
+        assert_eq!(
+            id_gen
+                .blocks(NonZeroU32::new(10).unwrap())
+                .try_generate_id(),
+            Err(IdError::TimestampOverflow)
+        );
+        assert!(std::panic::catch_unwind(|| id_gen.generate_id()).is_err());
+    }
+}
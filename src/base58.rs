@@ -0,0 +1,69 @@
+use crate::IdError;
+
+// the Bitcoin alphabet, without 0, O, I and l
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// number of base58 digits needed for `u64::MAX`
+pub const WIDTH: usize = 11;
+
+/// encode without padding, most significant digit first
+pub fn encode(mut value: u64) -> String {
+    let mut buf = Vec::with_capacity(WIDTH);
+
+    loop {
+        buf.push(ALPHABET[(value % 58) as usize]);
+        value /= 58;
+
+        if value == 0 {
+            break;
+        }
+    }
+
+    buf.iter().rev().map(|&b| b as char).collect()
+}
+
+/// decode a base58 string, with or without leading `1`s
+pub fn decode(s: &str) -> Result<u64, IdError> {
+    if s.is_empty() {
+        return Err(IdError::InvalidIdString);
+    }
+
+    s.bytes().try_fold(0u64, |acc, b| {
+        let digit = ALPHABET
+            .iter()
+            .position(|&c| c == b)
+            .ok_or(IdError::InvalidIdString)?;
+
+        acc.checked_mul(58)
+            .and_then(|acc| acc.checked_add(digit as u64))
+            .ok_or(IdError::InvalidIdString)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base58_round_trip() {
+        for value in [0, 1, 57, 58, 1 << 40, i64::MAX as u64, u64::MAX] {
+            let encoded = encode(value);
+
+            assert!(encoded.len() <= WIDTH);
+            assert_eq!(decode(&encoded), Ok(value));
+        }
+
+        assert_eq!(encode(0), "1");
+        assert_eq!(encode(57), "z");
+        assert_eq!(encode(58), "21");
+        assert_eq!(encode(u64::MAX), "jpXCZedGfVQ");
+        assert_eq!(decode("1112"), Ok(1));
+    }
+
+    #[test]
+    fn test_base58_rejects_invalid() {
+        for s in ["", "0", "O", "I", "l", "ab-c", "zzzzzzzzzzzz"] {
+            assert_eq!(decode(s), Err(IdError::InvalidIdString), "{}", s);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use crate::IdError;
+
+// digits are in ASCII order, so fixed-width strings sort like the numbers
+const ALPHABET: &[u8; 62] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// number of base62 digits needed for `u64::MAX`
+pub const WIDTH: usize = 11;
+
+/// encode as a zero-padded, fixed-width base62 string
+pub fn encode(mut value: u64) -> String {
+    let mut buf = [ALPHABET[0]; WIDTH];
+
+    for digit in buf.iter_mut().rev() {
+        *digit = ALPHABET[(value % 62) as usize];
+        value /= 62;
+    }
+
+    buf.iter().map(|&b| b as char).collect()
+}
+
+/// decode a base62 string, with or without leading zero padding
+pub fn decode(s: &str) -> Result<u64, IdError> {
+    if s.is_empty() || s.len() > WIDTH {
+        return Err(IdError::InvalidIdString);
+    }
+
+    s.bytes().try_fold(0u64, |acc, b| {
+        let digit = digit_value(b).ok_or(IdError::InvalidIdString)?;
+
+        acc.checked_mul(62)
+            .and_then(|acc| acc.checked_add(digit as u64))
+            .ok_or(IdError::InvalidIdString)
+    })
+}
+
+/// encode a big-endian byte string as a zero-padded base62 string of `width` digits
+pub fn encode_bytes(bytes: &[u8], width: usize) -> String {
+    let mut num = bytes.to_vec();
+    let mut buf = vec![ALPHABET[0]; width];
+
+    for digit in buf.iter_mut().rev() {
+        // long division of `num` by 62, keeping the remainder as the digit
+        let mut rem = 0u32;
+
+        for byte in num.iter_mut() {
+            let acc = rem << 8 | *byte as u32;
+            *byte = (acc / 62) as u8;
+            rem = acc % 62;
+        }
+
+        *digit = ALPHABET[rem as usize];
+    }
+
+    buf.iter().map(|&b| b as char).collect()
+}
+
+/// decode a base62 string into a big-endian byte string of `N` bytes
+pub fn decode_bytes<const N: usize>(s: &str) -> Result<[u8; N], IdError> {
+    if s.is_empty() {
+        return Err(IdError::InvalidIdString);
+    }
+
+    let mut out = [0u8; N];
+
+    for b in s.bytes() {
+        let mut carry = digit_value(b).ok_or(IdError::InvalidIdString)? as u32;
+
+        for byte in out.iter_mut().rev() {
+            let acc = *byte as u32 * 62 + carry;
+            *byte = acc as u8;
+            carry = acc >> 8;
+        }
+
+        if carry != 0 {
+            return Err(IdError::InvalidIdString);
+        }
+    }
+
+    Ok(out)
+}
+
+fn digit_value(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'A'..=b'Z' => Some(b - b'A' + 10),
+        b'a'..=b'z' => Some(b - b'a' + 36),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base62_round_trip() {
+        for value in [0, 1, 61, 62, 1 << 40, i64::MAX as u64, u64::MAX] {
+            let encoded = encode(value);
+
+            assert_eq!(encoded.len(), WIDTH);
+            assert_eq!(decode(&encoded), Ok(value));
+        }
+
+        assert_eq!(encode(u64::MAX), "LygHa16AHYF");
+        assert_eq!(decode("z"), Ok(61));
+    }
+
+    #[test]
+    fn test_base62_preserves_order() {
+        let values = [0, 9, 10, 35, 36, 61, 62, 3843, 3844, u64::MAX];
+
+        assert!(values.windows(2).all(|w| encode(w[0]) < encode(w[1])));
+    }
+
+    #[test]
+    fn test_base62_rejects_invalid() {
+        assert_eq!(decode(""), Err(IdError::InvalidIdString));
+        assert_eq!(decode("abc-"), Err(IdError::InvalidIdString));
+        assert_eq!(decode("zzzzzzzzzzz"), Err(IdError::InvalidIdString));
+        assert_eq!(decode("000000000000"), Err(IdError::InvalidIdString));
+    }
+
+    #[test]
+    fn test_base62_bytes_matches_u64() {
+        let value = 1_234_567_890_123u64;
+        let encoded = encode_bytes(&value.to_be_bytes(), WIDTH);
+
+        assert_eq!(encoded, encode(value));
+        assert_eq!(decode_bytes::<8>(&encoded), Ok(value.to_be_bytes()));
+        assert_eq!(
+            decode_bytes::<8>("LygHa16AHYG"),
+            Err(IdError::InvalidIdString)
+        );
+    }
+}
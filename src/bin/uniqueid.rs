@@ -0,0 +1,173 @@
+//! Mint and inspect IDs from the command line.
+//!
+//! ```text
+//! uniqueid gen [--count N] [--machine-id M] [--server-id S]
+//! uniqueid decode [--layout NAME] <id>...
+//! ```
+//!
+//! `--layout` names a preset like in config files, e.g. `discord` or
+//! `twitter` to inspect the snowflakes of those services.
+
+use std::process::ExitCode;
+use unique_id::{Id, IdGenerator, LayoutPreset};
+
+const USAGE: &str = "usage:
+    uniqueid gen [--count N] [--machine-id M] [--server-id S]
+    uniqueid decode [--layout NAME] <id>...";
+
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Gen {
+        count: usize,
+        machine_id: i32,
+        server_id: i32,
+    },
+    Decode {
+        layout: LayoutPreset,
+        ids: Vec<Id>,
+    },
+}
+
+fn parse(args: &[String]) -> Result<Command, String> {
+    match args.split_first() {
+        Some((cmd, rest)) if cmd == "gen" => {
+            let (mut count, mut machine_id, mut server_id) = (1, 0, 0);
+            let mut rest = rest.iter();
+
+            while let Some(flag) = rest.next() {
+                let value = rest
+                    .next()
+                    .ok_or_else(|| format!("{} needs a value", flag))?;
+                let invalid = |_| format!("invalid value for {}: {}", flag, value);
+
+                match flag.as_str() {
+                    "--count" | "-n" => count = value.parse().map_err(invalid)?,
+                    "--machine-id" => machine_id = value.parse().map_err(invalid)?,
+                    "--server-id" => server_id = value.parse().map_err(invalid)?,
+                    _ => return Err(format!("unknown option {}", flag)),
+                }
+            }
+
+            Ok(Command::Gen {
+                count,
+                machine_id,
+                server_id,
+            })
+        }
+        Some((cmd, rest)) if cmd == "decode" => {
+            let (layout, ids) = match rest {
+                [flag, name, ids @ ..] if flag == "--layout" => {
+                    let layout = LayoutPreset::from_name(name)
+                        .ok_or_else(|| format!("unknown layout {}", name))?;
+                    (layout, ids)
+                }
+                ids => (LayoutPreset::Default, ids),
+            };
+
+            if ids.is_empty() {
+                return Err(USAGE.to_string());
+            }
+
+            let ids = ids
+                .iter()
+                .map(|id| id.parse().map_err(|e| format!("{}: {}", id, e)))
+                .collect::<Result<_, _>>()?;
+
+            Ok(Command::Decode { layout, ids })
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn run(command: Command) -> Result<(), String> {
+    match command {
+        Command::Gen {
+            count,
+            machine_id,
+            server_id,
+        } => {
+            let mut id_gen = IdGenerator::new(machine_id, server_id).map_err(|e| e.to_string())?;
+
+            for id in id_gen.generate_ids(count) {
+                println!("{}", id);
+            }
+        }
+        Command::Decode { layout, ids } => {
+            for (i, id) in ids.into_iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                println!("{}", id.explain(&layout.layout()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match parse(&args).and_then(run) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_gen() {
+        assert_eq!(
+            parse(&args("gen")),
+            Ok(Command::Gen {
+                count: 1,
+                machine_id: 0,
+                server_id: 0
+            })
+        );
+        assert_eq!(
+            parse(&args("gen --count 5 --machine-id 3 --server-id 7")),
+            Ok(Command::Gen {
+                count: 5,
+                machine_id: 3,
+                server_id: 7
+            })
+        );
+        assert!(parse(&args("gen --count")).is_err());
+        assert!(parse(&args("gen --count many")).is_err());
+        assert!(parse(&args("gen --verbose 1")).is_err());
+    }
+
+    #[test]
+    fn test_parse_decode() {
+        assert_eq!(
+            parse(&args("decode 42 7")),
+            Ok(Command::Decode {
+                layout: LayoutPreset::Default,
+                ids: vec![Id::from(42), Id::from(7)]
+            })
+        );
+        assert_eq!(
+            parse(&args("decode --layout discord 42")),
+            Ok(Command::Decode {
+                layout: LayoutPreset::Discord,
+                ids: vec![Id::from(42)]
+            })
+        );
+        assert!(parse(&args("decode")).is_err());
+        assert!(parse(&args("decode --layout discord")).is_err());
+        assert!(parse(&args("decode --layout mastodon 42")).is_err());
+        assert!(parse(&args("decode -1")).is_err());
+        assert!(parse(&args("")).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+use crate::Layout;
+use std::iter::FusedIterator;
+use std::ops::Range;
+
+/// IDs reserved at once by [`IdGenerator::reserve`](crate::IdGenerator::reserve).
+///
+/// The block only stores the sequence ranges it covers, so reserving many IDs
+/// costs no more memory than a few ticks' worth of bookkeeping. The IDs come
+/// out in the order they were reserved, and nothing else draws them from the
+/// generator. That order is ascending unless the layout
+/// [reverses the sequence](Layout::with_reversed_sequence), which only keeps
+/// the ticks in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdBlock {
+    layout: Layout,
+    machine_id: i32,
+    server_id: i32,
+    // the sequences reserved in each timestamp, in ascending order
+    runs: Vec<(i64, Range<usize>)>,
+    len: usize,
+}
+
+impl IdBlock {
+    pub(crate) fn new(layout: Layout, machine_id: i32, server_id: i32) -> Self {
+        Self {
+            layout,
+            machine_id,
+            server_id,
+            runs: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, timestamp: i64, sequences: Range<usize>) {
+        self.len += sequences.len();
+        self.runs.push((timestamp, sequences));
+    }
+
+    /// number of IDs in the block
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// the first reserved ID of the block
+    pub fn first(&self) -> Option<i64> {
+        self.iter().next()
+    }
+
+    /// the last reserved ID of the block
+    pub fn last(&self) -> Option<i64> {
+        let (timestamp, sequences) = self.runs.last()?;
+
+        Some(self.id(*timestamp, sequences.end - 1))
+    }
+
+    /// iterate over the IDs in the order they were reserved
+    pub fn iter(&self) -> BlockIds<'_> {
+        BlockIds {
+            block: self,
+            run: 0,
+            sequence: self.runs.first().map_or(0, |(_, s)| s.start),
+            left: self.len,
+        }
+    }
+
+    fn id(&self, timestamp: i64, sequence: usize) -> i64 {
+        self.layout
+            .pack(timestamp, self.machine_id, self.server_id, sequence)
+    }
+}
+
+impl<'a> IntoIterator for &'a IdBlock {
+    type Item = i64;
+    type IntoIter = BlockIds<'a>;
+
+    fn into_iter(self) -> BlockIds<'a> {
+        self.iter()
+    }
+}
+
+/// An iterator over the IDs of an [`IdBlock`], created by [`IdBlock::iter`].
+#[derive(Debug, Clone)]
+pub struct BlockIds<'a> {
+    block: &'a IdBlock,
+    run: usize,
+    sequence: usize,
+    left: usize,
+}
+
+impl Iterator for BlockIds<'_> {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<i64> {
+        let (timestamp, sequences) = self.block.runs.get(self.run)?;
+        let id = self.block.id(*timestamp, self.sequence);
+
+        self.sequence += 1;
+        self.left -= 1;
+
+        if self.sequence == sequences.end {
+            self.run += 1;
+            self.sequence = self.block.runs.get(self.run).map_or(0, |(_, s)| s.start);
+        }
+
+        Some(id)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.left, Some(self.left))
+    }
+}
+
+impl ExactSizeIterator for BlockIds<'_> {}
+
+impl FusedIterator for BlockIds<'_> {}
@@ -0,0 +1,506 @@
+use crate::event::Listener;
+use crate::rate::RateLimiter;
+use crate::rng::Rng;
+use crate::utils::system_now;
+use crate::utils::to_ticks;
+use crate::{
+    Clock, ClockMode, DriftPolicy, EventListener, ExhaustionPolicy, IdError, IdGenerator, Layout,
+    RollbackPolicy, SystemClock, WaitStrategy,
+};
+use std::num::NonZeroU32;
+use std::time::{Duration, SystemTime};
+
+/// Builder for [`IdGenerator`] that allows a custom layout, epoch and initial sequence.
+///
+/// Moving the epoch closer to the present extends the lifetime of the
+/// timestamp field.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IdGeneratorBuilder {
+    layout: Layout,
+    epoch: Option<SystemTime>,
+    rollback_policy: RollbackPolicy,
+    exhaustion_policy: ExhaustionPolicy,
+    clock_mode: ClockMode,
+    monotonic: bool,
+    resume_after: Option<i64>,
+    max_drift: Option<Duration>,
+    drift_policy: DriftPolicy,
+    wait_strategy: WaitStrategy,
+    randomize_sequence_start: bool,
+    max_ids_per_second: Option<NonZeroU32>,
+    listener: Option<Listener>,
+    machine_id: i32,
+    server_id: i32,
+    sequence: usize,
+}
+
+impl IdGeneratorBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// set how the fields are packed into an ID (defaults to [`Layout::default`])
+    pub fn layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// set the epoch the timestamp is measured from (defaults to the layout's epoch)
+    pub fn epoch(mut self, epoch: SystemTime) -> Self {
+        self.epoch = Some(epoch);
+        self
+    }
+
+    /// set what happens when the clock moves backwards (defaults to
+    /// [`RollbackPolicy::WaitUntilCaughtUp`])
+    pub fn rollback_policy(mut self, policy: RollbackPolicy) -> Self {
+        self.rollback_policy = policy;
+        self
+    }
+
+    /// set what happens when a millisecond's sequence runs out (defaults to
+    /// [`ExhaustionPolicy::SpinWait`])
+    pub fn exhaustion_policy(mut self, policy: ExhaustionPolicy) -> Self {
+        self.exhaustion_policy = policy;
+        self
+    }
+
+    /// set how the generator waits for the clock to move on (defaults to
+    /// [`WaitStrategy::SpinHint`])
+    pub fn wait_strategy(mut self, strategy: WaitStrategy) -> Self {
+        self.wait_strategy = strategy;
+        self
+    }
+
+    /// set where the current time is read from (defaults to [`ClockMode::Wall`])
+    pub fn clock_mode(mut self, mode: ClockMode) -> Self {
+        self.clock_mode = mode;
+        self
+    }
+
+    /// guarantee that every id is greater than the one before, whichever of
+    /// `generate_id`, `generate_id_by_time` and `generate_id_lazy` issued them
+    ///
+    /// A clock that reads earlier than the last issued id is waited out even
+    /// with [`RollbackPolicy::Error`]. Combine this with
+    /// [`IdGeneratorBuilder::resume_after`] or a
+    /// [`PersistentIdGenerator`](crate::PersistentIdGenerator) to keep the
+    /// guarantee across restarts.
+    pub fn monotonic(mut self, monotonic: bool) -> Self {
+        self.monotonic = monotonic;
+        self
+    }
+
+    /// continue after the last id issued before a restart, so the first id is
+    /// greater than `id` even if the clock went back in the meantime
+    pub fn resume_after(mut self, id: i64) -> Self {
+        self.resume_after = Some(id);
+        self
+    }
+
+    /// bound how far [`IdGenerator::generate_id_lazy`] may date ids ahead of
+    /// the clock (unbounded by default)
+    pub fn max_drift(mut self, max_drift: Duration) -> Self {
+        self.max_drift = Some(max_drift);
+        self
+    }
+
+    /// set what lazy generation does once the max drift is reached (defaults
+    /// to [`DriftPolicy::Wait`])
+    pub fn drift_policy(mut self, policy: DriftPolicy) -> Self {
+        self.drift_policy = policy;
+        self
+    }
+
+    /// make [`IdGenerator::generate_id`] continue lazily in the next tick
+    /// when the sequence runs out, as long as that is at most `max_drift`
+    /// ahead of the clock, and wait for the clock beyond that
+    ///
+    /// Whenever real time has caught up the generator goes back to the clock,
+    /// so bursts get the throughput of lazy generation while timestamps stay
+    /// within `max_drift` of when the ids were issued.
+    pub fn hybrid(self, max_drift: Duration) -> Self {
+        self.exhaustion_policy(ExhaustionPolicy::OverflowIntoNextMs)
+            .max_drift(max_drift)
+            .drift_policy(DriftPolicy::Wait)
+    }
+
+    pub fn machine_id(mut self, machine_id: i32) -> Self {
+        self.machine_id = machine_id;
+        self
+    }
+
+    pub fn server_id(mut self, server_id: i32) -> Self {
+        self.server_id = server_id;
+        self
+    }
+
+    /// set the datacenter id of a [`Layout::multi_datacenter`] generator,
+    /// stored in the machine id field
+    pub fn datacenter_id(self, datacenter_id: i32) -> Self {
+        self.machine_id(datacenter_id)
+    }
+
+    /// set the worker id of a [`Layout::multi_datacenter`] generator, stored
+    /// in the server id field
+    pub fn worker_id(self, worker_id: i32) -> Self {
+        self.server_id(worker_id)
+    }
+
+    /// start the sequence of every tick at a random value instead of 0, so a
+    /// service issuing few IDs doesn't reveal how many it issued
+    ///
+    /// The start is drawn from the lower half of the sequence and the tick's
+    /// IDs count up from there, so they stay ascending at the cost of up to
+    /// half the IDs per tick.
+    pub fn randomize_sequence_start(mut self, randomize: bool) -> Self {
+        self.randomize_sequence_start = randomize;
+        self
+    }
+
+    /// limit how many ids the generator issues per second, admitting bursts
+    /// of up to one second's worth (unlimited by default)
+    ///
+    /// This keeps a runaway caller from minting far future-dated lazy IDs or
+    /// flooding downstream systems. The blocking methods sleep until the limit
+    /// admits the next ID, the `try_` methods fail with
+    /// [`IdError::RateLimited`].
+    pub fn max_ids_per_second(mut self, rate: NonZeroU32) -> Self {
+        self.max_ids_per_second = Some(rate);
+        self
+    }
+
+    /// call `listener` with every [`GeneratorEvent`](crate::GeneratorEvent)
+    /// of the generator, e.g. `.on_event(&|event| log::warn!("{:?}", event))`
+    ///
+    /// The listener is borrowed for `'static` so the builder stays `Copy`;
+    /// a closure that captures nothing is promoted to a static on its own,
+    /// others can be leaked with `Box::leak`.
+    pub fn on_event(mut self, listener: &'static dyn EventListener) -> Self {
+        self.listener = Some(Listener(listener));
+        self
+    }
+
+    /// set the sequence the generator starts counting from
+    pub fn sequence(mut self, sequence: usize) -> Self {
+        self.sequence = sequence;
+        self
+    }
+
+    pub fn build(self) -> Result<IdGenerator, IdError> {
+        self.build_with_clock(SystemClock::new(self.clock_mode))
+    }
+
+    /// build a generator reading the time from `clock`; the clock mode only
+    /// applies to the default [`SystemClock`]
+    pub fn build_with_clock<C: Clock>(self, clock: C) -> Result<IdGenerator<C>, IdError> {
+        let layout = match self.epoch {
+            Some(epoch) => self.layout.with_epoch(epoch),
+            None => self.layout,
+        };
+
+        if self.sequence >= layout.sequence_capacity() {
+            return Err(IdError::InvalidSequence(self.sequence));
+        }
+
+        if layout.epoch() > system_now() {
+            return Err(IdError::EpochInFuture);
+        }
+
+        let mut id_gen = IdGenerator::with_clock(self.machine_id, self.server_id, layout, clock)?;
+        id_gen.index = self.sequence;
+        id_gen.rollback_policy = self.rollback_policy;
+        id_gen.exhaustion_policy = self.exhaustion_policy;
+        id_gen.monotonic = self.monotonic;
+        id_gen.max_drift = self.max_drift.map(|d| to_ticks(d, layout.tick()));
+        id_gen.drift_policy = self.drift_policy;
+        id_gen.wait_strategy = self.wait_strategy;
+        id_gen.sequence_seed = self.randomize_sequence_start.then(|| Rng::new().next_u64());
+        id_gen.rate_limiter = self.max_ids_per_second.map(RateLimiter::per_second);
+        id_gen.listener = self.listener;
+
+        if let Some(id) = self.resume_after {
+            id_gen.resume_after(id);
+        }
+
+        Ok(id_gen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::decode;
+    use crate::MAX_IDS_PER_MILLISECOND;
+
+    #[test]
+    fn test_builder_custom_epoch() {
+        // 2020-01-01T00:00:00Z
+        let epoch = SystemTime::UNIX_EPOCH + Duration::from_millis(1_577_836_800_000);
+
+        let mut custom = IdGenerator::builder()
+            .epoch(epoch)
+            .machine_id(1)
+            .server_id(2)
+            .build()
+            .unwrap();
+        let mut unix = IdGenerator::new(1, 2).unwrap();
+
+        assert!(custom.generate_id() < unix.generate_id());
+    }
+
+    #[test]
+    fn test_builder_initial_sequence() {
+        let mut id_gen = IdGenerator::builder().sequence(41).build().unwrap();
+
+        assert_eq!(id_gen.generate_id() & 0xfff, 42);
+    }
+
+    #[test]
+    fn test_builder_layout_epoch_override() {
+        let epoch = SystemTime::UNIX_EPOCH + Duration::from_millis(1_577_836_800_000);
+
+        let id_gen = IdGenerator::builder()
+            .epoch(epoch)
+            .layout(Layout::sonyflake())
+            .build()
+            .unwrap();
+
+        assert_eq!(id_gen.layout().epoch(), epoch);
+        assert_eq!(id_gen.layout().sequence_capacity(), 256);
+    }
+
+    #[test]
+    fn test_builder_monotonic_clock() {
+        let mut id_gen = IdGenerator::builder()
+            .clock_mode(ClockMode::Monotonic)
+            .build()
+            .unwrap();
+
+        let ids: Vec<i64> = (0..10_000).map(|_| id_gen.generate_id_by_time()).collect();
+
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_builder_with_mock_clock() {
+        let clock = MockClock::new(1_000);
+        let mut id_gen = IdGenerator::builder()
+            .machine_id(1)
+            .build_with_clock(clock.clone())
+            .unwrap();
+
+        assert_eq!(id_gen.try_generate_id(), Ok(1_000 << 22 | 1 << 17 | 1));
+
+        for _ in 2..MAX_IDS_PER_MILLISECOND {
+            id_gen.try_generate_id().unwrap();
+        }
+        assert_eq!(id_gen.try_generate_id(), Err(IdError::SequenceExhausted));
+
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(id_gen.try_generate_id(), Ok(1_001 << 22 | 1 << 17));
+    }
+
+    #[test]
+    fn test_builder_monotonic_across_modes() {
+        let clock = MockClock::new(1_000);
+        let mut id_gen = IdGenerator::builder()
+            .rollback_policy(RollbackPolicy::Error)
+            .monotonic(true)
+            .build_with_clock(clock.clone())
+            .unwrap();
+
+        let mut ids = vec![id_gen.generate_id_by_time()];
+        ids.extend((0..5_000).map(|_| id_gen.generate_id_lazy()));
+
+        // the clock moves back behind the lazily issued ids, then catches up
+        clock.set_millis(999);
+        let waiter = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(10));
+            clock.set_millis(1_002);
+        });
+        ids.push(id_gen.generate_id_by_time());
+        ids.push(id_gen.generate_id());
+        waiter.join().unwrap();
+
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_builder_resume_after() {
+        let last = IdGenerator::builder()
+            .build_with_clock(MockClock::new(2_000))
+            .unwrap()
+            .generate_id_by_time();
+
+        // restarted with a clock that went back
+        let mut id_gen = IdGenerator::builder()
+            .resume_after(last)
+            .monotonic(true)
+            .build_with_clock(MockClock::new(1_000))
+            .unwrap();
+
+        assert!(id_gen.generate_id() > last);
+        assert!(id_gen.generate_id_lazy() > last);
+    }
+
+    #[test]
+    fn test_builder_max_drift() {
+        let clock = MockClock::new(1_000);
+        let mut id_gen = IdGenerator::builder()
+            .max_drift(Duration::from_millis(2))
+            .drift_policy(DriftPolicy::Error)
+            .build_with_clock(clock.clone())
+            .unwrap();
+
+        let issued = (0..)
+            .map_while(|_| id_gen.try_generate_id_lazy().ok())
+            .count();
+        assert_eq!(issued, 3 * MAX_IDS_PER_MILLISECOND - 1);
+        assert_eq!(id_gen.try_generate_id_lazy(), Err(IdError::DriftExceeded));
+
+        clock.advance(Duration::from_millis(1));
+        assert!(id_gen.try_generate_id_lazy().is_ok());
+    }
+
+    #[test]
+    fn test_builder_max_ids_per_second() {
+        let clock = MockClock::new(1_000);
+        let mut id_gen = IdGenerator::builder()
+            .max_ids_per_second(NonZeroU32::new(100).unwrap())
+            .build_with_clock(clock.clone())
+            .unwrap();
+
+        // a burst of one second's worth, then one id every 10 ms
+        let issued = (0..)
+            .map_while(|_| id_gen.try_generate_id_lazy().ok())
+            .count();
+        assert_eq!(issued, 100);
+        assert_eq!(id_gen.try_generate_id(), Err(IdError::RateLimited));
+        assert_eq!(id_gen.try_generate_id_by_time(), Err(IdError::RateLimited));
+
+        clock.advance(Duration::from_millis(10));
+        assert!(id_gen.try_generate_id_by_time().is_ok());
+        assert_eq!(id_gen.try_generate_id_lazy(), Err(IdError::RateLimited));
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(id_gen.reserve(100).len(), 100);
+        assert_eq!(id_gen.try_generate_id(), Err(IdError::RateLimited));
+    }
+
+    #[test]
+    fn test_builder_max_ids_per_second_waits() {
+        let mut id_gen = IdGenerator::builder()
+            .max_ids_per_second(NonZeroU32::new(1_000).unwrap())
+            .build()
+            .unwrap();
+
+        let start = std::time::Instant::now();
+        let ids = id_gen.generate_ids(1_050);
+
+        assert!(start.elapsed() >= Duration::from_millis(40));
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_builder_max_drift_waits() {
+        let clock = MockClock::new(1_000);
+        let mut id_gen = IdGenerator::builder()
+            .max_drift(Duration::ZERO)
+            .build_with_clock(clock.clone())
+            .unwrap();
+
+        for _ in 1..MAX_IDS_PER_MILLISECOND {
+            id_gen.generate_id_lazy();
+        }
+
+        let waiter = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(10));
+            clock.advance(Duration::from_millis(1));
+        });
+        let id = id_gen.generate_id_lazy();
+        waiter.join().unwrap();
+
+        assert_eq!(id, 1_001 << 22);
+    }
+
+    #[test]
+    fn test_builder_hybrid() {
+        let clock = MockClock::new(1_000);
+        let mut id_gen = IdGenerator::builder()
+            .hybrid(Duration::from_millis(5))
+            .build_with_clock(clock.clone())
+            .unwrap();
+
+        // a burst runs ahead of the stalled clock without waiting
+        let burst = id_gen.generate_ids(5 * MAX_IDS_PER_MILLISECOND);
+        assert_eq!(decode(*burst.last().unwrap()).timestamp, 1_005);
+
+        // once the clock has passed the lazy timestamp, ids follow it again
+        clock.set_millis(1_010);
+        let ids = id_gen.generate_ids(MAX_IDS_PER_MILLISECOND);
+        assert_eq!(decode(*ids.last().unwrap()).timestamp, 1_010);
+        assert!(burst.last() < ids.first());
+    }
+
+    #[test]
+    fn test_builder_randomize_sequence_start() {
+        let clock = MockClock::new(1_000);
+        let mut id_gen = IdGenerator::builder()
+            .randomize_sequence_start(true)
+            .build_with_clock(clock.clone())
+            .unwrap();
+
+        let mut firsts = Vec::new();
+        let mut ids = Vec::new();
+        for tick in 1..=20 {
+            clock.set_millis(1_000 + tick);
+            let id = id_gen.generate_id_by_time();
+            firsts.push(decode(id).sequence);
+            ids.extend([id, id_gen.generate_id_by_time()]);
+        }
+
+        // the first id of a tick reached by `generate_id` and `generate_id_lazy`
+        type Generate = fn(&mut IdGenerator<MockClock>) -> i64;
+        let mut first_of_tick = |timestamp, generate: Generate| loop {
+            let id = generate(&mut id_gen);
+            ids.push(id);
+            if decode(id).timestamp == timestamp {
+                return decode(id).sequence;
+            }
+        };
+        clock.set_millis(1_030);
+        firsts.push(first_of_tick(1_030, |g| g.generate_id()));
+        firsts.push(first_of_tick(1_031, |g| g.generate_id_lazy()));
+
+        assert!(firsts.iter().all(|&s| s < MAX_IDS_PER_MILLISECOND / 2));
+        assert!(firsts.iter().any(|&s| s > 0));
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_values() {
+        let future = SystemTime::now() + Duration::from_secs(3600);
+
+        assert_eq!(
+            IdGenerator::builder().machine_id(32).build().unwrap_err(),
+            IdError::InvalidMachineId(32)
+        );
+        assert_eq!(
+            IdGenerator::builder().server_id(-1).build().unwrap_err(),
+            IdError::InvalidServerId(-1)
+        );
+        assert_eq!(
+            IdGenerator::builder()
+                .sequence(MAX_IDS_PER_MILLISECOND)
+                .build()
+                .unwrap_err(),
+            IdError::InvalidSequence(MAX_IDS_PER_MILLISECOND)
+        );
+        assert_eq!(
+            IdGenerator::builder().epoch(future).build().unwrap_err(),
+            IdError::EpochInFuture
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use crate::IdError;
+
+/// Algorithms for the check digit appended by
+/// [`Id::to_string_with_check`](crate::Id::to_string_with_check).
+///
+/// Both catch every mistyped single digit. Damm also catches every swap of
+/// two adjacent digits, Luhn all but `09` and `90`, but Luhn is the one most
+/// other systems can already verify.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CheckDigit {
+    #[default]
+    Damm,
+    Luhn,
+}
+
+// the totally anti-symmetric quasigroup of order 10 from Damm's thesis
+const DAMM_TABLE: [[u8; 10]; 10] = [
+    [0, 3, 1, 7, 5, 9, 8, 6, 4, 2],
+    [7, 0, 9, 2, 1, 5, 4, 8, 6, 3],
+    [4, 2, 0, 6, 8, 7, 1, 3, 5, 9],
+    [1, 7, 5, 0, 9, 8, 3, 4, 2, 6],
+    [6, 1, 2, 3, 0, 4, 5, 9, 7, 8],
+    [3, 6, 7, 4, 2, 0, 9, 5, 8, 1],
+    [5, 8, 6, 9, 7, 2, 0, 1, 3, 4],
+    [8, 9, 4, 5, 3, 6, 2, 0, 1, 7],
+    [9, 4, 3, 8, 6, 1, 7, 2, 0, 5],
+    [2, 5, 8, 1, 4, 3, 6, 7, 9, 0],
+];
+
+impl CheckDigit {
+    /// the check digit of a string of ASCII digits
+    fn digit(self, digits: &[u8]) -> u8 {
+        match self {
+            CheckDigit::Damm => digits.iter().fold(0, |interim, &d| {
+                DAMM_TABLE[interim as usize][(d - b'0') as usize]
+            }),
+            CheckDigit::Luhn => {
+                // double every second digit, starting from the rightmost
+                let sum: u32 = digits
+                    .iter()
+                    .rev()
+                    .enumerate()
+                    .map(|(i, &d)| {
+                        let d = (d - b'0') as u32;
+                        match i % 2 {
+                            0 => d * 2 - if d > 4 { 9 } else { 0 },
+                            _ => d,
+                        }
+                    })
+                    .sum();
+
+                ((10 - sum % 10) % 10) as u8
+            }
+        }
+    }
+}
+
+/// append the check digit to a decimal number
+pub fn append(check: CheckDigit, value: u64) -> String {
+    let mut s = value.to_string();
+    let digit = check.digit(s.as_bytes());
+    s.push((b'0' + digit) as char);
+    s
+}
+
+/// verify and strip the check digit of a decimal number
+pub fn verify(check: CheckDigit, s: &str) -> Result<u64, IdError> {
+    let bytes = s.as_bytes();
+
+    if bytes.len() < 2 || !bytes.iter().all(u8::is_ascii_digit) {
+        return Err(IdError::InvalidIdString);
+    }
+
+    let (digits, last) = bytes.split_at(bytes.len() - 1);
+
+    if check.digit(digits) != last[0] - b'0' {
+        return Err(IdError::ChecksumMismatch);
+    }
+
+    s[..digits.len()]
+        .parse()
+        .map_err(|_| IdError::InvalidIdString)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_check_digits() {
+        // the reference examples of both algorithms
+        assert_eq!(append(CheckDigit::Damm, 572), "5724");
+        assert_eq!(append(CheckDigit::Luhn, 7_992_739_871), "79927398713");
+        assert_eq!(verify(CheckDigit::Damm, "5724"), Ok(572));
+        assert_eq!(verify(CheckDigit::Luhn, "79927398713"), Ok(7_992_739_871));
+    }
+
+    #[test]
+    fn test_check_digits_catch_typos() {
+        let value = 7_147_375_873_229_221_889;
+
+        for check in [CheckDigit::Damm, CheckDigit::Luhn] {
+            let s = append(check, value);
+
+            for i in 0..s.len() {
+                for typo in b'0'..=b'9' {
+                    let mut bytes = s.clone().into_bytes();
+                    if bytes[i] == typo {
+                        continue;
+                    }
+                    bytes[i] = typo;
+
+                    let typed = String::from_utf8(bytes).unwrap();
+                    assert_ne!(verify(check, &typed), Ok(value), "{:?} {}", check, typed);
+                }
+            }
+        }
+
+        let s = append(CheckDigit::Damm, value);
+
+        for i in 1..s.len() {
+            let mut bytes = s.clone().into_bytes();
+            if bytes[i - 1] == bytes[i] {
+                continue;
+            }
+            bytes.swap(i - 1, i);
+
+            let swapped = String::from_utf8(bytes).unwrap();
+            assert_eq!(
+                verify(CheckDigit::Damm, &swapped),
+                Err(IdError::ChecksumMismatch)
+            );
+        }
+    }
+
+    #[test]
+    fn test_verify_rejects_invalid() {
+        assert_eq!(verify(CheckDigit::Damm, ""), Err(IdError::InvalidIdString));
+        assert_eq!(verify(CheckDigit::Damm, "4"), Err(IdError::InvalidIdString));
+        assert_eq!(
+            verify(CheckDigit::Luhn, "12a4"),
+            Err(IdError::InvalidIdString)
+        );
+        assert_eq!(
+            verify(CheckDigit::Damm, "5742"),
+            Err(IdError::ChecksumMismatch)
+        );
+
+        let too_large = "99999999999999999999";
+        let digit = CheckDigit::Damm.digit(too_large.as_bytes());
+        assert_eq!(
+            verify(CheckDigit::Damm, &format!("{}{}", too_large, digit)),
+            Err(IdError::InvalidIdString)
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use crate::{Clock, Id, IdError, IdGenerator, IdSource, SystemClock};
+use std::collections::VecDeque;
+use std::io;
+use std::num::NonZeroU32;
+use std::time::{Duration, Instant};
+
+/// A remote service an [`IdClient`] fetches its IDs from, e.g. a
+/// [`DaemonClient`](crate::daemon::DaemonClient).
+///
+/// This crate has no HTTP or gRPC ID server. To use one, implement this
+/// trait for its client.
+pub trait RemoteIds {
+    /// fetch the next `n` IDs in one request
+    ///
+    /// A hung service should fail after a timeout rather than block. An
+    /// empty batch counts as a failed fetch.
+    fn fetch(&mut self, n: u32) -> io::Result<Vec<i64>>;
+}
+
+#[cfg(all(unix, feature = "daemon"))]
+impl RemoteIds for crate::daemon::DaemonClient {
+    fn fetch(&mut self, n: u32) -> io::Result<Vec<i64>> {
+        self.next_ids(n)
+    }
+}
+
+/// Hands out IDs fetched in batches from a remote service, and generates
+/// them locally while the service can't be reached.
+///
+/// The fallback generator must have machine bits none of the service's
+/// generators use, so its IDs can't collide with theirs. After a failed
+/// fetch the client sticks to the fallback for the
+/// [retry delay](Self::retry_after) before asking the service again. The IDs
+/// stay unique across a failover, but those of the fallback don't sort in
+/// with the ones fetched before.
+#[derive(Debug)]
+pub struct IdClient<R, C = SystemClock> {
+    remote: R,
+    fallback: IdGenerator<C>,
+    batch: u32,
+    retry_after: Duration,
+    cached: VecDeque<i64>,
+    // while the service is down: when to ask it again
+    retry_at: Option<Instant>,
+}
+
+impl<R: RemoteIds, C: Clock> IdClient<R, C> {
+    /// create a client fetching 1000 IDs at a time from `remote`, retrying
+    /// a failed service after a second
+    pub fn new(remote: R, fallback: IdGenerator<C>) -> Self {
+        Self {
+            remote,
+            fallback,
+            batch: 1_000,
+            retry_after: Duration::from_secs(1),
+            cached: VecDeque::new(),
+            retry_at: None,
+        }
+    }
+
+    /// set how many IDs to fetch per request (defaults to 1000)
+    pub fn batch(mut self, batch: NonZeroU32) -> Self {
+        self.batch = batch.get();
+        self
+    }
+
+    /// set how long to use the fallback after a failed fetch before asking
+    /// the service again (defaults to a second)
+    pub fn retry_after(mut self, retry_after: Duration) -> Self {
+        self.retry_after = retry_after;
+        self
+    }
+
+    /// whether the last fetch failed, so IDs come from the fallback
+    pub fn is_degraded(&self) -> bool {
+        self.retry_at.is_some()
+    }
+
+    /// the next cached ID, fetching a new batch once they run out, or an ID
+    /// of the fallback while the service is down
+    ///
+    /// The fallback waits for the clock like [`IdGenerator::generate_id`],
+    /// and this only fails where that would panic.
+    pub fn next_id(&mut self) -> Result<i64, IdError> {
+        if self.cached.is_empty() && self.retry_at.is_none_or(|at| Instant::now() >= at) {
+            self.refill();
+        }
+
+        match self.cached.pop_front() {
+            Some(id) => Ok(id),
+            None => self.fallback.wait_for_id(),
+        }
+    }
+
+    fn refill(&mut self) {
+        match self.remote.fetch(self.batch) {
+            Ok(ids) if !ids.is_empty() => {
+                self.cached.extend(ids);
+                self.retry_at = None;
+            }
+            _ => self.retry_at = Some(Instant::now() + self.retry_after),
+        }
+    }
+}
+
+impl<R: RemoteIds, C: Clock> IdSource for IdClient<R, C> {
+    type Id = Id;
+
+    fn next(&mut self) -> Result<Id, IdError> {
+        self.next_id().and_then(Id::try_from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode;
+
+    /// hands out counters from 1, unless it is down
+    struct Counter {
+        next: i64,
+        up: bool,
+        fetches: usize,
+    }
+
+    /// answers every fetch, but with no IDs
+    struct Empty;
+
+    impl RemoteIds for Empty {
+        fn fetch(&mut self, _: u32) -> io::Result<Vec<i64>> {
+            Ok(Vec::new())
+        }
+    }
+
+    impl RemoteIds for Counter {
+        fn fetch(&mut self, n: u32) -> io::Result<Vec<i64>> {
+            self.fetches += 1;
+            if !self.up {
+                return Err(io::ErrorKind::ConnectionRefused.into());
+            }
+
+            let ids = (self.next..self.next + n as i64).collect();
+            self.next += n as i64;
+            Ok(ids)
+        }
+    }
+
+    #[test]
+    fn test_client_fails_over_to_fallback() {
+        let remote = Counter {
+            next: 1,
+            up: true,
+            fetches: 0,
+        };
+        let mut client = IdClient::new(remote, IdGenerator::new(31, 0).unwrap())
+            .batch(NonZeroU32::new(3).unwrap())
+            .retry_after(Duration::from_secs(3_600));
+
+        let ids: Vec<i64> = (0..4).map(|_| client.next_id().unwrap()).collect();
+        assert_eq!(ids, [1, 2, 3, 4]);
+        assert_eq!(client.remote.fetches, 2);
+
+        // the cached ids are used up before the outage shows
+        client.remote.up = false;
+        assert_eq!(client.next_id().unwrap(), 5);
+        assert_eq!(client.next_id().unwrap(), 6);
+        let local = client.next_id().unwrap();
+        assert_eq!(decode(local).machine_id, 31);
+        assert!(client.is_degraded());
+
+        // no request until the retry delay passed
+        client.next_id().unwrap();
+        assert_eq!(client.remote.fetches, 3);
+
+        client.remote.up = true;
+        client.retry_at = Some(Instant::now());
+        assert_eq!(client.next_id().unwrap(), 7);
+        assert!(!client.is_degraded());
+    }
+
+    #[test]
+    fn test_client_fails_over_on_empty_batch() {
+        let mut client = IdClient::new(Empty, IdGenerator::new(31, 0).unwrap());
+
+        assert_eq!(decode(client.next_id().unwrap()).machine_id, 31);
+        assert!(client.is_degraded());
+    }
+}
@@ -0,0 +1,213 @@
+use crate::utils::*;
+use crate::{Clock, IdError, Layout, WaitStrategy};
+#[cfg(any(test, feature = "test-util"))]
+use std::sync::{
+    atomic::{AtomicI64, Ordering},
+    Arc,
+};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Where a generator reads the current time from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClockMode {
+    /// read the system clock on every call
+    #[default]
+    Wall,
+    /// read the system clock once when the generator is created and advance
+    /// it with [`Instant`], so later adjustments of the system clock have no
+    /// effect on the generator
+    Monotonic,
+}
+
+/// The default [`Clock`] of an [`IdGenerator`](crate::IdGenerator), reading
+/// the system clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock {
+    // with `ClockMode::Monotonic`: an instant and the time since the unix epoch at that instant
+    anchor: Option<(Instant, Duration)>,
+}
+
+impl SystemClock {
+    pub fn new(mode: ClockMode) -> Self {
+        let anchor = match mode {
+            ClockMode::Wall => None,
+            ClockMode::Monotonic => Some((Instant::now(), since_unix_epoch())),
+        };
+
+        Self { anchor }
+    }
+
+    fn elapsed(&self) -> Duration {
+        match self.anchor {
+            None => since_unix_epoch(),
+            Some((anchor, since_epoch)) => since_epoch + anchor.elapsed(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> i64 {
+        self.elapsed().as_millis() as i64
+    }
+
+    fn now_micros(&self) -> i64 {
+        self.elapsed().as_micros() as i64
+    }
+}
+
+/// A [`Clock`] that only moves when told to, for deterministic tests of
+/// code that depends on IDs.
+///
+/// Clones share the same time, so keep one to move the clock of a generator
+/// built with another. A generator waiting for the next tick spins until the
+/// clock is advanced, so tests usually prefer the `try_` methods.
+#[cfg(any(test, feature = "test-util"))]
+#[derive(Debug, Clone, Default)]
+pub struct MockClock {
+    micros: Arc<AtomicI64>,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl MockClock {
+    /// create a clock reading `millis` milliseconds since the unix epoch
+    pub fn new(millis: i64) -> Self {
+        let clock = Self::default();
+        clock.set_millis(millis);
+        clock
+    }
+
+    pub fn set_millis(&self, millis: i64) {
+        self.micros.store(millis * 1_000, Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.micros
+            .fetch_add(by.as_micros() as i64, Ordering::SeqCst);
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl Clock for MockClock {
+    fn now_millis(&self) -> i64 {
+        self.now_micros().div_euclid(1_000)
+    }
+
+    fn now_micros(&self) -> i64 {
+        self.micros.load(Ordering::SeqCst)
+    }
+}
+
+fn since_unix_epoch() -> Duration {
+    system_now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+/// Reading a [`Clock`] in the ticks of a [`Layout`].
+pub(crate) trait LayoutClock: Clock {
+    /// current timestamp in the layout's ticks, or an error if the clock lies
+    /// before the epoch
+    fn try_ticks(&self, layout: &Layout) -> Result<i64, IdError> {
+        let elapsed = self.now_micros() as i128 - epoch_micros(layout);
+
+        if elapsed < 0 {
+            return Err(IdError::ClockBeforeEpoch);
+        }
+
+        Ok((elapsed * 1_000 / layout.tick().as_nanos() as i128) as i64)
+    }
+
+    /// current timestamp in the layout's ticks, saturating at the epoch
+    fn ticks(&self, layout: &Layout) -> i64 {
+        self.try_ticks(layout).unwrap_or_default()
+    }
+
+    /// wait until the timestamp moves past `timestamp`
+    fn wait_next(&self, layout: &Layout, timestamp: i64, strategy: WaitStrategy) -> i64 {
+        wait_until_after_with(timestamp, strategy, || self.ticks(layout))
+    }
+
+    /// like `wait_next`, but give up with `None` once `deadline` has passed
+    fn wait_next_until(
+        &self,
+        layout: &Layout,
+        timestamp: i64,
+        strategy: WaitStrategy,
+        deadline: Instant,
+    ) -> Option<i64> {
+        wait_until_after_or_deadline(timestamp, strategy, Some(deadline), || self.ticks(layout))
+    }
+
+    /// time left until the timestamp moves past `timestamp`
+    fn until_after(&self, layout: &Layout, timestamp: i64) -> Duration {
+        let next_tick = layout.tick().as_nanos() as i128 * (timestamp + 1).max(0) as i128;
+        let elapsed = (self.now_micros() as i128 - epoch_micros(layout)) * 1_000;
+
+        Duration::from_nanos((next_tick - elapsed).max(0) as u64)
+    }
+}
+
+impl<C: Clock + ?Sized> LayoutClock for C {}
+
+/// microseconds from the unix epoch to the layout's epoch
+fn epoch_micros(layout: &Layout) -> i128 {
+    match layout.epoch().duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(d) => d.as_micros() as i128,
+        Err(e) => -(e.duration().as_micros() as i128),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_monotonic_clock_tracks_wall_clock() {
+        let layout = Layout::default();
+        let clock = SystemClock::new(ClockMode::Monotonic);
+
+        assert!((clock.ticks(&layout) - layout.now()).abs() <= 1);
+
+        let now = clock.ticks(&layout);
+        assert!(clock.wait_next(&layout, now, WaitStrategy::SpinHint) > now);
+    }
+
+    #[test]
+    fn test_until_after() {
+        let layout = Layout::default();
+        let clock = SystemClock::new(ClockMode::Wall);
+        let now = clock.ticks(&layout);
+
+        assert!(clock.until_after(&layout, now) <= layout.tick());
+        assert_eq!(clock.until_after(&layout, now - 10), Duration::ZERO);
+        assert!(clock.until_after(&layout, now + 10) > layout.tick() * 9);
+    }
+
+    #[test]
+    fn test_monotonic_clock_ignores_system_clock() {
+        let layout = Layout::default();
+        // as if the system clock read 100 seconds after the epoch at creation
+        let clock = SystemClock {
+            anchor: Some((Instant::now(), Duration::from_secs(100))),
+        };
+
+        assert!((100_000..100_010).contains(&clock.ticks(&layout)));
+    }
+
+    #[test]
+    fn test_mock_clock_in_layout_ticks() {
+        let clock = MockClock::new(1_500);
+        let layout = Layout::sonyflake().with_epoch(SystemTime::UNIX_EPOCH);
+
+        assert_eq!(clock.ticks(&layout), 150);
+
+        clock.clone().advance(Duration::from_millis(25));
+        assert_eq!(clock.now_millis(), 1_525);
+        assert_eq!(clock.ticks(&layout), 152);
+        assert_eq!(clock.ticks(&Layout::microsecond()), 0);
+        assert_eq!(
+            clock.try_ticks(&Layout::microsecond()),
+            Err(IdError::ClockBeforeEpoch)
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use crate::{BucketConfig, IdError, IdGenerator};
+use crossbeam_queue::ArrayQueue;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, TryLockError, Weak};
+use std::thread::{self, Thread};
+
+/// An [`IdGeneratorBucket`](crate::IdGeneratorBucket) that any number of
+/// threads can take IDs from at once.
+///
+/// The IDs wait in a lock-free queue, so taking one never locks. Only the
+/// refill locks the generator: inline by the thread that finds the bucket
+/// empty, or ahead of time by a background producer. Clones share the same
+/// bucket.
+#[derive(Debug, Clone)]
+pub struct ConcurrentIdBucket {
+    inner: Arc<BucketInner>,
+}
+
+#[derive(Debug)]
+struct BucketInner {
+    queue: ArrayQueue<i64>,
+    id_gen: Mutex<IdGenerator>,
+    refill_size: usize,
+    // the background producer and the number of ids below which it is woken
+    producer: OnceLock<(Thread, usize)>,
+}
+
+impl ConcurrentIdBucket {
+    pub fn new(machine_id: i32, server_id: i32) -> Result<Self, IdError> {
+        let id_gen = IdGenerator::new(machine_id, server_id)?;
+
+        Self::with_config(id_gen, BucketConfig::default())
+    }
+
+    /// create a bucket whose next batch is generated on a background thread
+    /// as soon as fewer than `low_watermark` ids are left
+    pub fn with_background_refill(
+        machine_id: i32,
+        server_id: i32,
+        low_watermark: usize,
+    ) -> Result<Self, IdError> {
+        let id_gen = IdGenerator::new(machine_id, server_id)?;
+        let config = BucketConfig {
+            background_low_watermark: Some(low_watermark),
+            ..BucketConfig::default()
+        };
+
+        Self::with_config(id_gen, config)
+    }
+
+    /// create a bucket drawing lazily generated ids from `id_gen`, see
+    /// [`IdGeneratorBucket::with_config`](crate::IdGeneratorBucket::with_config)
+    pub fn with_config(mut id_gen: IdGenerator, config: BucketConfig) -> Result<Self, IdError> {
+        config.validate()?;
+        id_gen.apply_bucket_config(&config);
+
+        let inner = Arc::new(BucketInner {
+            queue: ArrayQueue::new(config.capacity),
+            id_gen: Mutex::new(id_gen),
+            refill_size: config.refill_size,
+            producer: OnceLock::new(),
+        });
+
+        if let Some(low_watermark) = config.background_low_watermark {
+            let producer = spawn_producer(Arc::downgrade(&inner));
+            producer.unpark();
+            let _ = inner.producer.set((producer, low_watermark));
+        }
+
+        Ok(Self { inner })
+    }
+
+    /// take an id, refilling the bucket on this thread if it is empty
+    ///
+    /// # Panics
+    ///
+    /// Panics if a refill fails like
+    /// [`IdGenerator::generate_id_lazy`] does, e.g. once the timestamp
+    /// overflows the layout.
+    pub fn get_id(&self) -> i64 {
+        self.wait_for_id().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// like `get_id`, but returns the errors it would panic with
+    pub(crate) fn wait_for_id(&self) -> Result<i64, IdError> {
+        loop {
+            if let Some(id) = self.inner.queue.pop() {
+                self.inner.taken();
+                return Ok(id);
+            }
+
+            let mut id_gen = self.inner.lock();
+
+            // another thread may have refilled while this one waited
+            if self.inner.queue.is_empty() {
+                self.inner.fill(&mut id_gen)?;
+            }
+        }
+    }
+
+    /// take an id like [`ConcurrentIdBucket::get_id`] without ever blocking
+    ///
+    /// Returns [`IdError::SequenceExhausted`] when the bucket is empty and
+    /// another thread is refilling it, and [`IdError::DriftExceeded`] when a
+    /// refill would run further ahead of the clock than the max drift allows.
+    pub fn try_get_id(&self) -> Result<i64, IdError> {
+        if let Some(id) = self.inner.queue.pop() {
+            self.inner.taken();
+            return Ok(id);
+        }
+
+        let mut id_gen = match self.inner.id_gen.try_lock() {
+            Ok(id_gen) => id_gen,
+            Err(TryLockError::Poisoned(e)) => e.into_inner(),
+            Err(TryLockError::WouldBlock) => return Err(IdError::SequenceExhausted),
+        };
+
+        if self.inner.queue.is_empty() {
+            self.inner.try_fill(&mut id_gen)?;
+        }
+        drop(id_gen);
+
+        let id = self.inner.queue.pop().ok_or(IdError::SequenceExhausted)?;
+        self.inner.taken();
+
+        Ok(id)
+    }
+
+    /// take `n` ids at once, refilling the bucket as often as needed
+    pub fn get_ids(&self, n: usize) -> Vec<i64> {
+        let mut ids = vec![0; n];
+        self.fill_ids(&mut ids);
+        ids
+    }
+
+    /// fill `out` with ids, like [`ConcurrentIdBucket::get_ids`] without allocating
+    pub fn fill_ids(&self, out: &mut [i64]) {
+        for id in out.iter_mut() {
+            *id = self.get_id();
+        }
+    }
+}
+
+impl BucketInner {
+    fn lock(&self) -> MutexGuard<'_, IdGenerator> {
+        // the generator state is always consistent, see `SharedIdGenerator::lock`
+        self.id_gen.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// how many ids the next refill adds, staying within the capacity
+    fn refill_len(&self) -> usize {
+        self.refill_size
+            .min(self.queue.capacity() - self.queue.len())
+    }
+
+    // Only the holder of the generator lock pushes, and other threads only
+    // pop, so the room counted before a refill is still there during it.
+
+    /// add a batch of lazily generated ids, failing only if not a single id
+    /// could be generated
+    fn fill(&self, id_gen: &mut IdGenerator) -> Result<(), IdError> {
+        self.fill_with(|| id_gen.wait_for_lazy_id())
+    }
+
+    /// like `fill`, but stops instead of waiting for the clock
+    fn try_fill(&self, id_gen: &mut IdGenerator) -> Result<(), IdError> {
+        self.fill_with(|| id_gen.try_generate_id_lazy())
+    }
+
+    fn fill_with(&self, mut next: impl FnMut() -> Result<i64, IdError>) -> Result<(), IdError> {
+        for i in 0..self.refill_len() {
+            match next() {
+                Ok(id) => {
+                    if self.queue.push(id).is_err() {
+                        break;
+                    }
+                }
+                Err(e) if i == 0 => return Err(e),
+                Err(_) => break,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// wake the producer once fewer ids than its low watermark are left
+    fn taken(&self) {
+        if let Some((producer, low_watermark)) = self.producer.get() {
+            if self.queue.len() < *low_watermark {
+                producer.unpark();
+            }
+        }
+    }
+}
+
+impl Drop for BucketInner {
+    fn drop(&mut self) {
+        // let the producer see that the bucket is gone
+        if let Some((producer, _)) = self.producer.get() {
+            producer.unpark();
+        }
+    }
+}
+
+/// a thread refilling the bucket whenever it is unparked, until the bucket
+/// is dropped
+fn spawn_producer(bucket: Weak<BucketInner>) -> Thread {
+    thread::spawn(move || loop {
+        thread::park();
+
+        let Some(bucket) = bucket.upgrade() else {
+            break;
+        };
+        // a failed refill leaves the bucket to the consumers, which then
+        // refill it themselves and see the error
+        let mut id_gen = bucket.lock();
+        let _ = bucket.fill(&mut id_gen);
+    })
+    .thread()
+    .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_concurrent_bucket_across_threads() {
+        let bucket = ConcurrentIdBucket::new(1, 2).unwrap();
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let bucket = bucket.clone();
+                thread::spawn(move || bucket.get_ids(10_000))
+            })
+            .collect();
+
+        let mut seen = HashSet::new();
+        for handle in handles {
+            let ids = handle.join().unwrap();
+
+            // every thread sees its own ids in generation order
+            assert!(ids.windows(2).all(|w| w[0] < w[1]));
+            assert!(ids.into_iter().all(|id| seen.insert(id)));
+        }
+
+        assert_eq!(seen.len(), 40_000);
+    }
+
+    #[test]
+    fn test_concurrent_bucket_background_refill() {
+        let bucket = ConcurrentIdBucket::with_background_refill(1, 2, 1_024).unwrap();
+
+        // the producer fills the bucket without anyone asking for ids
+        let start = Instant::now();
+        while bucket.inner.queue.len() < 1_024 {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            thread::yield_now();
+        }
+
+        let ids = bucket.get_ids(3 * 4_096);
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+
+        assert!(bucket.try_get_id().is_ok());
+
+        let mut buf = [0; 100];
+        bucket.fill_ids(&mut buf);
+        assert!(ids[ids.len() - 1] < buf[0]);
+        assert!(buf.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_concurrent_bucket_overflow() {
+        // an epoch so far back that the timestamp has already overflowed
+        let epoch = std::time::SystemTime::UNIX_EPOCH - Duration::from_secs(20 * 365 * 86_400);
+        let id_gen = IdGenerator::builder().epoch(epoch).build().unwrap();
+        let bucket = ConcurrentIdBucket::with_config(id_gen, BucketConfig::default()).unwrap();
+
+        assert_eq!(bucket.wait_for_id(), Err(IdError::TimestampOverflow));
+        assert_eq!(bucket.try_get_id(), Err(IdError::TimestampOverflow));
+    }
+
+    #[test]
+    fn test_concurrent_bucket_rejects_invalid_config() {
+        let config = BucketConfig {
+            refill_size: 0,
+            ..BucketConfig::default()
+        };
+
+        assert_eq!(
+            ConcurrentIdBucket::with_config(IdGenerator::new(1, 2).unwrap(), config).unwrap_err(),
+            IdError::InvalidBucketConfig("refill_size must be between 1 and capacity")
+        );
+    }
+}
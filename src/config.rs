@@ -0,0 +1,297 @@
+use crate::{
+    IdError, IdGenerator, IdGeneratorBucket, Layout, WaitStrategy, MAX_IDS_PER_MILLISECOND,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
+#[cfg(feature = "config")]
+use std::{fmt, fs, io, path::Path};
+
+/// Plain settings for an [`IdGenerator`], suitable for storing alongside the
+/// rest of an application's configuration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct GeneratorConfig {
+    /// epoch as milliseconds since the unix epoch, 0 for the layout's own epoch
+    pub epoch_ms: u64,
+    pub machine_id: i32,
+    pub server_id: i32,
+    /// sequence the generator starts counting from
+    pub sequence: usize,
+    pub layout: LayoutPreset,
+    pub wait_strategy: WaitStrategy,
+    /// sizing for [`GeneratorConfig::build_bucket`]
+    pub bucket: BucketConfig,
+}
+
+impl GeneratorConfig {
+    pub fn build(&self) -> Result<IdGenerator, IdError> {
+        let mut builder = IdGenerator::builder()
+            .layout(self.layout.layout())
+            .wait_strategy(self.wait_strategy)
+            .machine_id(self.machine_id)
+            .server_id(self.server_id)
+            .sequence(self.sequence);
+
+        if self.epoch_ms != 0 {
+            builder = builder.epoch(SystemTime::UNIX_EPOCH + Duration::from_millis(self.epoch_ms));
+        }
+
+        builder.build()
+    }
+
+    /// build a bucket sized by `bucket` around the generator of this config
+    pub fn build_bucket(&self) -> Result<IdGeneratorBucket, IdError> {
+        IdGeneratorBucket::with_config(self.build()?, self.bucket)
+    }
+
+    /// read the config from a TOML file
+    ///
+    /// ```toml
+    /// layout = "sonyflake"
+    /// machine_id = 3
+    /// wait_strategy = { sleep_micros = 100 }
+    ///
+    /// [bucket]
+    /// capacity = 8192
+    /// max_drift_ms = 5
+    /// ```
+    #[cfg(feature = "config")]
+    pub fn from_toml(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let s = fs::read_to_string(path).map_err(ConfigError::Io)?;
+        Self::from_toml_str(&s)
+    }
+
+    /// parse the config from TOML, see [`GeneratorConfig::from_toml`]
+    #[cfg(feature = "config")]
+    pub fn from_toml_str(s: &str) -> Result<Self, ConfigError> {
+        toml::from_str(s).map_err(ConfigError::Parse)
+    }
+}
+
+/// The layout presets a [`GeneratorConfig`] can name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum LayoutPreset {
+    /// [`Layout::default`]
+    #[default]
+    Default,
+    /// [`Layout::sonyflake`]
+    Sonyflake,
+    /// [`Layout::twitter`]
+    Twitter,
+    /// [`Layout::multi_datacenter`]
+    MultiDatacenter,
+    /// [`Layout::discord`]
+    Discord,
+    /// [`Layout::microsecond`]
+    Microsecond,
+    /// [`Layout::js_safe`]
+    JsSafe,
+}
+
+impl LayoutPreset {
+    /// the preset a config file names `name`, e.g. `"discord"`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(LayoutPreset::Default),
+            "sonyflake" => Some(LayoutPreset::Sonyflake),
+            "twitter" => Some(LayoutPreset::Twitter),
+            "multi_datacenter" => Some(LayoutPreset::MultiDatacenter),
+            "discord" => Some(LayoutPreset::Discord),
+            "microsecond" => Some(LayoutPreset::Microsecond),
+            "js_safe" => Some(LayoutPreset::JsSafe),
+            _ => None,
+        }
+    }
+
+    pub fn layout(self) -> Layout {
+        match self {
+            LayoutPreset::Default => Layout::default(),
+            LayoutPreset::Sonyflake => Layout::sonyflake(),
+            LayoutPreset::Twitter => Layout::twitter(),
+            LayoutPreset::MultiDatacenter => Layout::multi_datacenter(),
+            LayoutPreset::Discord => Layout::discord(),
+            LayoutPreset::Microsecond => Layout::microsecond(),
+            LayoutPreset::JsSafe => Layout::js_safe(),
+        }
+    }
+}
+
+/// Errors returned when a config file can't be read.
+#[cfg(feature = "config")]
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+}
+
+#[cfg(feature = "config")]
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "can't read config: {}", e),
+            ConfigError::Parse(e) => write!(f, "invalid config: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "config")]
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io(e) => Some(e),
+            ConfigError::Parse(e) => Some(e),
+        }
+    }
+}
+
+/// Sizing for an [`IdGeneratorBucket`](crate::IdGeneratorBucket).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct BucketConfig {
+    /// most ids the bucket holds at once
+    pub capacity: usize,
+    /// ids generated per refill, never more than there is room for
+    pub refill_size: usize,
+    /// how far lazy generation may run ahead of the wall clock before it
+    /// waits for the clock to catch up, unbounded if `None`
+    pub max_drift_ms: Option<u64>,
+    /// refill on a background thread once fewer ids than this are left,
+    /// instead of refilling inline when the bucket is empty
+    pub background_low_watermark: Option<usize>,
+}
+
+impl Default for BucketConfig {
+    fn default() -> Self {
+        Self {
+            capacity: MAX_IDS_PER_MILLISECOND,
+            refill_size: MAX_IDS_PER_MILLISECOND,
+            max_drift_ms: None,
+            background_low_watermark: None,
+        }
+    }
+}
+
+impl BucketConfig {
+    pub(crate) fn validate(&self) -> Result<(), IdError> {
+        if self.refill_size == 0 || self.refill_size > self.capacity {
+            return Err(IdError::InvalidBucketConfig(
+                "refill_size must be between 1 and capacity",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_build() {
+        let config = GeneratorConfig {
+            epoch_ms: 1_577_836_800_000,
+            machine_id: 4,
+            server_id: 5,
+            ..GeneratorConfig::default()
+        };
+
+        let id = config.build().unwrap().generate_id();
+
+        assert_eq!(crate::decode(id).machine_id, 4);
+        assert_eq!(
+            GeneratorConfig {
+                machine_id: 99,
+                ..config
+            }
+            .build()
+            .unwrap_err(),
+            IdError::InvalidMachineId(99)
+        );
+    }
+
+    #[test]
+    fn test_bucket_config_validate() {
+        assert!(BucketConfig::default().validate().is_ok());
+        assert!(BucketConfig {
+            refill_size: 0,
+            ..BucketConfig::default()
+        }
+        .validate()
+        .is_err());
+        assert!(BucketConfig {
+            capacity: 10,
+            refill_size: 11,
+            ..BucketConfig::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_config_layout_keeps_preset_epoch() {
+        let config = GeneratorConfig {
+            layout: LayoutPreset::Sonyflake,
+            ..GeneratorConfig::default()
+        };
+
+        assert_eq!(config.build().unwrap().layout(), &Layout::sonyflake());
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_config_from_toml() {
+        let config = GeneratorConfig::from_toml_str(
+            r#"
+            layout = "discord"
+            epoch_ms = 1577836800000
+            machine_id = 3
+            wait_strategy = { sleep_micros = 100 }
+
+            [bucket]
+            capacity = 8192
+            max_drift_ms = 5
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.layout, LayoutPreset::Discord);
+        assert_eq!(config.epoch_ms, 1_577_836_800_000);
+        assert_eq!(config.machine_id, 3);
+        assert_eq!(config.wait_strategy, WaitStrategy::SleepMicros(100));
+        assert_eq!(config.bucket.capacity, 8192);
+        assert_eq!(config.bucket.refill_size, MAX_IDS_PER_MILLISECOND);
+        assert_eq!(config.bucket.max_drift_ms, Some(5));
+        assert!(config.build_bucket().unwrap().get_id() > 0);
+
+        assert!(matches!(
+            GeneratorConfig::from_toml_str("layout = \"mongo\""),
+            Err(ConfigError::Parse(_))
+        ));
+        assert!(matches!(
+            GeneratorConfig::from_toml("/nonexistent/uniqueid.toml"),
+            Err(ConfigError::Io(_))
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_config_serde() {
+        let config: GeneratorConfig =
+            serde_json::from_str(r#"{"epoch_ms":1577836800000,"machine_id":3}"#).unwrap();
+
+        assert_eq!(config.epoch_ms, 1_577_836_800_000);
+        assert_eq!(config.machine_id, 3);
+        assert_eq!(config.server_id, 0);
+        assert_eq!(
+            serde_json::from_str::<GeneratorConfig>(&serde_json::to_string(&config).unwrap())
+                .unwrap(),
+            config
+        );
+    }
+}
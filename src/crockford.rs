@@ -0,0 +1,141 @@
+use crate::IdError;
+
+pub const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+// extra symbols only used for the mod 37 check symbol
+const CHECK_ALPHABET: &[u8; 37] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ*~$=U";
+
+/// encode without padding, most significant digit first
+pub fn encode(value: u64) -> String {
+    let digits = (64 - value.leading_zeros()).div_ceil(5).max(1);
+
+    (0..digits)
+        .rev()
+        .map(|i| ALPHABET[((value >> (i * 5)) & 0x1f) as usize] as char)
+        .collect()
+}
+
+/// number of symbols needed for any `u64`
+pub const WIDTH: usize = 13;
+
+/// encode zero-padded to [`WIDTH`] symbols, so that encoded values sort like the values
+pub fn encode_padded(value: u64) -> String {
+    (0..WIDTH as u32)
+        .rev()
+        .map(|i| ALPHABET[((value >> (i * 5)) & 0x1f) as usize] as char)
+        .collect()
+}
+
+/// decode a string produced by [`encode_padded`], rejecting any other width
+/// and non-canonical symbols, which would sort differently
+pub fn decode_padded(s: &str) -> Result<u64, IdError> {
+    if s.len() != WIDTH || !s.bytes().all(|b| ALPHABET.contains(&b)) {
+        return Err(IdError::InvalidIdString);
+    }
+
+    decode(s)
+}
+
+pub fn encode_with_check(value: u64) -> String {
+    let mut encoded = encode(value);
+    encoded.push(CHECK_ALPHABET[(value % 37) as usize] as char);
+    encoded
+}
+
+/// decode case-insensitively, reading `I`/`L` as 1 and `O` as 0 and
+/// skipping `-` separators
+pub fn decode(s: &str) -> Result<u64, IdError> {
+    let mut value: u64 = 0;
+    let mut digits = 0;
+
+    for b in s.bytes().filter(|&b| b != b'-') {
+        let digit = decode_symbol(b)
+            .filter(|&d| d < 32)
+            .ok_or(IdError::InvalidIdString)?;
+
+        if value >> 59 != 0 {
+            return Err(IdError::InvalidIdString);
+        }
+
+        value = value << 5 | digit as u64;
+        digits += 1;
+    }
+
+    if digits == 0 {
+        return Err(IdError::InvalidIdString);
+    }
+
+    Ok(value)
+}
+
+/// decode a string whose last symbol is the check symbol
+pub fn decode_with_check(s: &str) -> Result<u64, IdError> {
+    let s = s.trim_end_matches('-');
+    let (body, check) = match s.char_indices().last() {
+        Some((i, _)) => s.split_at(i),
+        None => return Err(IdError::InvalidIdString),
+    };
+
+    let value = decode(body)?;
+    let check = check
+        .bytes()
+        .next()
+        .and_then(decode_symbol)
+        .ok_or(IdError::InvalidIdString)?;
+
+    if value % 37 != check as u64 {
+        return Err(IdError::ChecksumMismatch);
+    }
+
+    Ok(value)
+}
+
+pub fn decode_symbol(b: u8) -> Option<u8> {
+    let b = b.to_ascii_uppercase();
+
+    match b {
+        b'O' => Some(0),
+        b'I' | b'L' => Some(1),
+        _ => CHECK_ALPHABET
+            .iter()
+            .position(|&c| c == b)
+            .map(|pos| pos as u8),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crockford_round_trip() {
+        for value in [0, 1, 31, 32, 1 << 40, i64::MAX as u64, u64::MAX] {
+            assert_eq!(decode(&encode(value)), Ok(value));
+            assert_eq!(decode_with_check(&encode_with_check(value)), Ok(value));
+        }
+
+        assert_eq!(encode(0), "0");
+        assert_eq!(encode(32), "10");
+        assert_eq!(encode(u64::MAX), "FZZZZZZZZZZZZ");
+    }
+
+    #[test]
+    fn test_crockford_lenient_decode() {
+        assert_eq!(decode("1O"), decode("10"));
+        assert_eq!(decode("il"), Ok(33));
+        assert_eq!(decode("3f-zz"), decode("3FZZ"));
+    }
+
+    #[test]
+    fn test_crockford_check_symbol() {
+        let encoded = encode_with_check(1_234_567_890);
+
+        assert_eq!(encoded, "14SC0PJV");
+        assert_eq!(
+            decode_with_check("14SC0PJ0"),
+            Err(IdError::ChecksumMismatch)
+        );
+        // check symbols are not valid in the body
+        assert_eq!(decode("14SC*PJ"), Err(IdError::InvalidIdString));
+        assert_eq!(decode("GZZZZZZZZZZZZ"), Err(IdError::InvalidIdString));
+    }
+}
@@ -0,0 +1,356 @@
+//! Serving the IDs of one generator to every process on a host over a Unix
+//! domain socket.
+//!
+//! A request is a 4-byte big-endian count of IDs. The daemon answers with a
+//! 4-byte big-endian length followed by that many bytes: a status byte, then
+//! either the IDs as 8-byte big-endian integers or, if the daemon couldn't
+//! generate them, a UTF-8 error message. Counts range from 1 to
+//! [`MAX_BATCH`].
+
+use crate::{Id, IdError, IdGenerator, IdSource, SharedIdGenerator};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+/// the most IDs one request can ask for
+pub const MAX_BATCH: u32 = 1 << 16;
+
+const STATUS_OK: u8 = 0;
+const STATUS_ERROR: u8 = 1;
+
+/// Serves the IDs of one [`IdGenerator`] on a Unix domain socket, see the
+/// [module docs](self) for the protocol.
+///
+/// Every connection is served by a thread of its own, and all of them draw
+/// from the same generator, so the IDs are unique across every client.
+#[derive(Debug)]
+pub struct IdDaemon {
+    listener: UnixListener,
+    id_gen: SharedIdGenerator,
+}
+
+impl IdDaemon {
+    /// listen on `path`, replacing a socket left behind by a daemon that is
+    /// no longer running
+    pub fn bind(path: impl AsRef<Path>, id_gen: IdGenerator) -> io::Result<Self> {
+        let path = path.as_ref();
+
+        let stale = fs::metadata(path).is_ok_and(|meta| meta.file_type().is_socket())
+            && UnixStream::connect(path).is_err();
+        if stale {
+            fs::remove_file(path)?;
+        }
+
+        Ok(Self {
+            listener: UnixListener::bind(path)?,
+            id_gen: SharedIdGenerator::from(id_gen),
+        })
+    }
+
+    /// accept and serve connections until accepting fails
+    pub fn run(self) -> io::Result<()> {
+        loop {
+            let (stream, _) = self.listener.accept()?;
+            let id_gen = self.id_gen.clone();
+
+            // a client hanging up mid-request only ends its own connection
+            thread::spawn(move || serve(stream, &id_gen));
+        }
+    }
+}
+
+fn serve(mut stream: UnixStream, id_gen: &SharedIdGenerator) -> io::Result<()> {
+    let mut count = [0; 4];
+
+    loop {
+        match stream.read_exact(&mut count) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e),
+        }
+
+        stream.write_all(&response(id_gen, u32::from_be_bytes(count)))?;
+    }
+}
+
+/// the framed answer to a request for `count` IDs
+fn response(id_gen: &SharedIdGenerator, count: u32) -> Vec<u8> {
+    let ids = match count {
+        1..=MAX_BATCH => id_gen
+            .try_reserve(count as usize)
+            .map_err(|e| e.to_string()),
+        _ => Err(format!("a batch of {} ids is out of range", count)),
+    };
+
+    let mut body = Vec::new();
+    match ids {
+        Ok(block) => {
+            body.push(STATUS_OK);
+            block
+                .iter()
+                .for_each(|id| body.extend_from_slice(&id.to_be_bytes()));
+        }
+        Err(message) => {
+            body.push(STATUS_ERROR);
+            body.extend_from_slice(message.as_bytes());
+        }
+    }
+
+    let mut frame = (body.len() as u32).to_be_bytes().to_vec();
+    frame.append(&mut body);
+    frame
+}
+
+/// the IDs of a response body, or its error message as an error
+fn parse_body(body: &[u8]) -> io::Result<Vec<i64>> {
+    match body.split_first() {
+        Some((&STATUS_OK, ids)) if ids.len() % 8 == 0 => Ok(ids
+            .chunks_exact(8)
+            .map(|id| i64::from_be_bytes(id.try_into().expect("8 bytes")))
+            .collect()),
+        Some((&STATUS_ERROR, message)) => Err(io::Error::other(
+            String::from_utf8_lossy(message).into_owned(),
+        )),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "malformed daemon response",
+        )),
+    }
+}
+
+/// A blocking client of an [`IdDaemon`].
+///
+/// After an I/O error the client drops its connection and connects to the
+/// socket again on the next request, so it recovers once a restarted daemon
+/// is back. A request the daemon doesn't answer within the
+/// [timeout](Self::timeout) fails like one it can't be reached for.
+#[derive(Debug)]
+pub struct DaemonClient {
+    path: PathBuf,
+    timeout: Duration,
+    // none after an I/O error, until the next request connects again
+    stream: Option<UnixStream>,
+}
+
+impl DaemonClient {
+    /// connect to the daemon at `path`, with a timeout of 5 seconds
+    pub fn connect(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut client = Self {
+            path: path.as_ref().to_path_buf(),
+            timeout: Duration::from_secs(5),
+            stream: None,
+        };
+
+        client.stream()?;
+        Ok(client)
+    }
+
+    /// set how long to wait for the daemon to take or answer a request
+    /// (defaults to 5 seconds)
+    pub fn timeout(mut self, timeout: Duration) -> io::Result<Self> {
+        self.timeout = timeout;
+        if let Some(stream) = &self.stream {
+            stream.set_read_timeout(Some(timeout))?;
+            stream.set_write_timeout(Some(timeout))?;
+        }
+
+        Ok(self)
+    }
+
+    pub fn next_id(&mut self) -> io::Result<i64> {
+        Ok(self.next_ids(1)?[0])
+    }
+
+    /// fetch `n` IDs in one round trip
+    pub fn next_ids(&mut self, n: u32) -> io::Result<Vec<i64>> {
+        let body = self.round_trip(n).inspect_err(|_| self.stream = None)?;
+
+        parse_body(&body)
+    }
+
+    fn round_trip(&mut self, n: u32) -> io::Result<Vec<u8>> {
+        let stream = self.stream()?;
+        stream.write_all(&n.to_be_bytes())?;
+
+        let mut len = [0; 4];
+        stream.read_exact(&mut len)?;
+        let mut body = vec![0; u32::from_be_bytes(len) as usize];
+        stream.read_exact(&mut body)?;
+
+        Ok(body)
+    }
+
+    /// the open connection, or a new one after the last one broke
+    fn stream(&mut self) -> io::Result<&mut UnixStream> {
+        if self.stream.is_none() {
+            let stream = UnixStream::connect(&self.path)?;
+            stream.set_read_timeout(Some(self.timeout))?;
+            stream.set_write_timeout(Some(self.timeout))?;
+            self.stream = Some(stream);
+        }
+
+        Ok(self.stream.as_mut().expect("connected"))
+    }
+}
+
+impl IdSource for DaemonClient {
+    type Id = Id;
+
+    /// fails with [`IdError::BackendUnavailable`] when the daemon can't be
+    /// reached; call `next_id` to see the I/O error
+    fn next(&mut self) -> Result<Id, IdError> {
+        self.next_id()
+            .map_err(|_| IdError::BackendUnavailable)
+            .and_then(Id::try_from)
+    }
+}
+
+/// A client of an [`IdDaemon`] for async code, on the tokio reactor.
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub struct AsyncDaemonClient {
+    stream: tokio::net::UnixStream,
+}
+
+#[cfg(feature = "tokio")]
+impl AsyncDaemonClient {
+    pub async fn connect(path: impl AsRef<Path>) -> io::Result<Self> {
+        tokio::net::UnixStream::connect(path)
+            .await
+            .map(|stream| Self { stream })
+    }
+
+    pub async fn next_id(&mut self) -> io::Result<i64> {
+        Ok(self.next_ids(1).await?[0])
+    }
+
+    /// fetch `n` IDs in one round trip
+    pub async fn next_ids(&mut self, n: u32) -> io::Result<Vec<i64>> {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        self.stream.write_all(&n.to_be_bytes()).await?;
+
+        let len = self.stream.read_u32().await?;
+        let mut body = vec![0; len as usize];
+        self.stream.read_exact(&mut body).await?;
+
+        parse_body(&body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    fn socket_path() -> PathBuf {
+        std::env::temp_dir().join(format!("uniqueid-{:x}.sock", Rng::new().next_u64()))
+    }
+
+    fn spawn_daemon() -> PathBuf {
+        let path = socket_path();
+        let daemon = IdDaemon::bind(&path, IdGenerator::new(1, 2).unwrap()).unwrap();
+        thread::spawn(move || daemon.run());
+        path
+    }
+
+    #[test]
+    fn test_daemon_serves_unique_ids() {
+        let path = spawn_daemon();
+
+        let mut ids: Vec<i64> = (0..4)
+            .map(|_| {
+                let path = path.clone();
+                thread::spawn(move || {
+                    let mut client = DaemonClient::connect(path).unwrap();
+                    let mut ids = client.next_ids(1_000).unwrap();
+                    ids.push(client.next_id().unwrap());
+                    ids
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect();
+
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 4_004);
+
+        let mut client = DaemonClient::connect(&path).unwrap();
+        assert!(client.next_ids(0).is_err());
+        assert!(client.next_ids(MAX_BATCH + 1).is_err());
+        // the connection survives a rejected request
+        assert!(client.next_id().is_ok());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_daemon_client_reconnects() {
+        let path = socket_path();
+        let listener = UnixListener::bind(&path).unwrap();
+        let daemon = thread::spawn(move || {
+            // the first connection breaks before it is answered
+            drop(listener.accept().unwrap());
+            let (stream, _) = listener.accept().unwrap();
+            serve(stream, &SharedIdGenerator::new(1, 2).unwrap())
+        });
+
+        let mut client = DaemonClient::connect(&path).unwrap();
+        assert!(client.next_id().is_err());
+        assert!(client.next_id().is_ok());
+
+        drop(client);
+        daemon.join().unwrap().unwrap();
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_daemon_client_times_out() {
+        let path = socket_path();
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let mut client = DaemonClient::connect(&path)
+            .unwrap()
+            .timeout(Duration::from_millis(50))
+            .unwrap();
+        // accepted, but never answered
+        let (_stream, _) = listener.accept().unwrap();
+        assert!(client.next_id().is_err());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_daemon_replaces_stale_socket() {
+        let path = socket_path();
+        drop(UnixListener::bind(&path).unwrap());
+
+        let daemon = IdDaemon::bind(&path, IdGenerator::new(1, 2).unwrap()).unwrap();
+        // a live daemon is left alone
+        assert!(IdDaemon::bind(&path, IdGenerator::new(1, 3).unwrap()).is_err());
+
+        drop(daemon);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async_daemon_client() {
+        let path = spawn_daemon();
+
+        let mut client = AsyncDaemonClient::connect(&path).await.unwrap();
+        let first = client.next_id().await.unwrap();
+        let batch = client.next_ids(10).await.unwrap();
+
+        assert_eq!(batch.len(), 10);
+        assert!(batch.iter().all(|&id| id > first));
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,63 @@
+use crate::Layout;
+
+/// The fields packed into a generated ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IdParts {
+    /// timestamp ticks since the layout's epoch, milliseconds for the default layout
+    pub timestamp: i64,
+    pub machine_id: i32,
+    pub server_id: i32,
+    pub sequence: usize,
+}
+
+impl IdParts {
+    /// the datacenter id of a [`Layout::multi_datacenter`] ID
+    pub fn datacenter_id(&self) -> i32 {
+        self.machine_id
+    }
+
+    /// the worker id of a [`Layout::multi_datacenter`] ID
+    pub fn worker_id(&self) -> i32 {
+        self.server_id
+    }
+}
+
+/// split an ID built with the default [`Layout`] back into the values it was built from
+pub fn decode(id: i64) -> IdParts {
+    Layout::default().decode(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IdGenerator;
+    use unique_id_core::{MACHINE_ID_SHIFT, SERVER_ID_SHIFT, TIMESTAMP_SHIFT};
+
+    #[test]
+    fn test_decode_round_trip() {
+        let mut id_gen = IdGenerator::new(3, 7).unwrap();
+
+        let first = decode(id_gen.generate_id());
+        let second = decode(id_gen.generate_id());
+
+        assert_eq!(first.machine_id, 3);
+        assert_eq!(first.server_id, 7);
+        assert_eq!(first.sequence + 1, second.sequence);
+        assert!(first.timestamp <= second.timestamp);
+    }
+
+    #[test]
+    fn test_decode_fields() {
+        let id = 1_000 << TIMESTAMP_SHIFT | 31 << MACHINE_ID_SHIFT | 1 << SERVER_ID_SHIFT | 4095;
+
+        assert_eq!(
+            decode(id),
+            IdParts {
+                timestamp: 1_000,
+                machine_id: 31,
+                server_id: 1,
+                sequence: 4095,
+            }
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! Diesel support for `Id` as a `BIGINT`.
+//!
+//! Reading works on every backend. Writing needs the backend feature of
+//! this crate, `diesel-postgres`, `diesel-mysql` or `diesel-sqlite`, as
+//! SQLite binds values in a different way than the other backends.
+
+use crate::Id;
+use diesel::backend::Backend;
+use diesel::deserialize::{self, FromSql};
+#[cfg(feature = "diesel-mysql")]
+use diesel::mysql::Mysql;
+#[cfg(feature = "diesel-postgres")]
+use diesel::pg::Pg;
+#[cfg(any(feature = "diesel-postgres", feature = "diesel-mysql"))]
+use diesel::query_builder::bind_collector::RawBytesBindCollector;
+#[cfg(any(
+    feature = "diesel-postgres",
+    feature = "diesel-mysql",
+    feature = "diesel-sqlite"
+))]
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::BigInt;
+#[cfg(feature = "diesel-sqlite")]
+use diesel::sqlite::Sqlite;
+
+/// write `id` as the `i64` it stands for, on backends that serialize binds
+/// to bytes right away
+#[cfg(any(feature = "diesel-postgres", feature = "diesel-mysql"))]
+fn write_bigint<DB>(id: &Id, out: &mut Output<'_, '_, DB>) -> serialize::Result
+where
+    for<'c> DB: Backend<BindCollector<'c> = RawBytesBindCollector<DB>>,
+    i64: ToSql<BigInt, DB>,
+{
+    let value = i64::try_from(*id)?;
+    <i64 as ToSql<BigInt, DB>>::to_sql(&value, &mut out.reborrow())
+}
+
+/// `Id` is stored as a `BIGINT`; IDs that don't fit a non-negative `i64`
+/// are rejected rather than wrapped.
+#[cfg(feature = "diesel-postgres")]
+impl ToSql<BigInt, Pg> for Id {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        write_bigint(self, out)
+    }
+}
+
+#[cfg(feature = "diesel-mysql")]
+impl ToSql<BigInt, Mysql> for Id {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Mysql>) -> serialize::Result {
+        write_bigint(self, out)
+    }
+}
+
+// SQLite keeps the bound values instead of their bytes, so it takes the
+// `i64` by value
+#[cfg(feature = "diesel-sqlite")]
+impl ToSql<BigInt, Sqlite> for Id {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Sqlite>) -> serialize::Result {
+        out.set_value(i64::try_from(*self)?);
+        Ok(serialize::IsNull::No)
+    }
+}
+
+impl<DB> FromSql<BigInt, DB> for Id
+where
+    DB: Backend,
+    i64: FromSql<BigInt, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+        let value = <i64 as FromSql<BigInt, DB>>::from_sql(bytes)?;
+        Ok(Id::try_from(value)?)
+    }
+}
+
+#[cfg(all(test, feature = "diesel-sqlite"))]
+mod tests {
+    use crate::{Id, IdGenerator};
+    use diesel::connection::SimpleConnection;
+    use diesel::prelude::*;
+    use diesel::sqlite::SqliteConnection;
+
+    diesel::table! {
+        events (id) {
+            id -> BigInt,
+        }
+    }
+
+    #[derive(Debug, PartialEq, Queryable, Insertable)]
+    #[diesel(table_name = events)]
+    struct Event {
+        id: Id,
+    }
+
+    #[test]
+    fn test_diesel_round_trip() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.batch_execute("CREATE TABLE events (id BIGINT PRIMARY KEY NOT NULL)")
+            .unwrap();
+
+        let id = IdGenerator::new(1, 2).unwrap().iter().next().unwrap();
+        diesel::insert_into(events::table)
+            .values(&Event { id })
+            .execute(&mut conn)
+            .unwrap();
+
+        let stored: Vec<Event> = events::table.load(&mut conn).unwrap();
+        assert_eq!(stored, vec![Event { id }]);
+
+        let too_large = Event {
+            id: Id::from(u64::MAX),
+        };
+        assert!(diesel::insert_into(events::table)
+            .values(&too_large)
+            .execute(&mut conn)
+            .is_err());
+    }
+}
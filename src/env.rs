@@ -0,0 +1,138 @@
+use crate::{GeneratorConfig, IdError, IdGenerator};
+use std::fmt;
+use std::str::FromStr;
+
+const MACHINE_ID_VAR: &str = "UNIQUEID_MACHINE_ID";
+const SERVER_ID_VAR: &str = "UNIQUEID_SERVER_ID";
+const EPOCH_MS_VAR: &str = "UNIQUEID_EPOCH_MS";
+
+/// Errors returned when a generator can't be configured from the environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvError {
+    /// The variable is set but its value isn't an integer of the right type.
+    Invalid { var: &'static str, value: String },
+    /// The values parse but don't make a valid generator.
+    Config(IdError),
+}
+
+impl fmt::Display for EnvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvError::Invalid { var, value } => {
+                write!(f, "{}={:?} is not a valid integer", var, value)
+            }
+            EnvError::Config(e) => write!(f, "invalid generator configuration: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for EnvError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EnvError::Invalid { .. } => None,
+            EnvError::Config(e) => Some(e),
+        }
+    }
+}
+
+impl From<IdError> for EnvError {
+    fn from(e: IdError) -> Self {
+        EnvError::Config(e)
+    }
+}
+
+impl GeneratorConfig {
+    /// read the config from `UNIQUEID_MACHINE_ID`, `UNIQUEID_SERVER_ID` and
+    /// `UNIQUEID_EPOCH_MS`, using the defaults for unset variables
+    pub fn from_env() -> Result<Self, EnvError> {
+        Self::from_vars(|var| std::env::var_os(var).map(|v| v.to_string_lossy().into_owned()))
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, EnvError> {
+        let defaults = GeneratorConfig::default();
+
+        Ok(GeneratorConfig {
+            machine_id: parse(&var, MACHINE_ID_VAR)?.unwrap_or(defaults.machine_id),
+            server_id: parse(&var, SERVER_ID_VAR)?.unwrap_or(defaults.server_id),
+            epoch_ms: parse(&var, EPOCH_MS_VAR)?.unwrap_or(defaults.epoch_ms),
+            ..defaults
+        })
+    }
+}
+
+impl IdGenerator {
+    /// create a generator configured by the environment, see
+    /// [`GeneratorConfig::from_env`]
+    pub fn from_env() -> Result<Self, EnvError> {
+        Ok(GeneratorConfig::from_env()?.build()?)
+    }
+}
+
+fn parse<T: FromStr>(
+    var: impl Fn(&str) -> Option<String>,
+    name: &'static str,
+) -> Result<Option<T>, EnvError> {
+    match var(name) {
+        None => Ok(None),
+        Some(value) => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| EnvError::Invalid { var: name, value }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let pairs: Vec<(String, String)> = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+
+        move |var| pairs.iter().find(|(k, _)| k == var).map(|(_, v)| v.clone())
+    }
+
+    #[test]
+    fn test_config_from_vars() {
+        let config = GeneratorConfig::from_vars(vars(&[
+            (MACHINE_ID_VAR, "3"),
+            (SERVER_ID_VAR, " 4 "),
+            (EPOCH_MS_VAR, "1577836800000"),
+        ]))
+        .unwrap();
+
+        assert_eq!(config.machine_id, 3);
+        assert_eq!(config.server_id, 4);
+        assert_eq!(config.epoch_ms, 1_577_836_800_000);
+        assert_eq!(
+            GeneratorConfig::from_vars(vars(&[])).unwrap(),
+            GeneratorConfig::default()
+        );
+    }
+
+    #[test]
+    fn test_config_from_vars_errors() {
+        let err = GeneratorConfig::from_vars(vars(&[(EPOCH_MS_VAR, "-1")])).unwrap_err();
+
+        assert_eq!(
+            err,
+            EnvError::Invalid {
+                var: EPOCH_MS_VAR,
+                value: "-1".to_string()
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            r#"UNIQUEID_EPOCH_MS="-1" is not a valid integer"#
+        );
+
+        let config = GeneratorConfig::from_vars(vars(&[(MACHINE_ID_VAR, "99")])).unwrap();
+        assert_eq!(
+            EnvError::from(config.build().unwrap_err()).to_string(),
+            "invalid generator configuration: machine id 99 is out of range"
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use std::fmt;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::time::Duration;
+
+/// Something an [`IdGenerator`](crate::IdGenerator) ran into that an
+/// application may want to log or alert on, see
+/// [`IdGeneratorBuilder::on_event`](crate::IdGeneratorBuilder::on_event).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeneratorEvent {
+    /// Every sequence number of the tick `timestamp` has been issued.
+    SequenceRollover { timestamp: i64 },
+    /// The generator started waiting for the clock to move past `timestamp`.
+    WaitStarted { timestamp: i64 },
+    /// The wait for the clock is over after `waited`.
+    WaitEnded { waited: Duration },
+    /// The clock read `ticks` earlier than the last issued timestamp.
+    ClockRollback { ticks: i64 },
+    /// Lazy generation would have run `drift` ticks ahead of the clock, more
+    /// than the max drift allows.
+    DriftExceeded { drift: i64 },
+}
+
+/// Receives the [`GeneratorEvent`]s of a generator.
+///
+/// Implemented by every `Fn(GeneratorEvent)`. The listener is called on the
+/// thread generating the ID, so it should return quickly and must not
+/// generate IDs from the same generator.
+pub trait EventListener: Send + Sync {
+    fn on_event(&self, event: GeneratorEvent);
+}
+
+impl<F: Fn(GeneratorEvent) + Send + Sync> EventListener for F {
+    fn on_event(&self, event: GeneratorEvent) {
+        self(event)
+    }
+}
+
+/// The listener of a generator, a `'static` reference so builders stay
+/// `Copy`.
+#[derive(Clone, Copy)]
+pub(crate) struct Listener(pub &'static dyn EventListener);
+
+impl fmt::Debug for Listener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Listener").finish_non_exhaustive()
+    }
+}
+
+// generators stay unwind safe with a listener: it is only called where the
+// generator state is consistent, so a panicking listener can't break it
+impl UnwindSafe for Listener {}
+impl RefUnwindSafe for Listener {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::{DriftPolicy, IdGenerator, RollbackPolicy, MAX_IDS_PER_MILLISECOND};
+    use std::sync::Mutex;
+    use std::thread;
+
+    static EVENTS: Mutex<Vec<GeneratorEvent>> = Mutex::new(Vec::new());
+
+    #[test]
+    fn test_generator_events() {
+        let clock = MockClock::new(1_000);
+        let mut id_gen = IdGenerator::builder()
+            .rollback_policy(RollbackPolicy::Error)
+            .max_drift(Duration::from_millis(1))
+            .drift_policy(DriftPolicy::Error)
+            .on_event(&|event| EVENTS.lock().unwrap().push(event))
+            .build_with_clock(clock.clone())
+            .unwrap();
+
+        // the sequence of 1000 runs out and the generator waits for 1001
+        let advance = {
+            let clock = clock.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(20));
+                clock.set_millis(1_001);
+            })
+        };
+        id_gen.generate_ids(MAX_IDS_PER_MILLISECOND);
+        advance.join().unwrap();
+
+        clock.set_millis(999);
+        assert!(id_gen.try_generate_id_by_time().is_err());
+
+        // 1002 is within the max drift of 1001, 1003 isn't
+        clock.set_millis(1_001);
+        while id_gen.try_generate_id_lazy().is_ok() {}
+
+        let events = EVENTS.lock().unwrap();
+        assert!(
+            matches!(
+                events[..],
+                [
+                    GeneratorEvent::SequenceRollover { timestamp: 1_000 },
+                    GeneratorEvent::WaitStarted { timestamp: 1_000 },
+                    GeneratorEvent::WaitEnded { waited },
+                    GeneratorEvent::ClockRollback { ticks: 2 },
+                    GeneratorEvent::SequenceRollover { timestamp: 1_001 },
+                    GeneratorEvent::DriftExceeded { drift: 2 },
+                ] if waited >= Duration::from_millis(10)
+            ),
+            "{:?}",
+            events
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{Id, IdParts, Layout};
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+/// A breakdown of an [`Id`] into its fields, created by [`Id::explain`].
+///
+/// The `Display` form lists the fields one per line, with the timestamp as a
+/// UTC date and the raw bits split at the field boundaries:
+///
+/// ```text
+/// id:        7147375873229221889
+/// timestamp: 2024-01-01T00:00:00.000Z (1704067200000)
+/// machine:   3
+/// server:    7
+/// sequence:  1
+/// bits:      011000110011000010010100011111010000000000 00011 00111 000000000001
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdExplanation {
+    pub id: Id,
+    pub parts: IdParts,
+    /// the time the ID was generated
+    pub created_at: SystemTime,
+    layout: Layout,
+}
+
+impl IdExplanation {
+    pub(crate) fn new(id: Id, layout: &Layout) -> Self {
+        let parts = layout.decode(id.as_u64() as i64);
+
+        Self {
+            id,
+            parts,
+            created_at: layout.created_at(id.as_u64() as i64),
+            layout: *layout,
+        }
+    }
+
+    /// the 64 bits of the ID, with a space wherever a field starts or ends
+    pub fn bits(&self) -> String {
+        let mut bits = String::with_capacity(80);
+
+        for bit in (0..u64::BITS).rev() {
+            bits.push(if self.id.as_u64() >> bit & 1 == 1 {
+                '1'
+            } else {
+                '0'
+            });
+
+            if bit > 0 && self.layout.boundaries().any(|b| b == bit) {
+                bits.push(' ');
+            }
+        }
+
+        bits
+    }
+}
+
+impl fmt::Display for IdExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precise = self.layout.tick() < Duration::from_millis(1);
+
+        writeln!(f, "id:        {}", self.id)?;
+        writeln!(
+            f,
+            "timestamp: {} ({})",
+            Utc(self.created_at, precise),
+            self.parts.timestamp
+        )?;
+        writeln!(f, "machine:   {}", self.parts.machine_id)?;
+        writeln!(f, "server:    {}", self.parts.server_id)?;
+        writeln!(f, "sequence:  {}", self.parts.sequence)?;
+        write!(f, "bits:      {}", self.bits())
+    }
+}
+
+/// RFC 3339 form of a time, in microseconds if `.1` is set, milliseconds otherwise
+struct Utc(SystemTime, bool);
+
+impl fmt::Display for Utc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let since_epoch = self
+            .0
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        let secs = since_epoch.as_secs();
+        let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+        let time = secs % 86_400;
+
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            time / 3_600,
+            time % 3_600 / 60,
+            time % 60
+        )?;
+
+        if self.1 {
+            write!(f, ".{:06}Z", since_epoch.subsec_micros())
+        } else {
+            write!(f, ".{:03}Z", since_epoch.subsec_millis())
+        }
+    }
+}
+
+/// the proleptic Gregorian date `days` after 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_723), (2024, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+    }
+
+    #[test]
+    fn test_explain() {
+        // 2024-01-01T00:00:00.123Z
+        let id = Id::from(1_704_067_200_123_u64 << 22 | 3 << 17 | 7 << 12 | 1);
+        let explained = id.explain(&Layout::default());
+
+        assert_eq!(explained.parts.machine_id, 3);
+        assert_eq!(
+            explained.to_string(),
+            format!(
+                "id:        {}\n\
+                 timestamp: 2024-01-01T00:00:00.123Z (1704067200123)\n\
+                 machine:   3\n\
+                 server:    7\n\
+                 sequence:  1\n\
+                 bits:      {:042b} 00011 00111 000000000001",
+                id, 1_704_067_200_123_u64
+            )
+        );
+    }
+
+    #[test]
+    fn test_explain_bits_mark_unused_bits() {
+        let explained = Id::from(1).explain(&Layout::js_safe());
+
+        // 11 unused bits, then timestamp, machine and sequence
+        assert_eq!(
+            explained.bits(),
+            format!("{} {} {} {:08b}", "0".repeat(11), "0".repeat(41), "0000", 1)
+        );
+    }
+}
@@ -0,0 +1,94 @@
+//! C bindings, see `include/uniqueid.h`.
+//!
+//! Build the shared or static library with
+//! `cargo rustc --release --features ffi --crate-type cdylib` (or
+//! `staticlib`), so crates depending on this one don't build C artifacts.
+//!
+//! A generator is an opaque pointer owned by the caller: create it with
+//! [`uniqueid_new`] and release it with [`uniqueid_free`]. A generator must
+//! not be used from two threads at once. Errors are returned as null or -1,
+//! and no panic ever unwinds into the caller.
+
+use crate::IdGenerator;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// create a generator, or return null if an id doesn't fit its bit field
+#[no_mangle]
+pub extern "C" fn uniqueid_new(machine_id: i32, server_id: i32) -> *mut IdGenerator {
+    match catch(|| IdGenerator::new(machine_id, server_id).ok()) {
+        Some(id_gen) => Box::into_raw(Box::new(id_gen)),
+        None => ptr::null_mut(),
+    }
+}
+
+/// generate an ID, or return -1 if `id_gen` is null or no ID can be
+/// generated, e.g. once the timestamp overflows the layout
+///
+/// # Safety
+///
+/// `id_gen` must be null or a pointer returned by [`uniqueid_new`] that
+/// hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn uniqueid_generate(id_gen: *mut IdGenerator) -> i64 {
+    match id_gen.as_mut() {
+        Some(id_gen) => catch(|| id_gen.wait_for_id().ok()).unwrap_or(-1),
+        None => -1,
+    }
+}
+
+/// run `f`, turning a panic into `None`, since unwinding out of an
+/// `extern "C"` function aborts the process
+fn catch<T>(f: impl FnOnce() -> Option<T>) -> Option<T> {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(None)
+}
+
+/// release a generator; null is ignored
+///
+/// # Safety
+///
+/// `id_gen` must be null or a pointer returned by [`uniqueid_new`] that
+/// hasn't been freed yet. It must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn uniqueid_free(id_gen: *mut IdGenerator) {
+    if !id_gen.is_null() {
+        drop(Box::from_raw(id_gen));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ffi_round_trip() {
+        let id_gen = uniqueid_new(1, 2);
+        assert!(!id_gen.is_null());
+
+        unsafe {
+            let first = uniqueid_generate(id_gen);
+            let second = uniqueid_generate(id_gen);
+            assert!(0 < first && first < second);
+
+            uniqueid_free(id_gen);
+            assert_eq!(uniqueid_generate(ptr::null_mut()), -1);
+            uniqueid_free(ptr::null_mut());
+        }
+
+        assert!(uniqueid_new(32, 0).is_null());
+    }
+
+    #[test]
+    fn test_ffi_generate_fails_without_unwinding() {
+        // an epoch so far back that the timestamp has already overflowed
+        let epoch =
+            std::time::SystemTime::UNIX_EPOCH - std::time::Duration::from_secs(20 * 365 * 86_400);
+        let id_gen = IdGenerator::builder().epoch(epoch).build().unwrap();
+        let id_gen = Box::into_raw(Box::new(id_gen));
+
+        unsafe {
+            assert_eq!(uniqueid_generate(id_gen), -1);
+            uniqueid_free(id_gen);
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! A process-wide generator for applications that don't want to pass a
+//! generator around.
+//!
+//! ```no_run
+//! use unique_id::{global, GeneratorConfig};
+//!
+//! global::init(GeneratorConfig { machine_id: 3, ..Default::default() }).unwrap();
+//!
+//! let id = global::next();
+//! ```
+
+use crate::{GeneratorConfig, IdError, SharedIdGenerator};
+use std::sync::OnceLock;
+
+static GLOBAL: OnceLock<SharedIdGenerator> = OnceLock::new();
+
+/// set up the global generator from `config`, once per process
+///
+/// Returns [`IdError::AlreadyInitialized`] on every call after the first
+/// successful one, leaving the generator unchanged.
+pub fn init(config: GeneratorConfig) -> Result<(), IdError> {
+    let id_gen = SharedIdGenerator::from(config.build()?);
+
+    GLOBAL.set(id_gen).map_err(|_| IdError::AlreadyInitialized)
+}
+
+/// a handle to the global generator, if [`init`] was called
+pub fn get() -> Option<&'static SharedIdGenerator> {
+    GLOBAL.get()
+}
+
+/// generate an ID with the global generator
+///
+/// # Panics
+///
+/// Panics if [`init`] wasn't called before, or where
+/// [`IdGenerator::generate_id`](crate::IdGenerator::generate_id) does, use
+/// [`try_next`] to handle those cases.
+pub fn next() -> i64 {
+    try_next().unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// generate an ID with the global generator, or return
+/// [`IdError::NotInitialized`] if [`init`] wasn't called before
+///
+/// Fails like [`SharedIdGenerator::try_generate_id`] otherwise.
+pub fn try_next() -> Result<i64, IdError> {
+    get().ok_or(IdError::NotInitialized)?.try_generate_id()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode;
+
+    // the only test touching the global generator, as tests share the process
+    #[test]
+    fn test_global_generator() {
+        assert!(get().is_none());
+        assert!(std::panic::catch_unwind(next).is_err());
+        assert_eq!(try_next(), Err(IdError::NotInitialized));
+
+        let config = GeneratorConfig {
+            machine_id: 9,
+            server_id: 4,
+            ..Default::default()
+        };
+        assert_eq!(
+            init(GeneratorConfig {
+                machine_id: 99,
+                ..config
+            }),
+            Err(IdError::InvalidMachineId(99))
+        );
+        init(config).unwrap();
+        assert_eq!(init(config), Err(IdError::AlreadyInitialized));
+
+        let (a, b) = (next(), next());
+        assert!(a < b);
+        assert_eq!(decode(a).machine_id, 9);
+        assert_eq!(decode(b).server_id, 4);
+    }
+}
@@ -0,0 +1,216 @@
+use crate::clock::LayoutClock;
+use crate::{Clock, IdError, Layout, SystemClock};
+use std::time::Duration;
+
+/// Generates IDs from a hybrid logical clock, so an ID generated after
+/// [`observe`](Self::observe)-ing another node's ID always sorts after it,
+/// whatever the clocks of both nodes read.
+///
+/// The timestamp and sequence fields of [`Layout::hybrid_logical`] together
+/// hold an HLC: the timestamp is the largest physical time seen so far and
+/// the sequence a logical counter within it, so decode the IDs with that
+/// layout. A generator never waits for its
+/// clock. Once the counter of a millisecond runs out, or after observing an
+/// ID from a node whose clock is ahead, it dates IDs ahead of its own clock
+/// until the clock catches up.
+///
+/// A generator isn't `Clone`, as a copy would issue the same IDs. Give each
+/// one a machine and server id of its own instead.
+#[derive(Debug)]
+pub struct HlcIdGenerator<C = SystemClock> {
+    clock: C,
+    layout: Layout,
+    machine_id: i32,
+    server_id: i32,
+    // the timestamp and counter of the last ID issued or observed
+    timestamp: i64,
+    counter: usize,
+    // how many ticks an observed ID may be ahead of the clock
+    max_drift: Option<i64>,
+}
+
+impl HlcIdGenerator {
+    pub fn new(machine_id: i32, server_id: i32) -> Result<Self, IdError> {
+        Self::with_clock(SystemClock::default(), machine_id, server_id)
+    }
+}
+
+impl<C: Clock> HlcIdGenerator<C> {
+    pub fn with_clock(clock: C, machine_id: i32, server_id: i32) -> Result<Self, IdError> {
+        let layout = Layout::hybrid_logical();
+        layout.validate_ids(machine_id, server_id)?;
+
+        Ok(Self {
+            clock,
+            layout,
+            machine_id,
+            server_id,
+            timestamp: -1,
+            counter: 0,
+            max_drift: None,
+        })
+    }
+
+    /// reject observed IDs dated more than `max_drift` ahead of the clock,
+    /// so one node with a broken clock can't drag every other one along
+    pub fn with_max_drift(mut self, max_drift: Duration) -> Self {
+        self.max_drift = Some(max_drift.as_millis() as i64);
+        self
+    }
+
+    /// generate an ID that sorts after every ID generated or observed so far
+    ///
+    /// # Panics
+    ///
+    /// Panics once the timestamp overflows the layout, use
+    /// [`HlcIdGenerator::try_generate_id`] to handle that case.
+    pub fn generate_id(&mut self) -> i64 {
+        self.try_generate_id().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// generate an ID like [`HlcIdGenerator::generate_id`], or return
+    /// [`IdError::TimestampOverflow`] once the clock, or the counter carrying
+    /// into the timestamp, runs past the layout
+    pub fn try_generate_id(&mut self) -> Result<i64, IdError> {
+        let now = self.clock.ticks(&self.layout);
+
+        if now > self.timestamp {
+            self.check_timestamp(now)?;
+            self.timestamp = now;
+            self.counter = 0;
+        } else {
+            self.tick()?;
+        }
+
+        Ok(self.layout.pack(
+            self.timestamp,
+            self.machine_id,
+            self.server_id,
+            self.counter,
+        ))
+    }
+
+    /// merge the clock of the node that generated `remote` into ours, so
+    /// every ID generated from now on sorts after it
+    ///
+    /// Fails with [`IdError::DriftExceeded`] when `remote` is dated further
+    /// ahead of the clock than the [max drift](Self::with_max_drift) allows,
+    /// and with [`IdError::TimestampOverflow`] when its timestamp doesn't fit
+    /// the layout.
+    pub fn observe(&mut self, remote: i64) -> Result<(), IdError> {
+        let parts = self.layout.decode(remote);
+        self.check_timestamp(parts.timestamp)?;
+
+        if let Some(max_drift) = self.max_drift {
+            if parts.timestamp - self.clock.ticks(&self.layout) > max_drift {
+                return Err(IdError::DriftExceeded);
+            }
+        }
+
+        if parts.timestamp > self.timestamp {
+            self.timestamp = parts.timestamp;
+            self.counter = parts.sequence;
+        } else if parts.timestamp == self.timestamp {
+            self.counter = self.counter.max(parts.sequence);
+        }
+
+        Ok(())
+    }
+
+    /// advance the logical counter, carrying into the timestamp when it
+    /// runs out
+    fn tick(&mut self) -> Result<(), IdError> {
+        if self.counter + 1 == self.layout.sequence_capacity() {
+            self.check_timestamp(self.timestamp + 1)?;
+            self.timestamp += 1;
+            self.counter = 0;
+        } else {
+            self.counter += 1;
+        }
+
+        Ok(())
+    }
+
+    fn check_timestamp(&self, timestamp: i64) -> Result<(), IdError> {
+        if timestamp > self.layout.max_timestamp() {
+            return Err(IdError::TimestampOverflow);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+
+    fn decode(id: i64) -> crate::IdParts {
+        Layout::hybrid_logical().decode(id)
+    }
+
+    #[test]
+    fn test_hlc_ids_follow_observed_ids() {
+        let behind = MockClock::new(1_000);
+        let mut local = HlcIdGenerator::with_clock(behind.clone(), 1, 0).unwrap();
+        let mut remote = HlcIdGenerator::with_clock(MockClock::new(5_000), 2, 0).unwrap();
+
+        let first = local.generate_id();
+        let message = remote.generate_id();
+        local.observe(message).unwrap();
+        let reply = local.generate_id();
+
+        assert!(first < message && message < reply);
+        assert_eq!(decode(reply).timestamp, 5_000);
+        assert_eq!(decode(reply).sequence, 1);
+
+        // the logical counter keeps counting until the clock catches up
+        assert_eq!(decode(local.generate_id()).sequence, 2);
+        behind.set_millis(6_000);
+        assert_eq!(decode(local.generate_id()).sequence, 0);
+    }
+
+    #[test]
+    fn test_hlc_counter_carries_into_timestamp() {
+        let mut id_gen = HlcIdGenerator::with_clock(MockClock::new(1_000), 1, 2).unwrap();
+
+        let ids: Vec<i64> = (0..=crate::MAX_IDS_PER_MILLISECOND)
+            .map(|_| id_gen.generate_id())
+            .collect();
+
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(decode(*ids.last().unwrap()).timestamp, 1_001);
+    }
+
+    #[test]
+    fn test_hlc_max_drift() {
+        let mut id_gen = HlcIdGenerator::with_clock(MockClock::new(1_000), 1, 0)
+            .unwrap()
+            .with_max_drift(Duration::from_secs(1));
+        let mut remote = HlcIdGenerator::with_clock(MockClock::new(5_000), 2, 0).unwrap();
+
+        assert_eq!(
+            id_gen.observe(remote.generate_id()),
+            Err(IdError::DriftExceeded)
+        );
+        assert_eq!(decode(id_gen.generate_id()).timestamp, 1_000);
+    }
+
+    #[test]
+    fn test_hlc_timestamp_overflow() {
+        let max = Layout::hybrid_logical().max_timestamp();
+        let mut id_gen = HlcIdGenerator::with_clock(MockClock::new(1_000), 1, 0).unwrap();
+        let mut remote = HlcIdGenerator::with_clock(MockClock::new(max), 2, 0).unwrap();
+
+        // carrying past the last timestamp fails instead of wrapping to 0
+        let last = (0..crate::MAX_IDS_PER_MILLISECOND)
+            .map(|_| remote.generate_id())
+            .last()
+            .unwrap();
+        assert_eq!(remote.try_generate_id(), Err(IdError::TimestampOverflow));
+
+        id_gen.observe(last).unwrap();
+        assert_eq!(id_gen.try_generate_id(), Err(IdError::TimestampOverflow));
+        assert_eq!(id_gen.observe(-1), Err(IdError::TimestampOverflow));
+    }
+}
@@ -0,0 +1,84 @@
+use std::fs;
+use std::net::{IpAddr, UdpSocket};
+
+/// A number identifying this host: the primary MAC address if one can be
+/// found, otherwise a hash of the hostname and local IP address.
+pub(crate) fn host_identity() -> u64 {
+    primary_mac().unwrap_or_else(|| {
+        let ip = local_ip().map(|ip| ip.to_string()).unwrap_or_default();
+        fnv1a(format!("{}/{}", hostname(), ip).as_bytes())
+    })
+}
+
+/// the lowest bits of the host identity that fit a machine id up to `max_machine_id`
+pub(crate) fn machine_id(max_machine_id: i32) -> i32 {
+    (host_identity() & max_machine_id as u64) as i32
+}
+
+/// the MAC address of the first non-loopback interface, by name
+fn primary_mac() -> Option<u64> {
+    let mut names: Vec<_> = fs::read_dir("/sys/class/net")
+        .ok()?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| name != "lo")
+        .collect();
+    names.sort();
+
+    names.iter().find_map(|name| {
+        let address = fs::read_to_string(format!("/sys/class/net/{}/address", name)).ok()?;
+        parse_mac(address.trim()).filter(|&mac| mac != 0)
+    })
+}
+
+fn parse_mac(s: &str) -> Option<u64> {
+    let octets: Vec<&str> = s.split(':').collect();
+    if octets.len() != 6 {
+        return None;
+    }
+
+    octets.iter().try_fold(0, |mac, octet| {
+        u8::from_str_radix(octet, 16)
+            .ok()
+            .map(|byte| mac << 8 | byte as u64)
+    })
+}
+
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// the address outgoing traffic would use; connecting a UDP socket sends nothing
+fn local_ip() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:80").ok()?;
+    socket.local_addr().ok().map(|addr| addr.ip())
+}
+
+/// FNV-1a, so the same host maps to the same id across builds
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mac() {
+        assert_eq!(parse_mac("02:42:ac:11:00:02"), Some(0x0242_ac11_0002));
+        assert_eq!(parse_mac("02:42:ac:11:00"), None);
+        assert_eq!(parse_mac("02:42:ac:11:00:zz"), None);
+    }
+
+    #[test]
+    fn test_host_identity_is_stable() {
+        assert_eq!(host_identity(), host_identity());
+        assert_ne!(fnv1a(b"host-a/10.0.0.1"), fnv1a(b"host-b/10.0.0.1"));
+    }
+}
@@ -0,0 +1,470 @@
+use crate::{
+    base58, base62, check, crockford, decode, CheckDigit, IdError, IdExplanation, IdParts, Layout,
+};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+/// A generated ID.
+///
+/// IDs are produced as `i64` so they fit signed `BIGINT` columns, but they are
+/// never negative. `Id` keeps them apart from arbitrary integers and is the one
+/// place where conversions between the signed and unsigned forms are checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "diesel",
+    derive(diesel::expression::AsExpression, diesel::deserialize::FromSqlRow)
+)]
+#[cfg_attr(feature = "diesel", diesel(sql_type = diesel::sql_types::BigInt))]
+#[repr(transparent)]
+pub struct Id(u64);
+
+impl Id {
+    pub const fn as_u64(self) -> u64 {
+        self.0
+    }
+
+    /// split the ID into the values it was built from
+    pub fn parts(self) -> IdParts {
+        decode(self.0 as i64)
+    }
+
+    /// a printable breakdown of the fields of an ID built with `layout`, e.g.
+    /// for pasting an ID from a support ticket into `uniqueid decode`
+    pub fn explain(self, layout: &Layout) -> IdExplanation {
+        IdExplanation::new(self, layout)
+    }
+
+    /// whether the ID was generated in an earlier millisecond than `other`
+    ///
+    /// Unlike `<`, this doesn't depend on the machine and server bits, so it
+    /// holds across generators sharing an epoch.
+    pub fn issued_before(&self, other: &Id) -> bool {
+        self.parts().timestamp < other.parts().timestamp
+    }
+
+    /// whether both IDs were generated in the same millisecond, in which case
+    /// the order they were issued in by different generators is unknown
+    pub fn same_millisecond(&self, other: &Id) -> bool {
+        self.parts().timestamp == other.parts().timestamp
+    }
+
+    /// order IDs by creation time, for merging the streams of several
+    /// generators
+    ///
+    /// IDs are compared by timestamp, then by sequence, so the n-th ID of a
+    /// millisecond sorts with the n-th IDs of the other nodes instead of after
+    /// every ID of the nodes with lower machine ids. The machine and server
+    /// bits only break ties, which keeps the order total and consistent with
+    /// `==`. The plain `Ord` of `Id` compares the raw value instead.
+    pub fn cmp_by_time(&self, other: &Id) -> Ordering {
+        let (a, b) = (self.parts(), other.parts());
+
+        (a.timestamp, a.sequence, a.machine_id, a.server_id).cmp(&(
+            b.timestamp,
+            b.sequence,
+            b.machine_id,
+            b.server_id,
+        ))
+    }
+
+    /// the shard out of `num_shards` the ID belongs to, for routing rows of a
+    /// partitioned database
+    ///
+    /// The shard is `fmix64(id) % num_shards`, where `fmix64` is the 64-bit
+    /// finalizer of MurmurHash3, so services in other languages can compute
+    /// the same value. Hashing the whole ID spreads the IDs of one generator
+    /// evenly, where the machine and server bits alone would send all of them
+    /// to the same shard.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_shards` is zero.
+    pub fn shard(self, num_shards: u32) -> u32 {
+        assert!(num_shards > 0, "num_shards must not be zero");
+
+        let mut h = self.0;
+        h = (h ^ (h >> 33)).wrapping_mul(0xff51_afd7_ed55_8ccd);
+        h = (h ^ (h >> 33)).wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+        h ^= h >> 33;
+
+        (h % num_shards as u64) as u32
+    }
+
+    /// the time the ID was generated, given the epoch of its generator
+    pub fn created_at(self, epoch: SystemTime) -> SystemTime {
+        epoch + Duration::from_millis(self.parts().timestamp as u64)
+    }
+
+    /// the time an ID from a generator with the default unix epoch was generated
+    #[cfg(feature = "chrono")]
+    pub fn created_at_utc(self) -> chrono::DateTime<chrono::Utc> {
+        self.created_at(crate::utils::get_epoch()).into()
+    }
+
+    /// encode as an 11 character base62 string that sorts like the ID itself
+    pub fn to_base62(self) -> String {
+        base62::encode(self.0)
+    }
+
+    /// parse a base62 string produced by [`Id::to_base62`]
+    pub fn from_base62(s: &str) -> Result<Self, IdError> {
+        base62::decode(s).map(Id)
+    }
+
+    /// encode as 16 zero-padded lowercase hex digits, which sort like the
+    /// IDs, e.g. for log lines and trace ids
+    pub fn to_hex(self) -> String {
+        format!("{:016x}", self.0)
+    }
+
+    /// parse a string produced by [`Id::to_hex`], in either case
+    pub fn from_hex(s: &str) -> Result<Self, IdError> {
+        if s.len() != 16 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(IdError::InvalidIdString);
+        }
+
+        u64::from_str_radix(s, 16)
+            .map(Id)
+            .map_err(|_| IdError::InvalidIdString)
+    }
+
+    /// embed the ID into a version 8 (custom) UUID, for columns typed as UUID
+    ///
+    /// The 64 bits of the ID fill the UUID from the most significant bit,
+    /// skipping the version and variant bits, and the rest is zero, so the
+    /// UUIDs sort like the IDs.
+    #[cfg(feature = "uuid")]
+    pub fn to_uuid(self) -> uuid::Uuid {
+        let id = self.0 as u128;
+        let v = (id >> 16) << 80 // 48 bits before the version
+            | 0x8 << 76
+            | (id >> 4 & 0xfff) << 64 // 12 bits between version and variant
+            | 0b10 << 62
+            | (id & 0xf) << 58;
+
+        uuid::Uuid::from_u128(v)
+    }
+
+    /// recover the ID from a UUID produced by [`Id::to_uuid`]
+    #[cfg(feature = "uuid")]
+    pub fn try_from_uuid(uuid: uuid::Uuid) -> Result<Self, IdError> {
+        let v = uuid.as_u128();
+
+        if v >> 76 & 0xf != 0x8 || v >> 62 & 0b11 != 0b10 || v & ((1 << 58) - 1) != 0 {
+            return Err(IdError::InvalidIdString);
+        }
+
+        let id = (v >> 80) << 16 | (v >> 64 & 0xfff) << 4 | (v >> 58 & 0xf);
+
+        Ok(Id(id as u64))
+    }
+
+    /// encode as Bitcoin-style base58, which leaves out the look-alike
+    /// `0`, `O`, `I` and `l`
+    pub fn to_base58(self) -> String {
+        base58::encode(self.0)
+    }
+
+    /// parse a base58 string produced by [`Id::to_base58`]
+    pub fn from_base58(s: &str) -> Result<Self, IdError> {
+        base58::decode(s).map(Id)
+    }
+
+    /// encode as Crockford base32, e.g. for ticket numbers read by humans
+    pub fn to_crockford(self) -> String {
+        crockford::encode(self.0)
+    }
+
+    /// encode as 13 zero-padded Crockford base32 symbols, which sort
+    /// lexicographically in the same order as the IDs, e.g. for object
+    /// store keys
+    pub fn to_sortable(self) -> String {
+        crockford::encode_padded(self.0)
+    }
+
+    /// parse a string produced by [`Id::to_sortable`]
+    pub fn from_sortable(s: &str) -> Result<Self, IdError> {
+        crockford::decode_padded(s).map(Id)
+    }
+
+    /// encode as Crockford base32 followed by a mod 37 check symbol
+    pub fn to_crockford_with_check(self) -> String {
+        crockford::encode_with_check(self.0)
+    }
+
+    /// the decimal ID followed by a check digit, for IDs read out or typed
+    /// by people
+    pub fn to_string_with_check(self, check: CheckDigit) -> String {
+        check::append(check, self.0)
+    }
+
+    /// parse a string produced by [`Id::to_string_with_check`] and verify
+    /// its check digit
+    pub fn from_str_with_check(s: &str, check: CheckDigit) -> Result<Self, IdError> {
+        check::verify(check, s).map(Id)
+    }
+
+    /// parse Crockford base32, ignoring case, hyphens and ambiguous characters
+    pub fn from_crockford(s: &str) -> Result<Self, IdError> {
+        crockford::decode(s).map(Id)
+    }
+
+    /// parse Crockford base32 and verify its trailing check symbol
+    pub fn from_crockford_with_check(s: &str) -> Result<Self, IdError> {
+        crockford::decode_with_check(s).map(Id)
+    }
+}
+
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl FromStr for Id {
+    type Err = IdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<u64>()
+            .map(Id)
+            .map_err(|_| IdError::InvalidIdString)
+    }
+}
+
+impl From<u64> for Id {
+    fn from(id: u64) -> Self {
+        Id(id)
+    }
+}
+
+impl From<Id> for u64 {
+    fn from(id: Id) -> Self {
+        id.0
+    }
+}
+
+impl TryFrom<i64> for Id {
+    type Error = IdError;
+
+    fn try_from(id: i64) -> Result<Self, Self::Error> {
+        u64::try_from(id)
+            .map(Id)
+            .map_err(|_| IdError::NegativeId(id))
+    }
+}
+
+impl TryFrom<Id> for i64 {
+    type Error = IdError;
+
+    fn try_from(id: Id) -> Result<Self, Self::Error> {
+        i64::try_from(id.0).map_err(|_| IdError::IdOutOfRange(id.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IdGenerator;
+
+    #[test]
+    fn test_id_conversions() {
+        let raw = IdGenerator::new(1, 2).unwrap().generate_id();
+        let id = Id::try_from(raw).unwrap();
+
+        assert_eq!(i64::try_from(id).unwrap(), raw);
+        assert_eq!(u64::from(id), raw as u64);
+        assert_eq!(id.parts(), decode(raw));
+
+        assert_eq!(Id::try_from(-1_i64), Err(IdError::NegativeId(-1)));
+        assert_eq!(
+            i64::try_from(Id::from(u64::MAX)),
+            Err(IdError::IdOutOfRange(u64::MAX))
+        );
+    }
+
+    #[test]
+    fn test_id_ordering_across_generators() {
+        let early = Id::from(1_000 << 22 | 31 << 17 | 9);
+        let late = Id::from(1_001 << 22);
+        let other_node = Id::from(1_000 << 22 | 1 << 17 | 10);
+
+        assert!(early.issued_before(&late));
+        assert!(!late.issued_before(&early));
+        assert!(!early.issued_before(&other_node));
+        assert!(early.same_millisecond(&other_node));
+        assert!(other_node < early);
+        assert_eq!(other_node.cmp_by_time(&early), Ordering::Greater);
+        assert_eq!(early.cmp_by_time(&early), Ordering::Equal);
+
+        let mut ids = vec![late, other_node, early];
+        ids.sort_by(Id::cmp_by_time);
+        assert_eq!(ids, [early, other_node, late]);
+    }
+
+    #[test]
+    fn test_id_shard() {
+        // the values other implementations of fmix64 give
+        assert_eq!(Id::from(0).shard(16), 0);
+        assert_eq!(Id::from(1).shard(16), 12);
+        assert_eq!(Id::from(1_234_567_890).shard(16), 13);
+        assert_eq!(Id::from(1_234_567_890).shard(1), 0);
+
+        let mut counts = [0; 8];
+        for raw in IdGenerator::new(1, 2).unwrap().generate_ids(8_000) {
+            counts[Id::try_from(raw).unwrap().shard(8) as usize] += 1;
+        }
+        assert!(
+            counts.iter().all(|&n| (800..1_200).contains(&n)),
+            "{:?}",
+            counts
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "num_shards must not be zero")]
+    fn test_id_shard_zero() {
+        Id::from(1).shard(0);
+    }
+
+    #[test]
+    fn test_id_display_from_str() {
+        let id = Id::from(1_234_567_890);
+
+        assert_eq!(id.to_string(), "1234567890");
+        assert_eq!("1234567890".parse::<Id>(), Ok(id));
+        assert_eq!("-1".parse::<Id>(), Err(IdError::InvalidIdString));
+    }
+
+    #[test]
+    fn test_id_base62() {
+        let id = Id::from(1_234_567_890);
+
+        assert_eq!(id.to_base62(), "000001LY7VK");
+        assert_eq!(Id::from_base62(&id.to_base62()), Ok(id));
+    }
+
+    #[test]
+    fn test_id_with_check_digit() {
+        let id = Id::from(1_234_567_890);
+
+        for check in [CheckDigit::Damm, CheckDigit::Luhn] {
+            let s = id.to_string_with_check(check);
+
+            assert_eq!(s.len(), 11);
+            assert_eq!(Id::from_str_with_check(&s, check), Ok(id));
+            assert_eq!(
+                Id::from_str_with_check(&s.replacen('5', "6", 1), check),
+                Err(IdError::ChecksumMismatch)
+            );
+        }
+    }
+
+    #[test]
+    fn test_id_hex() {
+        let ids = [0, 1, 0xabc, 1 << 40, u64::MAX].map(Id::from);
+        let encoded: Vec<String> = ids.iter().map(|id| id.to_hex()).collect();
+
+        assert_eq!(encoded[0], "0000000000000000");
+        assert_eq!(encoded[2], "0000000000000abc");
+        assert!(encoded.windows(2).all(|w| w[0] < w[1]));
+        assert!(ids
+            .iter()
+            .zip(&encoded)
+            .all(|(&id, s)| Id::from_hex(s) == Ok(id)));
+        assert_eq!(Id::from_hex("0000000000000ABC"), Ok(ids[2]));
+
+        for s in [
+            "abc",
+            "+000000000000abc",
+            "0000000000000abg",
+            "00000000000000abc",
+        ] {
+            assert_eq!(Id::from_hex(s), Err(IdError::InvalidIdString), "{}", s);
+        }
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn test_id_uuid() {
+        let ids = [0, 1, 0xf, 0x10, 1 << 40, u64::MAX].map(Id::from);
+        let uuids: Vec<uuid::Uuid> = ids.iter().map(|id| id.to_uuid()).collect();
+
+        assert!(uuids.iter().all(|u| u.get_version_num() == 8));
+        assert!(uuids
+            .iter()
+            .all(|u| u.get_variant() == uuid::Variant::RFC4122));
+        assert!(uuids.windows(2).all(|w| w[0] < w[1]));
+        assert!(ids
+            .iter()
+            .zip(&uuids)
+            .all(|(&id, &u)| Id::try_from_uuid(u) == Ok(id)));
+        assert_eq!(
+            Id::from(0x0123_4567_89ab_cdef).to_uuid().to_string(),
+            "01234567-89ab-8cde-bc00-000000000000"
+        );
+        assert_eq!(
+            Id::try_from_uuid(uuid::Uuid::nil()),
+            Err(IdError::InvalidIdString)
+        );
+        assert_eq!(
+            Id::try_from_uuid(crate::Uuid7Generator::new().generate().into()),
+            Err(IdError::InvalidIdString)
+        );
+    }
+
+    #[test]
+    fn test_id_base58() {
+        let id = Id::from(1_234_567_890);
+
+        assert_eq!(id.to_base58(), "2t6V2H");
+        assert_eq!(Id::from_base58(&id.to_base58()), Ok(id));
+        assert_eq!(Id::from_base58("2t6V0H"), Err(IdError::InvalidIdString));
+    }
+
+    #[test]
+    fn test_id_sortable() {
+        let ids = [0, 1, 31, 32, 1 << 40, u64::MAX - 1, u64::MAX].map(Id::from);
+        let encoded: Vec<String> = ids.iter().map(|id| id.to_sortable()).collect();
+
+        assert_eq!(encoded[0], "0000000000000");
+        assert_eq!(encoded[6], "FZZZZZZZZZZZZ");
+        assert!(encoded.windows(2).all(|w| w[0] < w[1]));
+        assert!(ids
+            .iter()
+            .zip(&encoded)
+            .all(|(&id, s)| Id::from_sortable(s) == Ok(id)));
+        assert_eq!(Id::from_sortable("1Z"), Err(IdError::InvalidIdString));
+        assert_eq!(
+            Id::from_sortable("000000000000z"),
+            Err(IdError::InvalidIdString)
+        );
+    }
+
+    #[test]
+    fn test_id_created_at() {
+        let before = SystemTime::now() - Duration::from_millis(1);
+        let raw = IdGenerator::new(1, 2).unwrap().generate_id();
+        let id = Id::try_from(raw).unwrap();
+
+        let created = id.created_at(SystemTime::UNIX_EPOCH);
+
+        assert!(created >= before && created <= SystemTime::now());
+        assert_eq!(
+            Id::from(5 << 22).created_at(SystemTime::UNIX_EPOCH),
+            SystemTime::UNIX_EPOCH + Duration::from_millis(5)
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_id_created_at_utc() {
+        let id = Id::from(1_577_836_800_000 << 22);
+
+        assert_eq!(
+            id.created_at_utc().to_rfc3339(),
+            "2020-01-01T00:00:00+00:00"
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use crate::rng::Rng;
+use crate::utils::*;
+
+const MACHINE_ID_SHIFT: u32 = 32;
+const TIMESTAMP_SHIFT: u32 = 64;
+
+/// How the low 32 bits of a 128-bit ID are filled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Id128Tail {
+    /// count up from 0 in every millisecond
+    #[default]
+    Counter,
+    /// count up from a random value in every millisecond, so the tail
+    /// doesn't reveal how many IDs were issued
+    Random,
+}
+
+/// Generates `u128` IDs: a 64-bit millisecond timestamp since the unix
+/// epoch, a 32-bit machine id and a 32-bit tail.
+///
+/// IDs from one generator are strictly increasing.
+#[derive(Debug, Clone)]
+pub struct Id128Generator {
+    machine_id: u32,
+    tail: Id128Tail,
+    timestamp: i64,
+    counter: u32,
+    rng: Rng,
+}
+
+impl Id128Generator {
+    pub fn new(machine_id: u32) -> Self {
+        Self::with_tail(machine_id, Id128Tail::default())
+    }
+
+    pub fn with_tail(machine_id: u32, tail: Id128Tail) -> Self {
+        Self {
+            machine_id,
+            tail,
+            timestamp: -1,
+            counter: 0,
+            rng: Rng::new(),
+        }
+    }
+
+    pub fn generate_id(&mut self) -> u128 {
+        let epoch = get_epoch();
+        let now = get_timestamp(epoch);
+
+        if now > self.timestamp {
+            self.start_millisecond(now);
+        } else if self.counter == u32::MAX {
+            let now = bind_time(self.timestamp, epoch);
+            self.start_millisecond(now);
+        } else {
+            self.counter += 1;
+        }
+
+        (self.timestamp as u128) << TIMESTAMP_SHIFT
+            | (self.machine_id as u128) << MACHINE_ID_SHIFT
+            | self.counter as u128
+    }
+
+    fn start_millisecond(&mut self, now: i64) {
+        self.timestamp = now;
+        self.counter = match self.tail {
+            Id128Tail::Counter => 0,
+            // keep half of the range free so the counter can't run out early
+            Id128Tail::Random => self.rng.next_u64() as u32 >> 1,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_id128_layout() {
+        let mut id_gen = Id128Generator::new(0xdead_beef);
+        let id = id_gen.generate_id();
+
+        assert_eq!((id >> MACHINE_ID_SHIFT) as u32, 0xdead_beef);
+        assert_eq!(id as u32, 0);
+        assert!((id >> TIMESTAMP_SHIFT) as i64 <= get_timestamp(get_epoch()));
+    }
+
+    #[test]
+    fn test_id128_monotonic() {
+        for tail in [Id128Tail::Counter, Id128Tail::Random] {
+            let mut id_gen = Id128Generator::with_tail(7, tail);
+            let ids: Vec<u128> = (0..10_000).map(|_| id_gen.generate_id()).collect();
+
+            assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        }
+    }
+
+    #[test]
+    fn test_id128_counter_overflow() {
+        let mut id_gen = Id128Generator::new(1);
+        let first = id_gen.generate_id();
+        id_gen.counter = u32::MAX;
+
+        let next = id_gen.generate_id();
+
+        assert!(next >> TIMESTAMP_SHIFT > first >> TIMESTAMP_SHIFT);
+    }
+}
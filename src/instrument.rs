@@ -0,0 +1,117 @@
+//! Hooks recording what a generator does through the `metrics` and `tracing`
+//! crates. Without those features every hook compiles to nothing.
+//!
+//! | metric                                | kind      |
+//! |---------------------------------------|-----------|
+//! | `unique_id_ids_issued_total`          | counter   |
+//! | `unique_id_sequence_rollovers_total`  | counter   |
+//! | `unique_id_clock_rollbacks_total`     | counter   |
+//! | `unique_id_waits_total`               | counter   |
+//! | `unique_id_spin_wait_seconds`         | histogram |
+
+#[cfg(feature = "metrics")]
+const IDS_ISSUED: &str = "unique_id_ids_issued_total";
+#[cfg(feature = "metrics")]
+const SEQUENCE_ROLLOVERS: &str = "unique_id_sequence_rollovers_total";
+#[cfg(feature = "metrics")]
+const CLOCK_ROLLBACKS: &str = "unique_id_clock_rollbacks_total";
+#[cfg(feature = "metrics")]
+const WAITS: &str = "unique_id_waits_total";
+#[cfg(feature = "metrics")]
+const SPIN_WAIT: &str = "unique_id_spin_wait_seconds";
+
+pub(crate) fn id_issued() {
+    ids_issued(1);
+}
+
+pub(crate) fn ids_issued(n: usize) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!(IDS_ISSUED).increment(n as u64);
+
+    let _ = n;
+}
+
+/// every sequence number of the tick `timestamp` has been issued
+pub(crate) fn sequence_rollover(timestamp: i64) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!(SEQUENCE_ROLLOVERS).increment(1);
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(timestamp, "sequence exhausted");
+
+    let _ = timestamp;
+}
+
+/// the clock read `ticks` earlier than the last issued timestamp
+pub(crate) fn clock_rollback(ticks: i64) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!(CLOCK_ROLLBACKS).increment(1);
+
+    #[cfg(feature = "tracing")]
+    tracing::warn!(ticks, "clock moved backwards");
+
+    let _ = ticks;
+}
+
+/// run `wait`, recording how long it spun
+pub(crate) fn spin_wait<T>(wait: impl FnOnce() -> T) -> T {
+    #[cfg(any(feature = "metrics", feature = "tracing"))]
+    {
+        let start = std::time::Instant::now();
+        let result = wait();
+        let elapsed = start.elapsed();
+
+        #[cfg(feature = "metrics")]
+        {
+            metrics::counter!(WAITS).increment(1);
+            metrics::histogram!(SPIN_WAIT).record(elapsed.as_secs_f64());
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(?elapsed, "waited for the next tick");
+
+        result
+    }
+
+    #[cfg(not(any(feature = "metrics", feature = "tracing")))]
+    wait()
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::{IdGenerator, MAX_IDS_PER_MILLISECOND};
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    #[test]
+    fn test_metrics_recorded() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+
+        metrics::with_local_recorder(&recorder, || {
+            let mut id_gen = IdGenerator::builder()
+                .build_with_clock(MockClock::new(1_000))
+                .unwrap();
+
+            for _ in 0..MAX_IDS_PER_MILLISECOND {
+                let _ = id_gen.try_generate_id();
+            }
+        });
+
+        let snapshot = snapshotter.snapshot().into_vec();
+        let value = |name: &str| {
+            snapshot
+                .iter()
+                .find(|(key, ..)| key.key().name() == name)
+                .map(|(.., value)| value)
+        };
+
+        assert_eq!(
+            value(IDS_ISSUED),
+            Some(&DebugValue::Counter(MAX_IDS_PER_MILLISECOND as u64 - 1))
+        );
+        assert_eq!(value(SEQUENCE_ROLLOVERS), Some(&DebugValue::Counter(1)));
+        assert_eq!(value(CLOCK_ROLLBACKS), None);
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{Clock, Id, IdGenerator, SystemClock};
+use std::iter::FusedIterator;
+
+/// An endless iterator over IDs from an [`IdGenerator`], created by
+/// [`IdGenerator::iter`].
+#[derive(Debug)]
+pub struct Ids<'a, C = SystemClock> {
+    id_gen: &'a mut IdGenerator<C>,
+}
+
+impl<'a, C> Ids<'a, C> {
+    pub(crate) fn new(id_gen: &'a mut IdGenerator<C>) -> Self {
+        Self { id_gen }
+    }
+}
+
+impl<C: Clock> Iterator for Ids<'_, C> {
+    type Item = Id;
+
+    fn next(&mut self) -> Option<Id> {
+        Some(Id::from(self.id_gen.generate_id() as u64))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+impl<C: Clock> FusedIterator for Ids<'_, C> {}
+
+impl<'a, C: Clock> IntoIterator for &'a mut IdGenerator<C> {
+    type Item = Id;
+    type IntoIter = Ids<'a, C>;
+
+    fn into_iter(self) -> Ids<'a, C> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iter_take_collect() {
+        let mut id_gen = IdGenerator::new(1, 2).unwrap();
+
+        let ids: Vec<Id> = id_gen.iter().take(1_000).collect();
+        let next = id_gen.iter().next().unwrap();
+
+        assert_eq!(ids.len(), 1_000);
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        assert!(next > ids[999]);
+    }
+
+    #[test]
+    fn test_into_iter_for_loop() {
+        let mut id_gen = IdGenerator::new(1, 2).unwrap();
+        let mut count = 0;
+
+        for id in &mut id_gen {
+            assert_eq!(id.parts().machine_id, 1);
+
+            count += 1;
+            if count == 10 {
+                break;
+            }
+        }
+
+        assert_eq!(count, 10);
+    }
+}
@@ -0,0 +1,130 @@
+//! [KSUID](https://github.com/segmentio/ksuid) generation.
+//!
+//! A KSUID is 20 bytes: a 32-bit timestamp in seconds since the KSUID epoch
+//! followed by a 128-bit random payload. The random payload makes KSUIDs
+//! collision resistant without assigning machine ids.
+
+use crate::rng::Rng;
+use crate::utils::*;
+use crate::{base62, IdError};
+use std::fmt;
+use std::str::FromStr;
+
+/// 2014-05-13T16:53:20Z in seconds since the unix epoch
+pub const KSUID_EPOCH: u64 = 1_400_000_000;
+
+const ENCODED_LEN: usize = 27;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Ksuid([u8; 20]);
+
+impl Ksuid {
+    pub fn from_parts(timestamp: u32, payload: [u8; 16]) -> Self {
+        let mut bytes = [0u8; 20];
+        bytes[..4].copy_from_slice(&timestamp.to_be_bytes());
+        bytes[4..].copy_from_slice(&payload);
+
+        Ksuid(bytes)
+    }
+
+    pub const fn from_bytes(bytes: [u8; 20]) -> Self {
+        Ksuid(bytes)
+    }
+
+    /// seconds since [`KSUID_EPOCH`]
+    pub fn timestamp(&self) -> u32 {
+        u32::from_be_bytes([self.0[0], self.0[1], self.0[2], self.0[3]])
+    }
+
+    /// seconds since the unix epoch
+    pub fn unix_timestamp(&self) -> u64 {
+        self.timestamp() as u64 + KSUID_EPOCH
+    }
+
+    pub fn payload(&self) -> [u8; 16] {
+        let mut payload = [0u8; 16];
+        payload.copy_from_slice(&self.0[4..]);
+        payload
+    }
+
+    pub const fn as_bytes(&self) -> &[u8; 20] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Ksuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&base62::encode_bytes(&self.0, ENCODED_LEN))
+    }
+}
+
+impl FromStr for Ksuid {
+    type Err = IdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != ENCODED_LEN {
+            return Err(IdError::InvalidIdString);
+        }
+
+        base62::decode_bytes(s).map(Ksuid)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct KsuidGenerator {
+    rng: Rng,
+}
+
+impl Default for KsuidGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KsuidGenerator {
+    pub fn new() -> Self {
+        Self { rng: Rng::new() }
+    }
+
+    pub fn generate(&mut self) -> Ksuid {
+        let seconds = get_timestamp(get_epoch()) as u64 / 1000;
+        let timestamp = seconds.saturating_sub(KSUID_EPOCH) as u32;
+
+        Ksuid::from_parts(timestamp, self.rng.next_u128().to_be_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ksuid_generate() {
+        let mut ksuid_gen = KsuidGenerator::new();
+        let now = get_timestamp(get_epoch()) as u64 / 1000;
+
+        let a = ksuid_gen.generate();
+        let b = ksuid_gen.generate();
+
+        assert_ne!(a, b);
+        assert!(a.unix_timestamp().abs_diff(now) <= 1);
+    }
+
+    #[test]
+    fn test_ksuid_string_round_trip() {
+        let min = Ksuid::from_bytes([0; 20]);
+        let max = Ksuid::from_bytes([0xff; 20]);
+
+        assert_eq!(min.to_string(), "000000000000000000000000000");
+        assert_eq!(max.to_string(), "aWgEPTl1tmebfsQzFP4bxwgy80V");
+        assert_eq!("aWgEPTl1tmebfsQzFP4bxwgy80V".parse(), Ok(max));
+
+        let ksuid = KsuidGenerator::new().generate();
+        assert_eq!(ksuid.to_string().parse(), Ok(ksuid));
+
+        assert_eq!(
+            "aWgEPTl1tmebfsQzFP4bxwgy80W".parse::<Ksuid>(),
+            Err(IdError::InvalidIdString)
+        );
+    }
+}
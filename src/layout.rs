@@ -0,0 +1,699 @@
+use crate::utils::*;
+use crate::{Id, IdError, IdExplanation, IdParts};
+use std::time::{Duration, SystemTime};
+use unique_id_core::{
+    MACHINE_ID_BITS, MACHINE_ID_SHIFT, SEQUENCE_BITS, SERVER_ID_BITS, SERVER_ID_SHIFT,
+    TIMESTAMP_BITS, TIMESTAMP_SHIFT,
+};
+
+/// A bit field inside an ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Field {
+    bits: u32,
+    shift: u32,
+}
+
+impl Field {
+    const fn new(bits: u32, shift: u32) -> Self {
+        Self { bits, shift }
+    }
+
+    const fn mask(self) -> i64 {
+        (1 << self.bits) - 1
+    }
+
+    fn pack(self, value: i64) -> i64 {
+        (value & self.mask()) << self.shift
+    }
+
+    fn unpack(self, id: i64) -> i64 {
+        (id >> self.shift) & self.mask()
+    }
+
+    /// the bits of an ID this field occupies
+    #[cfg(test)]
+    fn bits_in_id(self) -> i64 {
+        self.mask() << self.shift
+    }
+}
+
+/// Describes how the fields of an ID are packed, what the timestamp is
+/// measured from, and how long one timestamp tick is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    epoch: SystemTime,
+    tick: Duration,
+    timestamp: Field,
+    machine_id: Field,
+    server_id: Field,
+    sequence: Field,
+    // store the sequence bit-reversed, see `with_reversed_sequence`
+    reverse_sequence: bool,
+    // after a split: the bits above the sequence telling the generators of
+    // one split apart, see `split`
+    partition: Field,
+    partition_id: i64,
+}
+
+impl Default for Layout {
+    /// millisecond timestamp with 41 usable bits, 5-bit machine id, 5-bit
+    /// server id and 12-bit sequence, counted from the unix epoch
+    fn default() -> Self {
+        Self {
+            epoch: get_epoch(),
+            tick: Duration::from_millis(1),
+            timestamp: Field::new(TIMESTAMP_BITS, TIMESTAMP_SHIFT),
+            machine_id: Field::new(MACHINE_ID_BITS, MACHINE_ID_SHIFT),
+            server_id: Field::new(SERVER_ID_BITS, SERVER_ID_SHIFT),
+            sequence: Field::new(SEQUENCE_BITS, 0),
+            reverse_sequence: false,
+            partition: Field::new(0, 0),
+            partition_id: 0,
+        }
+    }
+}
+
+impl Layout {
+    /// The [Sonyflake](https://github.com/sony/sonyflake) layout: a 39-bit
+    /// timestamp in units of 10 ms since 2014-09-01, an 8-bit sequence and a
+    /// 16-bit machine id in the lowest bits. There is no server id.
+    pub fn sonyflake() -> Self {
+        Self {
+            epoch: SystemTime::UNIX_EPOCH + Duration::from_millis(1_409_529_600_000),
+            tick: Duration::from_millis(10),
+            timestamp: Field::new(39, 24),
+            machine_id: Field::new(16, 0),
+            server_id: Field::new(0, 0),
+            sequence: Field::new(8, 16),
+            reverse_sequence: false,
+            partition: Field::new(0, 0),
+            partition_id: 0,
+        }
+    }
+
+    /// The original [Twitter snowflake](https://github.com/twitter-archive/snowflake)
+    /// layout: a 41-bit millisecond timestamp since 2010-11-04, a 5-bit
+    /// datacenter id (`machine_id`), a 5-bit worker id (`server_id`) and a
+    /// 12-bit sequence.
+    pub fn twitter() -> Self {
+        Self {
+            epoch: SystemTime::UNIX_EPOCH + Duration::from_millis(1_288_834_974_657),
+            timestamp: Field::new(TIMESTAMP_BITS - 1, TIMESTAMP_SHIFT),
+            ..Self::default()
+        }
+    }
+
+    /// A layout for deployments spanning several datacenters, addressed like
+    /// the original Twitter snowflake: a 41-bit millisecond timestamp since the
+    /// unix epoch with the sign bit kept clear, a 5-bit datacenter id, a 5-bit
+    /// worker id and a 12-bit sequence.
+    ///
+    /// The datacenter and worker ids are stored in the `machine_id` and
+    /// `server_id` fields. Set them with
+    /// [`IdGeneratorBuilder::datacenter_id`](crate::IdGeneratorBuilder::datacenter_id)
+    /// and [`IdGeneratorBuilder::worker_id`](crate::IdGeneratorBuilder::worker_id),
+    /// and read them back with [`IdParts::datacenter_id`] and
+    /// [`IdParts::worker_id`].
+    pub fn multi_datacenter() -> Self {
+        Self {
+            timestamp: Field::new(TIMESTAMP_BITS - 1, TIMESTAMP_SHIFT),
+            ..Self::default()
+        }
+    }
+
+    /// The [Discord snowflake](https://discord.com/developers/docs/reference#snowflakes)
+    /// layout: a 42-bit millisecond timestamp since 2015-01-01, a 5-bit
+    /// internal worker id (`machine_id`), a 5-bit internal process id
+    /// (`server_id`) and a 12-bit increment.
+    pub fn discord() -> Self {
+        Self {
+            epoch: SystemTime::UNIX_EPOCH + Duration::from_millis(1_420_070_400_000),
+            ..Self::default()
+        }
+    }
+
+    /// A layout with microsecond resolution for workloads that rarely issue
+    /// more than a few IDs per microsecond: a 51-bit timestamp in
+    /// microseconds since 2024-01-01 (lasting about 71 years), a 5-bit
+    /// machine id, a 5-bit server id and a 2-bit sequence.
+    pub fn microsecond() -> Self {
+        Self {
+            epoch: SystemTime::UNIX_EPOCH + Duration::from_millis(1_704_067_200_000),
+            tick: Duration::from_micros(1),
+            timestamp: Field::new(51, 12),
+            machine_id: Field::new(5, 7),
+            server_id: Field::new(5, 2),
+            sequence: Field::new(2, 0),
+            reverse_sequence: false,
+            partition: Field::new(0, 0),
+            partition_id: 0,
+        }
+    }
+
+    /// A layout whose IDs never exceed `2^53 - 1`, so they survive being
+    /// parsed as JSON numbers by JavaScript: a 41-bit millisecond timestamp
+    /// since 2024-01-01 (lasting about 69 years), a 4-bit machine id and an
+    /// 8-bit sequence. There is no server id.
+    pub fn js_safe() -> Self {
+        Self {
+            epoch: SystemTime::UNIX_EPOCH + Duration::from_millis(1_704_067_200_000),
+            tick: Duration::from_millis(1),
+            timestamp: Field::new(41, 12),
+            machine_id: Field::new(4, 8),
+            server_id: Field::new(0, 0),
+            sequence: Field::new(8, 0),
+            reverse_sequence: false,
+            partition: Field::new(0, 0),
+            partition_id: 0,
+        }
+    }
+
+    /// The layout of [`HlcIdGenerator`](crate::HlcIdGenerator): the default
+    /// layout with the 12-bit sequence moved above the 5-bit machine and
+    /// server ids, so the IDs of one millisecond sort by their logical
+    /// counter before the node that generated them.
+    pub fn hybrid_logical() -> Self {
+        Self {
+            machine_id: Field::new(MACHINE_ID_BITS, SERVER_ID_BITS),
+            server_id: Field::new(SERVER_ID_BITS, 0),
+            sequence: Field::new(SEQUENCE_BITS, MACHINE_ID_BITS + SERVER_ID_BITS),
+            ..Self::default()
+        }
+    }
+
+    /// use a different epoch with the same field layout
+    pub fn with_epoch(mut self, epoch: SystemTime) -> Self {
+        self.epoch = epoch;
+        self
+    }
+
+    /// store the sequence with its bits reversed, so the IDs of one tick are
+    /// spread across the range of that tick instead of being consecutive
+    ///
+    /// Consecutive inserts then land on different B-tree pages instead of
+    /// all hitting the last one. The timestamp stays in the high bits, so IDs
+    /// still sort by tick, but the IDs within a tick no longer ascend, even
+    /// with [`IdGeneratorBuilder::monotonic`](crate::IdGeneratorBuilder::monotonic).
+    /// [`Layout::decode`] returns the original sequence.
+    pub fn with_reversed_sequence(mut self) -> Self {
+        self.reverse_sequence = true;
+        self
+    }
+
+    pub fn epoch(&self) -> SystemTime {
+        self.epoch
+    }
+
+    /// the duration of one timestamp unit
+    pub fn tick(&self) -> Duration {
+        self.tick
+    }
+
+    pub fn max_machine_id(&self) -> i32 {
+        self.machine_id.mask() as i32
+    }
+
+    pub fn max_server_id(&self) -> i32 {
+        self.server_id.mask() as i32
+    }
+
+    /// how many IDs fit into one timestamp tick
+    pub fn sequence_capacity(&self) -> usize {
+        1 << self.sequence.bits
+    }
+
+    /// the largest timestamp an ID can carry without reaching the sign bit
+    pub fn max_timestamp(&self) -> i64 {
+        self.timestamp.mask().min(i64::MAX >> self.timestamp.shift)
+    }
+
+    /// the moment the timestamp runs out, after which no more IDs can be
+    /// generated with this layout and epoch
+    pub fn exhaustion_date(&self) -> SystemTime {
+        self.epoch + from_ticks(self.max_timestamp() + 1, self.tick)
+    }
+
+    /// split an ID built with this layout into its fields
+    pub fn decode(&self, id: i64) -> IdParts {
+        IdParts {
+            timestamp: self.timestamp.unpack(id),
+            machine_id: self.machine_id.unpack(id) as i32,
+            server_id: self.server_id.unpack(id) as i32,
+            sequence: self.sequence_bits(self.sequence.unpack(id) as usize),
+        }
+    }
+
+    /// the time an ID built with this layout was generated
+    pub fn created_at(&self, id: i64) -> SystemTime {
+        self.epoch + from_ticks(self.timestamp.unpack(id), self.tick)
+    }
+
+    /// the time an ID built with this layout was generated, in UTC
+    #[cfg(feature = "chrono")]
+    pub fn created_at_utc(&self, id: i64) -> chrono::DateTime<chrono::Utc> {
+        self.created_at(id).into()
+    }
+
+    /// parse a decimal ID built with this layout into its fields and creation
+    /// time, e.g. a Discord or Twitter snowflake taken from an API response
+    pub fn parse(&self, s: &str) -> Result<IdExplanation, IdError> {
+        s.parse::<Id>().map(|id| id.explain(self))
+    }
+
+    /// the smallest ID this layout can hold for `time`, e.g. the lower bound of
+    /// a `WHERE id BETWEEN` scan for rows created from `time` on
+    ///
+    /// Times before the epoch or after the exhaustion date are clamped to the
+    /// first and last timestamp.
+    pub fn min_id_at(&self, time: SystemTime) -> i64 {
+        self.timestamp.pack(self.ticks_at(time))
+    }
+
+    /// the largest ID this layout can hold for `time`, with every other field
+    /// at its maximum, e.g. the upper bound of a scan for rows created up to
+    /// and including `time`
+    pub fn max_id_at(&self, time: SystemTime) -> i64 {
+        self.pack(
+            self.ticks_at(time),
+            self.max_machine_id(),
+            self.max_server_id(),
+            self.sequence_capacity() - 1,
+        )
+    }
+
+    fn ticks_at(&self, time: SystemTime) -> i64 {
+        time.duration_since(self.epoch)
+            .map_or(0, |elapsed| to_ticks(elapsed, self.tick))
+            .min(self.max_timestamp())
+    }
+
+    /// pack the fields into an ID, masking each value to its field so an out
+    /// of range value can't corrupt its neighbours
+    ///
+    /// The timestamp is masked to [`Layout::max_timestamp`], so the ID is never
+    /// negative even when the timestamp field reaches the sign bit.
+    pub(crate) fn pack(
+        &self,
+        timestamp: i64,
+        machine_id: i32,
+        server_id: i32,
+        sequence: usize,
+    ) -> i64 {
+        self.timestamp.pack(timestamp & self.max_timestamp())
+            | self.machine_id.pack(machine_id as i64)
+            | self.server_id.pack(server_id as i64)
+            | self.sequence.pack(self.sequence_bits(sequence) as i64)
+            | self.partition.pack(self.partition_id)
+    }
+
+    /// the layouts of the `2^bits` generators of
+    /// [`IdGenerator::split`](crate::IdGenerator::split), which store their
+    /// index in the top `bits` of this layout's sequence
+    pub(crate) fn split(&self, bits: u32) -> Result<Vec<Layout>, IdError> {
+        if bits > self.sequence.bits {
+            return Err(IdError::InvalidSplit(bits));
+        }
+
+        // the partition of an earlier split sits right above the sequence,
+        // so it grows downwards into it
+        let sequence = Field::new(self.sequence.bits - bits, self.sequence.shift);
+        let partition = Field::new(self.partition.bits + bits, sequence.shift + sequence.bits);
+
+        Ok((0..1 << bits)
+            .map(|index| Layout {
+                sequence,
+                partition,
+                partition_id: self.partition_id << bits | index,
+                ..*self
+            })
+            .collect())
+    }
+
+    /// the sequence as stored in an ID, an involution
+    fn sequence_bits(&self, sequence: usize) -> usize {
+        if self.reverse_sequence && self.sequence.bits > 0 {
+            let sequence = sequence & self.sequence.mask() as usize;
+            sequence.reverse_bits() >> (usize::BITS - self.sequence.bits)
+        } else {
+            sequence
+        }
+    }
+
+    /// the bit positions where a field starts or ends, i.e. where the bits
+    /// below belong to a different field or are unused
+    pub(crate) fn boundaries(&self) -> impl Iterator<Item = u32> {
+        [
+            self.timestamp,
+            self.machine_id,
+            self.server_id,
+            self.sequence,
+            self.partition,
+        ]
+        .into_iter()
+        .filter(|field| field.bits > 0)
+        .flat_map(|field| [field.shift, field.shift + field.bits])
+    }
+
+    /// whether the fields are disjoint and fit into 64 bits
+    #[cfg(test)]
+    fn is_well_formed(&self) -> bool {
+        let fields = [
+            self.timestamp,
+            self.machine_id,
+            self.server_id,
+            self.sequence,
+            self.partition,
+        ];
+
+        fields.iter().all(|f| f.bits + f.shift <= i64::BITS)
+            && fields.iter().enumerate().all(|(i, a)| {
+                fields[i + 1..]
+                    .iter()
+                    .all(|b| a.bits_in_id() & b.bits_in_id() == 0)
+            })
+    }
+
+    /// check that `machine_id` and `server_id` fit into their bit fields
+    pub(crate) fn validate_ids(&self, machine_id: i32, server_id: i32) -> Result<(), IdError> {
+        if !(0..=self.max_machine_id()).contains(&machine_id) {
+            return Err(IdError::InvalidMachineId(machine_id));
+        }
+
+        if !(0..=self.max_server_id()).contains(&server_id) {
+            return Err(IdError::InvalidServerId(server_id));
+        }
+
+        Ok(())
+    }
+
+    /// current timestamp in ticks since the epoch, saturating at the epoch
+    pub(crate) fn now(&self) -> i64 {
+        get_ticks(self.epoch, self.tick)
+    }
+
+    /// wait until the timestamp moves past `timestamp`
+    pub(crate) fn wait_next(&self, timestamp: i64) -> i64 {
+        bind_ticks(timestamp, self.epoch, self.tick)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::{IdGenerator, MAX_IDS_PER_MILLISECOND, MAX_MACHINE_ID, MAX_SERVER_ID};
+
+    #[test]
+    fn test_presets_are_well_formed() {
+        let presets = [
+            Layout::default(),
+            Layout::sonyflake(),
+            Layout::twitter(),
+            Layout::multi_datacenter(),
+            Layout::discord(),
+            Layout::microsecond(),
+            Layout::js_safe(),
+            Layout::hybrid_logical(),
+        ];
+
+        assert!(presets.iter().all(Layout::is_well_formed));
+        assert!(presets
+            .iter()
+            .flat_map(|layout| layout.split(2).unwrap())
+            .all(|layout| layout.is_well_formed()));
+
+        let overlapping = Layout {
+            sequence: Field::new(13, 0),
+            ..Layout::default()
+        };
+        assert!(!overlapping.is_well_formed());
+    }
+
+    #[test]
+    fn test_exhaustion_date() {
+        // 2039-09-07T15:47:35.552Z, where the 42nd bit reaches the sign bit
+        assert_eq!(
+            Layout::default().exhaustion_date(),
+            SystemTime::UNIX_EPOCH + Duration::from_millis(1 << 41)
+        );
+        assert_eq!(Layout::sonyflake().max_timestamp(), (1 << 39) - 1);
+        assert_eq!(Layout::js_safe().max_timestamp(), (1 << 41) - 1);
+    }
+
+    #[test]
+    fn test_min_max_id_at() {
+        let layout = Layout::default();
+        let time = layout.epoch() + Duration::from_millis(1_000);
+        let mut id_gen = IdGenerator::builder()
+            .machine_id(2)
+            .build_with_clock(MockClock::new(1_000))
+            .unwrap();
+        let ids = id_gen.generate_ids(10);
+
+        assert_eq!(layout.min_id_at(time), 1_000 << 22);
+        assert_eq!(layout.max_id_at(time), (1_001 << 22) - 1);
+        assert!(ids
+            .iter()
+            .all(|id| (layout.min_id_at(time)..=layout.max_id_at(time)).contains(id)));
+        assert_eq!(
+            layout.min_id_at(SystemTime::UNIX_EPOCH - Duration::from_secs(1)),
+            0
+        );
+        assert_eq!(
+            Layout::js_safe().max_id_at(SystemTime::UNIX_EPOCH + Duration::from_secs(1 << 60)),
+            (1 << 53) - 1
+        );
+    }
+
+    #[test]
+    fn test_reversed_sequence() {
+        let layout = Layout::default().with_reversed_sequence();
+        let mut id_gen = IdGenerator::builder()
+            .layout(layout)
+            .build_with_clock(MockClock::new(1_000))
+            .unwrap();
+
+        let ids = id_gen.generate_ids(MAX_IDS_PER_MILLISECOND - 1);
+        let mut low_bits: Vec<i64> = ids.iter().map(|id| id & 0xfff).collect();
+
+        assert_eq!(layout.pack(7, 0, 0, 1), 7 << 22 | 0x800);
+        assert_eq!(layout.decode(7 << 22 | 0x800).sequence, 1);
+        assert!(ids.iter().all(|&id| layout.decode(id).timestamp == 1_000));
+        assert!(ids
+            .iter()
+            .enumerate()
+            .all(|(i, &id)| layout.decode(id).sequence == i + 1));
+        assert!(!ids.windows(2).all(|w| w[0] < w[1]));
+
+        low_bits.sort();
+        low_bits.dedup();
+        assert_eq!(low_bits.len(), ids.len());
+    }
+
+    #[test]
+    fn test_pack_masks_fields() {
+        let layout = Layout::default();
+
+        assert_eq!(layout.pack(0, 0, 0, MAX_IDS_PER_MILLISECOND), 0);
+        assert_eq!(
+            layout.pack(0, MAX_MACHINE_ID + 1, 1, 0),
+            layout.pack(0, 0, 1, 0)
+        );
+    }
+
+    #[test]
+    fn test_ids_never_negative() {
+        let presets = [
+            Layout::default(),
+            Layout::sonyflake(),
+            Layout::twitter(),
+            Layout::discord(),
+            Layout::microsecond(),
+            Layout::js_safe(),
+            Layout::hybrid_logical(),
+        ];
+
+        for layout in presets {
+            let max = layout.max_timestamp();
+
+            for timestamp in [max, max + 1, i64::MAX, -1] {
+                let id = layout.pack(timestamp, -1, -1, usize::MAX);
+                assert!(id >= 0, "{:?} {}", layout, timestamp);
+            }
+            assert_eq!(layout.pack(max + 1, 0, 0, 0), 0);
+            assert!(layout.max_id_at(SystemTime::UNIX_EPOCH + Duration::from_secs(1 << 40)) > 0);
+        }
+
+        // the first millisecond past the sign bit is refused instead of wrapping
+        let clock = MockClock::new(Layout::default().max_timestamp() + 1);
+        let mut id_gen = IdGenerator::builder().build_with_clock(clock).unwrap();
+        assert_eq!(id_gen.try_generate_id(), Err(IdError::TimestampOverflow));
+    }
+
+    #[test]
+    fn test_default_layout_matches_constants() {
+        let layout = Layout::default();
+
+        assert_eq!(layout.max_machine_id(), MAX_MACHINE_ID);
+        assert_eq!(layout.max_server_id(), MAX_SERVER_ID);
+        assert_eq!(layout.sequence_capacity(), MAX_IDS_PER_MILLISECOND);
+
+        let id = layout.pack(1_000, 3, 4, 5);
+        assert_eq!(id, 1_000 << 22 | 3 << 17 | 4 << 12 | 5);
+    }
+
+    #[test]
+    fn test_sonyflake_layout() {
+        let layout = Layout::sonyflake();
+        let id = layout.pack(123_456, 0xbeef, 0, 200);
+
+        // sonyflake packs `time << 24 | sequence << 16 | machine_id`
+        assert_eq!(id, 123_456 << 24 | 200 << 16 | 0xbeef);
+        assert_eq!(
+            layout.decode(id),
+            IdParts {
+                timestamp: 123_456,
+                machine_id: 0xbeef,
+                server_id: 0,
+                sequence: 200,
+            }
+        );
+        assert_eq!(layout.max_server_id(), 0);
+        assert_eq!(layout.sequence_capacity(), 256);
+    }
+
+    #[test]
+    fn test_discord_layout_decodes_discord_ids() {
+        // example from the Discord API reference
+        let parts = Layout::discord().decode(175_928_847_299_117_063);
+
+        assert_eq!(
+            parts,
+            IdParts {
+                timestamp: 41_944_705_796,
+                machine_id: 1,
+                server_id: 0,
+                sequence: 7,
+            }
+        );
+
+        let explanation = Layout::discord().parse("175928847299117063").unwrap();
+        assert_eq!(explanation.parts, parts);
+        assert_eq!(
+            explanation.created_at,
+            SystemTime::UNIX_EPOCH + Duration::from_millis(1_462_015_105_796)
+        );
+        assert!(explanation.to_string().contains("2016-04-30T11:18:25.796Z"));
+        assert_eq!(
+            Layout::discord().parse("-1").err(),
+            Some(IdError::InvalidIdString)
+        );
+    }
+
+    #[test]
+    fn test_twitter_layout_created_at() {
+        // the tweet ID's date as shown by Twitter, 2019-12-31T19:26:16.771Z
+        let id = 1_212_092_628_029_698_048;
+
+        assert_eq!(
+            Layout::twitter().created_at(id),
+            SystemTime::UNIX_EPOCH + Duration::from_millis(1_577_820_376_771)
+        );
+    }
+
+    #[test]
+    fn test_multi_datacenter_layout() {
+        let layout = Layout::multi_datacenter();
+        let mut id_gen = IdGenerator::builder()
+            .layout(layout)
+            .datacenter_id(2)
+            .worker_id(31)
+            .build()
+            .unwrap();
+
+        let parts = layout.decode(id_gen.generate_id());
+
+        assert_eq!((parts.datacenter_id(), parts.worker_id()), (2, 31));
+        assert_eq!(layout.max_timestamp(), (1 << 41) - 1);
+        assert_eq!(
+            IdGenerator::builder()
+                .layout(layout)
+                .datacenter_id(32)
+                .build()
+                .err(),
+            Some(IdError::InvalidMachineId(32))
+        );
+    }
+
+    #[test]
+    fn test_twitter_layout_round_trip() {
+        let layout = Layout::twitter();
+        let mut id_gen = crate::IdGenerator::builder()
+            .layout(layout)
+            .machine_id(3)
+            .server_id(17)
+            .build()
+            .unwrap();
+
+        let parts = layout.decode(id_gen.generate_id());
+        let unix_ms = parts.timestamp + 1_288_834_974_657;
+
+        assert_eq!((parts.machine_id, parts.server_id), (3, 17));
+        assert!((unix_ms - get_timestamp(get_epoch())).abs() < 1_000);
+    }
+
+    #[test]
+    fn test_microsecond_layout() {
+        let layout = Layout::microsecond();
+        let mut id_gen = crate::IdGenerator::builder()
+            .layout(layout)
+            .machine_id(31)
+            .server_id(31)
+            .build()
+            .unwrap();
+
+        let ids = id_gen.generate_ids(1_000);
+        let parts = layout.decode(ids[0]);
+
+        assert!(ids.iter().all(|&id| id > 0));
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!((parts.machine_id, parts.server_id), (31, 31));
+        assert!((layout.now() - parts.timestamp) < 1_000_000);
+        assert_eq!(layout.sequence_capacity(), 4);
+    }
+
+    #[test]
+    fn test_js_safe_layout() {
+        const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+        let layout = Layout::js_safe();
+
+        let max = layout.pack((1 << 41) - 1, layout.max_machine_id(), 0, 255);
+        assert_eq!(max, MAX_SAFE_INTEGER);
+
+        let mut id_gen = crate::IdGenerator::builder()
+            .layout(layout)
+            .machine_id(15)
+            .build()
+            .unwrap();
+        let ids = id_gen.generate_ids(1_000);
+
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        assert!(ids.iter().all(|&id| 0 < id && id <= MAX_SAFE_INTEGER));
+        assert_eq!(layout.decode(ids[0]).machine_id, 15);
+        assert_eq!(layout.sequence_capacity(), 256);
+    }
+
+    #[test]
+    fn test_sonyflake_generator() {
+        let mut id_gen = crate::IdGenerator::sonyflake(0xbeef).unwrap();
+        let layout = Layout::sonyflake();
+
+        let ids: Vec<i64> = (0..1_000).map(|_| id_gen.generate_id()).collect();
+        let parts = layout.decode(ids[0]);
+
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(parts.machine_id, 0xbeef);
+        assert!(parts.timestamp <= layout.now());
+        assert!(crate::IdGenerator::sonyflake(1 << 16).is_err());
+    }
+}
@@ -1,24 +1,174 @@
-use crate::utils::*;
+use crate::clock::LayoutClock;
+use crate::event::Listener;
+use crate::rate::RateLimiter;
+use crate::refill::{BackgroundRefill, LazySource, Refill};
+use crate::rng::Rng;
+use crate::utils::to_ticks;
 use std::cmp::Ordering;
-use std::time::SystemTime;
+use std::collections::VecDeque;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use unique_id_core::{MAX_IDS_PER_MILLISECOND, MAX_MACHINE_ID, MAX_SERVER_ID};
 
+#[cfg(feature = "tokio")]
+mod async_gen;
+mod atomic;
+mod base58;
+mod base62;
+mod block;
+mod builder;
+mod check;
+mod client;
+mod clock;
+#[cfg(feature = "crossbeam")]
+mod concurrent;
+mod config;
+mod crockford;
+#[cfg(all(unix, feature = "daemon"))]
+pub mod daemon;
+mod decode;
+#[cfg(feature = "diesel")]
+mod diesel_impl;
+mod env;
+mod event;
+mod explain;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod global;
+mod hlc;
+mod host;
+mod id;
+mod id128;
+mod instrument;
+mod iter;
+pub mod ksuid;
+mod layout;
+mod local;
+mod obfuscate;
+pub mod objectid;
+mod per_core;
+mod persist;
+mod policy;
+mod pool;
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+#[cfg(feature = "python")]
+mod python;
+mod random_tail;
+mod rate;
+#[cfg(feature = "redis")]
+mod redis_counter;
+mod refill;
+mod registry;
+#[cfg(feature = "tower")]
+mod request_id;
+mod ring;
+mod rng;
+pub mod segment;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod shared;
+mod source;
+#[cfg(feature = "sqlx")]
+mod sqlx_impl;
+mod state;
+mod stats;
+mod timed_id;
+mod token;
+pub mod trace;
+pub mod ulid;
 mod utils;
+mod uuid7;
+#[cfg(feature = "wasm")]
+mod wasm;
+pub mod worker;
+pub mod xid;
+
+#[cfg(feature = "tokio")]
+pub use async_gen::AsyncIdGenerator;
+pub use atomic::{AtomicIdBlocks, AtomicIdGenerator};
+pub use block::{BlockIds, IdBlock};
+pub use builder::IdGeneratorBuilder;
+pub use check::CheckDigit;
+pub use client::{IdClient, RemoteIds};
+#[cfg(feature = "test-util")]
+pub use clock::MockClock;
+pub use clock::{ClockMode, SystemClock};
+#[cfg(feature = "crossbeam")]
+pub use concurrent::ConcurrentIdBucket;
+#[cfg(feature = "config")]
+pub use config::ConfigError;
+pub use config::{BucketConfig, GeneratorConfig, LayoutPreset};
+pub use decode::{decode, IdParts};
+pub use env::EnvError;
+pub use event::{EventListener, GeneratorEvent};
+pub use explain::IdExplanation;
+pub use hlc::HlcIdGenerator;
+pub use id::Id;
+pub use id128::{Id128Generator, Id128Tail};
+pub use iter::Ids;
+pub use layout::Layout;
+pub use local::next_id;
+pub use obfuscate::{ObfuscatedId, ObfuscationKey};
+pub use per_core::PerCoreGenerator;
+pub use persist::PersistentIdGenerator;
+pub use policy::{DriftPolicy, ExhaustionPolicy, RollbackPolicy, WaitStrategy};
+pub use pool::GeneratorPool;
+pub use random_tail::RandomTailIdGenerator;
+#[cfg(feature = "redis")]
+pub use redis_counter::RedisCounterGenerator;
+pub use registry::{GeneratorRegistry, RegisteredGenerator};
+#[cfg(feature = "tower")]
+pub use request_id::{RequestId, RequestIdLayer, RequestIdService};
+pub use ring::RingBufferGenerator;
+#[cfg(feature = "serde")]
+pub use serde_impl::id_as_string;
+pub use shared::SharedIdGenerator;
+pub use source::IdSource;
+pub use state::GeneratorState;
+pub use stats::GeneratorStats;
+pub use token::TokenEncoder;
+pub use uuid7::{Uuid7, Uuid7Generator};
+#[cfg(feature = "wasm")]
+pub use wasm::JsIdGenerator;
+pub use unique_id_core::{Clock, IdError, Sequencer};
 
 // Requirements Specification
 // 1. ID must be a 64-bit unsigned integer
 // 2. ID must be unique
 // 3. ID mut can be sorted by time
 //
-// ┌────────timestamp(42bit)──────────┬──sequence(10bit)───┬───serial(12bit)───┐
-// │                                  │                    │                   │
-// │                                  │                    │                   │
-// └──────────────────────────────────┴ total 64 bits──────┴───────────────────┘
-
-const MAX_IDS_PER_MILLISECOND: usize = 4096;
+// ┌────────timestamp(42bit)──────────┬─machine(5bit)─┬─server(5bit)─┬─sequence(12bit)─┐
+// │                                  │               │              │                 │
+// │                                  │               │              │                 │
+// └──────────────────────────────────┴──────────total 64 bits───────┴─────────────────┘
+//
+// The widths are defined once in `unique_id_core`; other layouts are described by `Layout`.
 
-#[derive(Debug, Clone, Copy)]
-pub struct IdGenerator {
-    epoch: SystemTime,
+/// Generates IDs from the time of a [`Clock`], the system clock by default.
+///
+/// A generator isn't `Clone`, as a copy would issue the same IDs as the
+/// original. Use [`IdGenerator::split`] to hand generators out to several
+/// threads.
+#[derive(Debug)]
+pub struct IdGenerator<C = SystemClock> {
+    layout: Layout,
+    clock: C,
+    rollback_policy: RollbackPolicy,
+    exhaustion_policy: ExhaustionPolicy,
+    monotonic: bool,
+    // how many ticks lazy generation may run ahead of the clock
+    max_drift: Option<i64>,
+    drift_policy: DriftPolicy,
+    wait_strategy: WaitStrategy,
+    // with a randomized sequence start: the seed of the first sequence of each tick
+    sequence_seed: Option<u64>,
+    // with a rate limit: the token bucket every issued id is taken from
+    rate_limiter: Option<RateLimiter>,
+    listener: Option<Listener>,
+    // the ids issued and the waits for the clock so far, see `stats`
+    generated: u64,
+    waits: u64,
     timestamp: i64,
     machine_id: i32,
     server_id: i32,
@@ -26,152 +176,847 @@ pub struct IdGenerator {
 }
 
 impl IdGenerator {
-    pub fn new(machine_id: i32, server_id: i32) -> Self {
-        let epoch = get_epoch();
+    /// create a generator, rejecting ids that don't fit their bit fields
+    pub fn new(machine_id: i32, server_id: i32) -> Result<Self, IdError> {
+        Self::with_layout(machine_id, server_id, Layout::default())
+    }
+
+    /// create a generator producing [Sonyflake](https://github.com/sony/sonyflake)
+    /// compatible IDs, see [`Layout::sonyflake`]
+    pub fn sonyflake(machine_id: i32) -> Result<Self, IdError> {
+        Self::with_layout(machine_id, 0, Layout::sonyflake())
+    }
+
+    /// create a generator whose machine id is taken from the lowest bits of
+    /// the primary MAC address, or of a hash of the hostname and IP address
+    /// when there is none. The server id is 0.
+    ///
+    /// Hosts whose identities share those bits get the same machine id, so
+    /// this only suits fleets small enough to check for that.
+    pub fn from_host() -> Self {
+        let layout = Layout::default();
+        let machine_id = host::machine_id(layout.max_machine_id());
+
+        Self::with_layout(machine_id, 0, layout).expect("masked machine id fits the layout")
+    }
+
+    /// create a generator using the machine id leased by `provider`
+    pub fn with_provider(
+        provider: &impl worker::WorkerIdProvider,
+        server_id: i32,
+    ) -> Result<Self, IdError> {
+        Self::new(provider.machine_id(), server_id)
+    }
+
+    /// create a builder to configure the epoch, ids and initial sequence
+    pub fn builder() -> IdGeneratorBuilder {
+        IdGeneratorBuilder::new()
+    }
 
-        Self::with_epochs(machine_id, server_id, epoch)
+    fn with_layout(machine_id: i32, server_id: i32, layout: Layout) -> Result<Self, IdError> {
+        Self::with_clock(machine_id, server_id, layout, SystemClock::default())
     }
+}
+
+impl<C: Clock> IdGenerator<C> {
+    fn with_clock(
+        machine_id: i32,
+        server_id: i32,
+        layout: Layout,
+        clock: C,
+    ) -> Result<Self, IdError> {
+        layout.validate_ids(machine_id, server_id)?;
 
-    fn with_epochs(machine_id: i32, server_id: i32, epoch: SystemTime) -> Self {
-        let timestamp = get_timestamp(epoch);
+        let timestamp = clock.ticks(&layout);
 
-        Self {
-            epoch,
+        Ok(Self {
+            layout,
+            clock,
+            rollback_policy: RollbackPolicy::default(),
+            exhaustion_policy: ExhaustionPolicy::default(),
+            monotonic: false,
+            max_drift: None,
+            drift_policy: DriftPolicy::default(),
+            wait_strategy: WaitStrategy::default(),
+            sequence_seed: None,
+            rate_limiter: None,
+            listener: None,
+            generated: 0,
+            waits: 0,
             timestamp,
             machine_id,
             server_id,
             index: 0,
-        }
+        })
+    }
+
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    /// the moment the timestamp of this generator's layout runs out, see
+    /// [`Layout::exhaustion_date`]
+    pub fn exhaustion_date(&self) -> SystemTime {
+        self.layout.exhaustion_date()
     }
 
+    /// time left until [`IdGenerator::exhaustion_date`] by this generator's
+    /// clock, zero once it has passed
+    pub fn lifetime_remaining(&self) -> Duration {
+        self.clock
+            .until_after(&self.layout, self.layout.max_timestamp())
+    }
+
+    /// split the generator into `2^new_machine_bits` generators that never
+    /// issue the same ID, e.g. one for each worker thread
+    ///
+    /// Each generator stores its index in the top `new_machine_bits` bits of
+    /// the sequence, like an extra machine id, and so issues `2^new_machine_bits`
+    /// times fewer IDs per tick. They keep the settings of this generator,
+    /// each with its own copy of the rate limit, and start at the tick after
+    /// its last ID. Fails with [`IdError::InvalidSplit`] if the sequence has
+    /// fewer bits than `new_machine_bits`.
+    pub fn split(self, new_machine_bits: u32) -> Result<Vec<Self>, IdError>
+    where
+        C: Clone,
+    {
+        let layouts = self.layout.split(new_machine_bits)?;
+
+        Ok(layouts
+            .into_iter()
+            .map(|layout| Self {
+                layout,
+                clock: self.clock.clone(),
+                rollback_policy: self.rollback_policy,
+                exhaustion_policy: self.exhaustion_policy,
+                monotonic: self.monotonic,
+                max_drift: self.max_drift,
+                drift_policy: self.drift_policy,
+                wait_strategy: self.wait_strategy,
+                sequence_seed: self.sequence_seed,
+                rate_limiter: self.rate_limiter,
+                listener: self.listener,
+                generated: 0,
+                waits: 0,
+                timestamp: self.timestamp,
+                machine_id: self.machine_id,
+                server_id: self.server_id,
+                // the next ID waits for the clock to pass the last one of
+                // this generator, as its ticks use the whole sequence
+                index: layout.sequence_capacity() - 1,
+            })
+            .collect())
+    }
+
+    /// generate a unique id, applying the exhaustion policy once the
+    /// sequence of the current millisecond runs out
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sequence is exhausted and the exhaustion policy is
+    /// [`ExhaustionPolicy::ReturnError`], while the clock reads earlier than
+    /// the epoch, or once the timestamp overflows the layout, use
+    /// [`IdGenerator::try_generate_id`] to handle those cases.
     pub fn generate_id(&mut self) -> i64 {
-        self.index = self.generalize_index(self.index);
+        self.wait_for_id().unwrap_or_else(|e| panic!("{}", e))
+    }
 
-        if self.index == 0 {
-            let mut now = get_timestamp(self.epoch);
+    /// like `generate_id`, but returns the errors it would panic with
+    pub(crate) fn wait_for_id(&mut self) -> Result<i64, IdError> {
+        loop {
+            self.wait_for_rate(1);
 
-            if now == self.timestamp {
-                now = bind_time(self.timestamp, self.epoch);
+            match self.poll_id() {
+                Ok(Some(id)) => return Ok(id),
+                Err(IdError::TimestampOverflow) => return Err(IdError::TimestampOverflow),
+                Err(e @ IdError::ClockBeforeEpoch) => return Err(e),
+                _ => {}
             }
 
-            self.timestamp = now;
+            match self.exhaustion_policy {
+                ExhaustionPolicy::SpinWait => {
+                    self.wait_next(self.timestamp);
+                }
+                ExhaustionPolicy::Sleep => {
+                    self.wait_for_clock(self.timestamp, |id_gen| {
+                        thread::sleep(id_gen.until_next_tick())
+                    });
+                }
+                ExhaustionPolicy::OverflowIntoNextMs => return self.wait_for_lazy_id(),
+                ExhaustionPolicy::ReturnError => return Err(IdError::SequenceExhausted),
+            }
         }
+    }
 
-        self.shift_bits(
-            self.timestamp, 
-            self.machine_id, 
-            self.server_id, 
-            self.index
-        )
+    /// generate a unique id without ever blocking
+    ///
+    /// Returns [`IdError::SequenceExhausted`] when the sequence of the current
+    /// millisecond has run out, unless the exhaustion policy is
+    /// [`ExhaustionPolicy::OverflowIntoNextMs`], and
+    /// [`IdError::ClockBeforeEpoch`] when the clock is read and lies before
+    /// the epoch, [`IdError::TimestampOverflow`] once the timestamp no
+    /// longer fits the layout, and [`IdError::RateLimited`] when the
+    /// [rate limit](IdGeneratorBuilder::max_ids_per_second) is reached.
+    pub fn try_generate_id(&mut self) -> Result<i64, IdError> {
+        if let Some(id) = self.poll_id()? {
+            return Ok(id);
+        }
+
+        match self.exhaustion_policy {
+            ExhaustionPolicy::OverflowIntoNextMs => self.try_generate_id_lazy(),
+            _ => Err(IdError::SequenceExhausted),
+        }
+    }
+
+    /// generate a unique id like [`IdGenerator::generate_id`], but fail with
+    /// [`IdError::Timeout`] if the clock doesn't move on within `timeout`
+    /// once the sequence of the current millisecond runs out, e.g. because
+    /// the clock froze while the VM was suspended
+    ///
+    /// This always waits for the clock, whatever the exhaustion policy.
+    pub fn generate_id_timeout(&mut self, timeout: Duration) -> Result<i64, IdError> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            self.wait_for_rate(1);
+
+            if let Some(id) = self.poll_id()? {
+                return Ok(id);
+            }
+
+            self.wait_for_clock(self.timestamp, |id_gen| {
+                instrument::spin_wait(|| {
+                    id_gen.clock.wait_next_until(
+                        &id_gen.layout,
+                        id_gen.timestamp,
+                        id_gen.wait_strategy,
+                        deadline,
+                    )
+                })
+            })
+            .ok_or(IdError::Timeout)?;
+        }
+    }
+
+    /// generate `n` ids with [`IdGenerator::generate_id`], waiting for the
+    /// next millisecond whenever the sequence runs out
+    pub fn generate_ids(&mut self, n: usize) -> Vec<i64> {
+        let mut ids = vec![0; n];
+        self.fill_ids(&mut ids);
+        ids
+    }
+
+    /// fill `out` with ids, like [`IdGenerator::generate_ids`] without allocating
+    pub fn fill_ids(&mut self, out: &mut [i64]) {
+        for id in out.iter_mut() {
+            *id = self.generate_id();
+        }
+    }
+
+    /// reserve `n` ids at once, so they are all known before any is used
+    ///
+    /// The ids are taken from the current tick and the following ones, which
+    /// start at the clock if it is ahead and run ahead of it lazily otherwise,
+    /// held back by the max drift like [`IdGenerator::generate_id_lazy`]. They
+    /// are all smaller than any id the generator issues afterwards.
+    ///
+    /// # Panics
+    ///
+    /// Panics once the timestamp overflows the layout, or when the max drift
+    /// is reached and the drift policy is [`DriftPolicy::Error`], use
+    /// [`IdGenerator::try_reserve`] to handle those cases.
+    pub fn reserve(&mut self, n: usize) -> IdBlock {
+        self.try_reserve(n).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// reserve `n` ids at once like [`IdGenerator::reserve`], but return
+    /// [`IdError::TimestampOverflow`] or [`IdError::DriftExceeded`] instead of
+    /// panicking
+    ///
+    /// The ids of the ticks already reserved before the error are skipped.
+    pub fn try_reserve(&mut self, n: usize) -> Result<IdBlock, IdError> {
+        self.wait_for_rate(n);
+
+        let mut block = IdBlock::new(self.layout, self.machine_id, self.server_id);
+        let capacity = self.layout.sequence_capacity();
+
+        while block.len() < n {
+            let mut start = self.generalize_index(self.index);
+
+            if start == 0 {
+                self.advance_tick()?;
+                start = self.first_index(self.timestamp);
+            }
+
+            let end = capacity.min(start + n - block.len());
+            block.push(self.timestamp, start..end);
+            self.index = end - 1;
+        }
+
+        instrument::ids_issued(n);
+        self.generated += n as u64;
+        self.take_rate(n);
+        Ok(block)
+    }
+
+    /// iterate over ids from [`IdGenerator::generate_id`] as [`Id`]s
+    ///
+    /// The iterator borrows the generator, so the generator can't be
+    /// accidentally copied into an iterator chain.
+    pub fn iter(&mut self) -> Ids<'_, C> {
+        Ids::new(self)
     }
 
     /// generate a unique id by using real time
+    ///
+    /// # Panics
+    ///
+    /// Panics if the clock moved backwards and the rollback policy is
+    /// [`RollbackPolicy::Error`], or once the timestamp overflows the layout,
+    /// use [`IdGenerator::try_generate_id_by_time`] to handle those cases.
     pub fn generate_id_by_time(&mut self) -> i64 {
-        self.index = self.generalize_index(self.index);
+        loop {
+            match self.try_generate_id_by_time() {
+                Err(IdError::RateLimited) => self.wait_for_rate(1),
+                result => return result.unwrap_or_else(|e| panic!("{}", e)),
+            }
+        }
+    }
+
+    /// generate a unique id by using real time, applying the rollback policy
+    /// when the clock reads earlier than the last issued timestamp
+    ///
+    /// Returns [`IdError::ClockBeforeEpoch`] instead of dating the id at the
+    /// epoch when the clock lies before it, [`IdError::TimestampOverflow`]
+    /// once the timestamp no longer fits the layout, and
+    /// [`IdError::RateLimited`] when the rate limit is reached.
+    pub fn try_generate_id_by_time(&mut self) -> Result<i64, IdError> {
+        self.check_rate(1)?;
+        let mut now = self.try_now()?;
 
-        let mut now = get_timestamp(self.epoch);
+        if now < self.timestamp {
+            self.clock_rollback(self.timestamp - now);
+
+            match self.rollback_policy {
+                // in monotonic mode a rollback is waited out instead of failing
+                RollbackPolicy::Error if !self.monotonic => {
+                    return Err(IdError::ClockRolledBack(self.timestamp - now));
+                }
+                RollbackPolicy::Error | RollbackPolicy::WaitUntilCaughtUp => {
+                    now = self.wait_next(self.timestamp - 1);
+                }
+                RollbackPolicy::BorrowFutureSequence => {
+                    return self.wait_for_lazy_id();
+                }
+            }
+        }
+
+        self.check_timestamp(now)?;
+        self.index = self.generalize_index(self.index);
 
         match now.cmp(&self.timestamp) {
             Ordering::Equal => {
                 if self.index == 0 {
-                    now = bind_time(now, self.epoch);
+                    self.sequence_rollover(now);
+                    if let Err(e) = self.check_timestamp(now + 1) {
+                        // keep the last index so it isn't issued twice
+                        self.index = self.layout.sequence_capacity() - 1;
+                        return Err(e);
+                    }
+                    now = self.wait_next(now);
                     self.timestamp = now;
+                    self.index = self.first_index(now);
                 }
             }
             _ => {
                 self.timestamp = now;
-                self.index = 0;
+                self.index = self.first_index(now);
             }
         }
 
-        self.shift_bits(
+        Ok(self.shift_bits(
             self.timestamp,
             self.machine_id,
             self.server_id,
             self.index,
-        )
+        ))
     }
 
+    /// generate a unique id without reading the clock, moving the timestamp
+    /// on by one tick whenever the sequence runs out
+    ///
+    /// With a [max drift](IdGeneratorBuilder::max_drift), the timestamp is
+    /// held back that far ahead of the clock by waiting for it, or by
+    /// panicking with [`DriftPolicy::Error`].
+    ///
+    /// # Panics
+    ///
+    /// Panics once the timestamp overflows the layout, or when the max drift
+    /// is reached and the drift policy is [`DriftPolicy::Error`].
     pub fn generate_id_lazy(&mut self) -> i64 {
-        self.index = self.generalize_index(self.index);
+        self.wait_for_lazy_id().unwrap_or_else(|e| panic!("{}", e))
+    }
 
-        if self.index == 0 {
-            self.timestamp += 1;
+    /// generate a unique id like [`IdGenerator::generate_id_lazy`] without
+    /// ever blocking
+    ///
+    /// Returns [`IdError::DriftExceeded`] instead of moving the timestamp
+    /// further than the max drift ahead of the clock, whatever the drift
+    /// policy, [`IdError::TimestampOverflow`] once the timestamp no longer
+    /// fits the layout, and [`IdError::RateLimited`] when the rate limit is
+    /// reached.
+    pub fn try_generate_id_lazy(&mut self) -> Result<i64, IdError> {
+        self.check_rate(1)?;
+
+        let mut index = self.generalize_index(self.index);
+        let rollover = index == 0;
+        let timestamp = if rollover {
+            self.timestamp + 1
+        } else {
+            self.timestamp
+        };
+
+        self.check_timestamp(timestamp)?;
+
+        if let Some(max_drift) = self.max_drift {
+            // the clock is only read when the timestamp moves
+            let drift = timestamp - self.now();
+            if rollover && drift > max_drift {
+                self.emit(GeneratorEvent::DriftExceeded { drift });
+                return Err(IdError::DriftExceeded);
+            }
         }
 
-        self.shift_bits(
+        if rollover {
+            self.sequence_rollover(self.timestamp);
+            index = self.first_index(timestamp);
+        }
+
+        self.index = index;
+        self.timestamp = timestamp;
+
+        Ok(self.shift_bits(timestamp, self.machine_id, self.server_id, index))
+    }
+
+    /// current timestamp, saturating at the epoch if the clock reads earlier
+    fn now(&self) -> i64 {
+        self.clock.ticks(&self.layout)
+    }
+
+    fn try_now(&self) -> Result<i64, IdError> {
+        self.clock.try_ticks(&self.layout)
+    }
+
+    fn wait_next(&mut self, timestamp: i64) -> i64 {
+        self.wait_for_clock(timestamp, |id_gen| {
+            instrument::spin_wait(|| {
+                id_gen
+                    .clock
+                    .wait_next(&id_gen.layout, timestamp, id_gen.wait_strategy)
+            })
+        })
+    }
+
+    /// run `wait` for the clock to move past `timestamp`, counting the wait
+    /// and telling the listener when it starts and ends
+    fn wait_for_clock<T>(&mut self, timestamp: i64, wait: impl FnOnce(&Self) -> T) -> T {
+        self.waits += 1;
+        self.emit(GeneratorEvent::WaitStarted { timestamp });
+
+        let start = Instant::now();
+        let result = wait(self);
+
+        self.emit(GeneratorEvent::WaitEnded {
+            waited: start.elapsed(),
+        });
+        result
+    }
+
+    fn emit(&self, event: GeneratorEvent) {
+        if let Some(Listener(listener)) = self.listener {
+            listener.on_event(event);
+        }
+    }
+
+    /// every sequence number of the tick `timestamp` has been issued
+    fn sequence_rollover(&self, timestamp: i64) {
+        instrument::sequence_rollover(timestamp);
+        self.emit(GeneratorEvent::SequenceRollover { timestamp });
+    }
+
+    /// the clock read `ticks` earlier than the last issued timestamp
+    fn clock_rollback(&self, ticks: i64) {
+        instrument::clock_rollback(ticks);
+        self.emit(GeneratorEvent::ClockRollback { ticks });
+    }
+
+    /// like `generate_id`, but returns `None` instead of waiting when the
+    /// sequence of the current tick is exhausted
+    pub(crate) fn poll_id(&mut self) -> Result<Option<i64>, IdError> {
+        self.check_rate(1)?;
+
+        let mut index = self.generalize_index(self.index);
+
+        if index == 0 {
+            let now = self.try_now()?;
+
+            if now <= self.timestamp {
+                self.sequence_rollover(self.timestamp);
+                return Ok(None);
+            }
+
+            self.timestamp = now;
+            index = self.first_index(now);
+        }
+
+        self.check_timestamp(self.timestamp)?;
+        self.index = index;
+
+        Ok(Some(self.shift_bits(
             self.timestamp,
             self.machine_id,
             self.server_id,
             self.index,
-        )
+        )))
+    }
+
+    /// like `generate_id_lazy`, but returns the errors it would panic with
+    fn wait_for_lazy_id(&mut self) -> Result<i64, IdError> {
+        loop {
+            match self.try_generate_id_lazy() {
+                Err(IdError::DriftExceeded) if self.drift_policy == DriftPolicy::Wait => {
+                    // the next tick is allowed once it's at most `max_drift` ahead
+                    self.wait_for_drift(self.max_drift.unwrap_or(0) - 1);
+                }
+                Err(IdError::RateLimited) => self.wait_for_rate(1),
+                result => return result,
+            }
+        }
+    }
+
+    /// move to the next tick for `reserve`: the clock if it is ahead, the
+    /// next tick within the max drift otherwise
+    fn advance_tick(&mut self) -> Result<(), IdError> {
+        let now = self.now();
+        let mut next = now.max(self.timestamp + 1);
+
+        if let Some(max_drift) = self.max_drift {
+            if next - now > max_drift {
+                self.emit(GeneratorEvent::DriftExceeded { drift: next - now });
+                match self.drift_policy {
+                    DriftPolicy::Wait => self.wait_for_drift(max_drift - 1),
+                    DriftPolicy::Error => return Err(IdError::DriftExceeded),
+                }
+                next = self.now().max(self.timestamp + 1);
+            }
+        }
+
+        self.check_timestamp(next)?;
+        self.sequence_rollover(self.timestamp);
+        self.timestamp = next;
+
+        Ok(())
+    }
+
+    /// reject a timestamp that doesn't fit the layout without reaching the sign bit
+    fn check_timestamp(&self, timestamp: i64) -> Result<(), IdError> {
+        if timestamp > self.layout.max_timestamp() {
+            return Err(IdError::TimestampOverflow);
+        }
+
+        Ok(())
+    }
+
+    /// continue after `id`, so every following id is greater than it even if
+    /// the clock reads earlier than the time `id` was issued
+    pub(crate) fn resume_after(&mut self, id: i64) {
+        let last = self.layout.decode(id);
+
+        if (last.timestamp, last.sequence) >= (self.timestamp, self.index) {
+            self.timestamp = last.timestamp;
+            self.index = last.sequence;
+        }
+    }
+
+    /// wait until the last issued timestamp is at most `max_drift` ticks ahead of the clock
+    pub(crate) fn wait_for_drift(&mut self, max_drift: i64) {
+        if self.timestamp - self.now() > max_drift {
+            self.wait_next(self.timestamp - max_drift - 1);
+        }
+    }
+
+    /// time left until the clock moves past the last issued timestamp
+    pub(crate) fn until_next_tick(&self) -> Duration {
+        self.clock.until_after(&self.layout, self.timestamp)
     }
 
     /// helper function to generate id
-    fn shift_bits(&self, timestamp: i64, machine_id: i32, server_id: i32, index: usize) -> i64 {
-        // `timestamp` is 64 bits, left shift 22 bits to make it 42 bits
-        // `machine_id` left shift 17 bits to make it 12 bits
-        // `server_id` left shift 12 bits to make it 12 bits
-        // `index` is complementing bits.
-        timestamp << 22
-        | (machine_id as i64) << 17
-        | (server_id as i64) << 12
-        | index as i64
+    fn shift_bits(&mut self, timestamp: i64, machine_id: i32, server_id: i32, index: usize) -> i64 {
+        // with the default layout, `timestamp` takes the 42 bits above bit 22,
+        // `machine_id` 5 bits from bit 17, `server_id` 5 bits from bit 12 and
+        // `index` the low 12 bits. Each value is masked to its field.
+        instrument::id_issued();
+        self.generated += 1;
+        self.take_rate(1);
+        self.layout.pack(timestamp, machine_id, server_id, index)
+    }
+
+    /// fail with [`IdError::RateLimited`] unless the rate limit admits `n`
+    /// more ids right now
+    fn check_rate(&self, n: usize) -> Result<(), IdError> {
+        match self.rate_delay(n) {
+            Duration::ZERO => Ok(()),
+            _ => Err(IdError::RateLimited),
+        }
+    }
+
+    /// sleep until the rate limit admits `n` more ids
+    fn wait_for_rate(&self, n: usize) {
+        loop {
+            match self.rate_delay(n) {
+                Duration::ZERO => break,
+                delay => thread::sleep(delay),
+            }
+        }
+    }
+
+    fn rate_delay(&self, n: usize) -> Duration {
+        self.rate_limiter
+            .map_or(Duration::ZERO, |limiter| limiter.delay(self.now_nanos(), n))
+    }
+
+    /// count `n` issued ids against the rate limit
+    fn take_rate(&mut self, n: usize) {
+        if let Some(mut limiter) = self.rate_limiter {
+            limiter.take(self.now_nanos(), n);
+            self.rate_limiter = Some(limiter);
+        }
+    }
+
+    fn now_nanos(&self) -> i128 {
+        self.clock.now_micros() as i128 * 1_000
+    }
+
+    /// the sequence a new tick starts at: 0, or with a randomized sequence
+    /// start a value derived from the seed and `timestamp` in the lower half
+    /// of the sequence, so the rest of the tick still counts up
+    fn first_index(&self, timestamp: i64) -> usize {
+        match self.sequence_seed {
+            None => 0,
+            Some(seed) => {
+                let half = (self.layout.sequence_capacity() / 2).max(1) as u64;
+                (Rng::with_seed(seed ^ timestamp as u64).next_u64() % half) as usize
+            }
+        }
+    }
+
+    /// take the max drift of a bucket's `config` and always wait for the clock
+    /// once it is reached
+    pub(crate) fn apply_bucket_config(&mut self, config: &BucketConfig) {
+        if let Some(ms) = config.max_drift_ms {
+            self.max_drift = Some(to_ticks(Duration::from_millis(ms), self.layout.tick()));
+        }
+        self.drift_policy = DriftPolicy::Wait;
     }
 
     fn generalize_index(&mut self, index: usize) -> usize {
-        // because we have 12 bits for serial number, which means we can generate 4096 ids in one millisecond
-        // so need to divide the time into 4096 parts.
-        (index + 1) % MAX_IDS_PER_MILLISECOND
+        // the sequence field decides how many ids fit into one tick, 4096
+        // for the 12 bits of the default layout
+        (index + 1) % self.layout.sequence_capacity()
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct IdGeneratorBucket {
-    id_gen: IdGenerator,
-    bucket: Vec<i64>,
+    refill: Refill,
+    // ids are taken from the front, so they come out in generation order
+    bucket: VecDeque<i64>,
+    capacity: usize,
+    refill_size: usize,
 }
 
 impl IdGeneratorBucket {
-    pub fn new(machine_id: i32, server_id: i32) -> Self {
-        let epoch = get_epoch();
-        Self::with_epochs(machine_id, server_id, epoch)
+    pub fn new(machine_id: i32, server_id: i32) -> Result<Self, IdError> {
+        let id_gen = IdGenerator::new(machine_id, server_id)?;
+
+        Self::with_config(id_gen, BucketConfig::default())
     }
 
-    fn with_epochs(machine_id: i32, server_id: i32, epoch: SystemTime) -> Self {
-        let id_gen = IdGenerator::with_epochs(machine_id, server_id, epoch);
-        let bucket = Vec::with_capacity(MAX_IDS_PER_MILLISECOND);
+    /// create a bucket whose next batch is generated on a background thread
+    /// as soon as fewer than `low_watermark` ids are left, so `get_id` doesn't
+    /// have to generate ids itself
+    pub fn with_background_refill(
+        machine_id: i32,
+        server_id: i32,
+        low_watermark: usize,
+    ) -> Result<Self, IdError> {
+        let id_gen = IdGenerator::new(machine_id, server_id)?;
+        let config = BucketConfig {
+            background_low_watermark: Some(low_watermark),
+            ..BucketConfig::default()
+        };
+
+        Self::with_config(id_gen, config)
+    }
+
+    /// create a bucket drawing lazily generated ids from `id_gen`
+    ///
+    /// The max drift of `config` replaces the generator's own, and the bucket
+    /// always waits for the clock once it is reached.
+    pub fn with_config(mut id_gen: IdGenerator, config: BucketConfig) -> Result<Self, IdError> {
+        config.validate()?;
+        id_gen.apply_bucket_config(&config);
+        let source = LazySource::new(id_gen);
+
+        let refill = match config.background_low_watermark {
+            Some(low_watermark) => Refill::Background(BackgroundRefill::spawn(
+                source,
+                low_watermark,
+                config.refill_size,
+            )),
+            None => Refill::Inline(Box::new(source)),
+        };
 
-        Self { id_gen, bucket }
+        Ok(Self {
+            refill,
+            bucket: VecDeque::with_capacity(config.capacity),
+            capacity: config.capacity,
+            refill_size: config.refill_size,
+        })
     }
 
+    /// take an id, refilling the bucket first if it is empty
+    ///
+    /// # Panics
+    ///
+    /// Panics once the timestamp overflows the layout, like
+    /// [`IdGenerator::generate_id_lazy`].
     pub fn get_id(&mut self) -> i64 {
+        self.wait_for_id().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// like `get_id`, but returns the errors it would panic with
+    pub(crate) fn wait_for_id(&mut self) -> Result<i64, IdError> {
         if self.bucket.is_empty() {
-            self.generate_ids();
+            self.refill()?;
         }
 
-        self.bucket.pop().unwrap()
+        // a refill that didn't fail added at least one id
+        let id = self.bucket.pop_front().ok_or(IdError::SequenceExhausted)?;
+        self.refill.taken(self.bucket.len(), self.next_refill_size());
+
+        Ok(id)
     }
 
-    pub fn generate_ids(&mut self) {
-        for _ in 0..MAX_IDS_PER_MILLISECOND {
-            self.bucket.push(self.id_gen.generate_id_lazy());
+    /// take an id like [`IdGeneratorBucket::get_id`] without ever blocking
+    ///
+    /// Returns [`IdError::SequenceExhausted`] when the bucket is empty and a
+    /// background refill hasn't delivered its batch yet,
+    /// [`IdError::BackendUnavailable`] once its thread has died, and
+    /// [`IdError::DriftExceeded`] when an inline refill would run further
+    /// ahead of the clock than the max drift allows.
+    pub fn try_get_id(&mut self) -> Result<i64, IdError> {
+        if self.bucket.is_empty() {
+            let n = self.next_refill_size();
+            self.refill.try_fill(&mut self.bucket, n)?;
         }
+
+        let id = self.bucket.pop_front().ok_or(IdError::SequenceExhausted)?;
+        self.refill.taken(self.bucket.len(), self.next_refill_size());
+
+        Ok(id)
+    }
+
+    /// take `n` ids at once, refilling the bucket as often as needed
+    pub fn get_ids(&mut self, n: usize) -> Vec<i64> {
+        let mut ids = vec![0; n];
+        self.fill_ids(&mut ids);
+        ids
+    }
+
+    /// fill `out` with ids, like [`IdGeneratorBucket::get_ids`] without allocating
+    pub fn fill_ids(&mut self, out: &mut [i64]) {
+        let mut filled = 0;
+
+        while filled < out.len() {
+            if self.bucket.is_empty() {
+                self.generate_ids();
+            }
+
+            let take = (out.len() - filled).min(self.bucket.len());
+            for (slot, id) in out[filled..filled + take]
+                .iter_mut()
+                .zip(self.bucket.drain(..take))
+            {
+                *slot = id;
+            }
+            filled += take;
+            self.refill.taken(self.bucket.len(), self.next_refill_size());
+        }
+    }
+
+    /// top up the bucket with up to `refill_size` ids, staying within its capacity
+    ///
+    /// # Panics
+    ///
+    /// Panics once the timestamp overflows the layout, like
+    /// [`IdGenerator::generate_id_lazy`].
+    pub fn generate_ids(&mut self) {
+        self.refill().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn refill(&mut self) -> Result<(), IdError> {
+        let n = self.next_refill_size();
+        self.refill.fill(&mut self.bucket, n)
+    }
+
+    fn next_refill_size(&self) -> usize {
+        self.refill_size.min(self.capacity - self.bucket.len())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::time::Instant;
+    use crate::clock::MockClock;
+    use crate::utils::*;
+    use crate::IdSource;
 
     const MAX_CAPACITY: usize = 10_000;
 
+    #[test]
+    fn test_timestamp_overflow() {
+        let clock = MockClock::new(Layout::default().max_timestamp());
+        let mut id_gen = IdGenerator::builder()
+            .build_with_clock(clock.clone())
+            .unwrap();
+
+        assert!(id_gen.try_generate_id_by_time().unwrap() > 0);
+        assert_eq!(id_gen.lifetime_remaining(), Duration::from_millis(1));
+
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(id_gen.lifetime_remaining(), Duration::ZERO);
+        assert_eq!(
+            id_gen.try_generate_id_by_time(),
+            Err(IdError::TimestampOverflow)
+        );
+
+        // the rest of the last millisecond can still be issued
+        for _ in 2..MAX_IDS_PER_MILLISECOND {
+            assert!(id_gen.try_generate_id().unwrap() > 0);
+        }
+        assert_eq!(id_gen.try_generate_id(), Err(IdError::TimestampOverflow));
+        assert_eq!(
+            id_gen.try_generate_id_by_time(),
+            Err(IdError::TimestampOverflow)
+        );
+    }
+
     #[test]
     fn test_id_generator_real_time() {
         let now = Instant::now();
 
-        let mut id_gen = IdGenerator::new(1, 2);
+        let mut id_gen = IdGenerator::new(1, 2).unwrap();
         let mut ids: Vec<i64> = Vec::with_capacity(MAX_CAPACITY);
 
         for _ in 0..99 {
@@ -195,7 +1040,7 @@ mod tests {
     fn test_generate_id_basic() {
         let now = Instant::now();
 
-        let mut id_gen = IdGenerator::new(1, 2);
+        let mut id_gen = IdGenerator::new(1, 2).unwrap();
         let mut ids: Vec<i64> = Vec::with_capacity(MAX_CAPACITY);
 
         for _ in 0..99 {
@@ -219,7 +1064,7 @@ mod tests {
     fn test_lazy_generate() {
         let now = Instant::now();
 
-        let mut id_gen = IdGenerator::new(1, 2);
+        let mut id_gen = IdGenerator::new(1, 2).unwrap();
         let mut ids: Vec<i64> = Vec::with_capacity(MAX_CAPACITY);
 
         for _ in 0..99 {
@@ -238,4 +1083,384 @@ mod tests {
 
         println!("time elapsed: {:?}\n", now.elapsed());
     }
+
+    #[test]
+    fn test_reject_out_of_range_ids() {
+        assert_eq!(
+            IdGenerator::new(32, 0).unwrap_err(),
+            IdError::InvalidMachineId(32)
+        );
+        assert_eq!(
+            IdGenerator::new(0, 9999).unwrap_err(),
+            IdError::InvalidServerId(9999)
+        );
+        assert_eq!(
+            IdGeneratorBucket::new(-1, 0).unwrap_err(),
+            IdError::InvalidMachineId(-1)
+        );
+        assert!(IdGenerator::new(MAX_MACHINE_ID, MAX_SERVER_ID).is_ok());
+    }
+
+    #[test]
+    fn test_from_host() {
+        let mut first = IdGenerator::from_host();
+        let second = IdGenerator::from_host();
+
+        assert_eq!(first.machine_id, second.machine_id);
+        assert_eq!(decode(first.generate_id()).machine_id, first.machine_id);
+    }
+
+    #[test]
+    fn test_generate_ids_batch() {
+        let mut id_gen = IdGenerator::new(1, 2).unwrap();
+
+        // more than one millisecond worth of sequence numbers
+        let ids = id_gen.generate_ids(MAX_IDS_PER_MILLISECOND * 2 + 10);
+
+        assert_eq!(ids.len(), MAX_IDS_PER_MILLISECOND * 2 + 10);
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+
+        let mut buf = [0; 16];
+        id_gen.fill_ids(&mut buf);
+
+        assert!(buf[0] > ids[ids.len() - 1]);
+        assert!(buf.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_bucket_returns_ids_in_order() {
+        let mut bucket = IdGeneratorBucket::new(1, 2).unwrap();
+        let ids: Vec<i64> = (0..MAX_CAPACITY).map(|_| bucket.get_id()).collect();
+
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_bucket_get_ids() {
+        let mut bucket = IdGeneratorBucket::new(1, 2).unwrap();
+
+        let first = bucket.get_id();
+        let ids = bucket.get_ids(MAX_CAPACITY);
+        let last = bucket.get_id();
+
+        assert_eq!(ids.len(), MAX_CAPACITY);
+        assert!(first < ids[0] && ids[MAX_CAPACITY - 1] < last);
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        assert!(bucket.get_ids(0).is_empty());
+
+        let mut buf = [0; 5_000];
+        bucket.fill_ids(&mut buf);
+        assert!(last < buf[0]);
+        assert!(buf.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_bucket_config_bounds_size_and_drift() {
+        let config = BucketConfig {
+            capacity: 100,
+            refill_size: 64,
+            max_drift_ms: Some(2),
+            background_low_watermark: None,
+        };
+        let mut bucket =
+            IdGeneratorBucket::with_config(IdGenerator::new(1, 2).unwrap(), config).unwrap();
+
+        bucket.generate_ids();
+        assert_eq!(bucket.bucket.len(), 64);
+        bucket.generate_ids();
+        assert_eq!(bucket.bucket.len(), 100);
+
+        let ids = bucket.get_ids(MAX_IDS_PER_MILLISECOND * 5);
+        let now = get_timestamp(get_epoch());
+        let last = decode(ids[ids.len() - 1]).timestamp;
+
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        assert!(last - now <= 2);
+    }
+
+    #[test]
+    fn test_bucket_try_get_id() {
+        let config = BucketConfig {
+            max_drift_ms: Some(0),
+            ..BucketConfig::default()
+        };
+        let mut bucket =
+            IdGeneratorBucket::with_config(IdGenerator::new(1, 2).unwrap(), config).unwrap();
+        let mut last = 0;
+
+        for _ in 0..MAX_IDS_PER_MILLISECOND * 3 {
+            match bucket.try_get_id() {
+                Ok(id) => {
+                    assert!(decode(id).timestamp <= get_timestamp(get_epoch()));
+                    assert!(id > last);
+                    last = id;
+                }
+                Err(e) => assert_eq!(e, IdError::DriftExceeded),
+            }
+        }
+    }
+
+    #[test]
+    fn test_bucket_background_refill() {
+        let mut bucket = IdGeneratorBucket::with_background_refill(1, 2, 1_024).unwrap();
+        let ids: Vec<i64> = (0..MAX_CAPACITY).map(|_| bucket.get_id()).collect();
+
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_bucket_refill_errors_instead_of_running_empty() {
+        // an epoch so far back that the timestamp has already overflowed
+        let epoch = SystemTime::UNIX_EPOCH - Duration::from_secs(20 * 365 * 86_400);
+        let overflowed = || IdGenerator::builder().epoch(epoch).build().unwrap();
+
+        let mut inline =
+            IdGeneratorBucket::with_config(overflowed(), BucketConfig::default()).unwrap();
+        assert_eq!(inline.next(), Err(IdError::TimestampOverflow));
+
+        let config = BucketConfig {
+            background_low_watermark: Some(1_024),
+            ..BucketConfig::default()
+        };
+        let mut background = IdGeneratorBucket::with_config(overflowed(), config).unwrap();
+        assert_eq!(background.next(), Err(IdError::TimestampOverflow));
+        assert!(std::panic::catch_unwind(move || background.get_id()).is_err());
+    }
+
+    fn exhausted_generator(policy: ExhaustionPolicy) -> IdGenerator {
+        let mut id_gen = IdGenerator::builder()
+            .exhaustion_policy(policy)
+            .build()
+            .unwrap();
+        // the next id would need a new millisecond
+        id_gen.index = MAX_IDS_PER_MILLISECOND - 1;
+        id_gen.timestamp = id_gen.now() + 1_000;
+        id_gen
+    }
+
+    #[test]
+    fn test_exhaustion_policy_return_error() {
+        let mut id_gen = exhausted_generator(ExhaustionPolicy::ReturnError);
+
+        assert_eq!(id_gen.try_generate_id(), Err(IdError::SequenceExhausted));
+        // a failed attempt doesn't consume the sequence
+        assert_eq!(id_gen.index, MAX_IDS_PER_MILLISECOND - 1);
+    }
+
+    #[test]
+    fn test_exhaustion_policy_overflow() {
+        let mut id_gen = exhausted_generator(ExhaustionPolicy::OverflowIntoNextMs);
+        let last = id_gen.timestamp;
+
+        let id = id_gen.try_generate_id().unwrap();
+
+        assert_eq!(decode(id).timestamp, last + 1);
+        assert_eq!(decode(id).sequence, 0);
+    }
+
+    #[test]
+    fn test_exhaustion_policy_waits() {
+        for policy in [ExhaustionPolicy::SpinWait, ExhaustionPolicy::Sleep] {
+            let mut id_gen = IdGenerator::builder()
+                .exhaustion_policy(policy)
+                .build()
+                .unwrap();
+            let ids = id_gen.generate_ids(MAX_IDS_PER_MILLISECOND * 3);
+
+            assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        }
+    }
+
+    #[test]
+    fn test_generate_id_timeout() {
+        let clock = MockClock::new(1_000);
+        let mut id_gen = IdGenerator::builder()
+            .build_with_clock(clock.clone())
+            .unwrap();
+
+        for _ in 1..MAX_IDS_PER_MILLISECOND {
+            id_gen.generate_id_timeout(Duration::ZERO).unwrap();
+        }
+
+        let start = Instant::now();
+        assert_eq!(
+            id_gen.generate_id_timeout(Duration::from_millis(20)),
+            Err(IdError::Timeout)
+        );
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(id_gen.generate_id_timeout(Duration::ZERO), Ok(1_001 << 22));
+    }
+
+    #[test]
+    fn test_reserve() {
+        let clock = MockClock::new(1_000);
+        let mut id_gen = IdGenerator::builder()
+            .machine_id(1)
+            .build_with_clock(clock.clone())
+            .unwrap();
+        let before = id_gen.generate_id();
+
+        let block = id_gen.reserve(MAX_IDS_PER_MILLISECOND * 2);
+        let ids: Vec<i64> = block.iter().collect();
+
+        assert_eq!(block.len(), ids.len());
+        assert_eq!(block.iter().len(), ids.len());
+        assert_eq!(block.first(), Some(before + 1));
+        assert_eq!(block.last(), ids.last().copied());
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(decode(block.last().unwrap()).timestamp, 1_002);
+
+        // the clock is ahead by now, so the next block starts there
+        clock.set_millis(1_010);
+        let block = id_gen.reserve(MAX_IDS_PER_MILLISECOND);
+        assert!(block.first() > ids.last().copied());
+        assert_eq!(decode(block.last().unwrap()).timestamp, 1_010);
+
+        assert!(id_gen.generate_id() > block.last().unwrap());
+        assert!(id_gen.reserve(0).is_empty());
+    }
+
+    #[test]
+    fn test_try_reserve_drift_exceeded() {
+        let mut id_gen = IdGenerator::builder()
+            .max_drift(Duration::from_millis(1))
+            .drift_policy(DriftPolicy::Error)
+            .build_with_clock(MockClock::new(1_000))
+            .unwrap();
+
+        assert!(id_gen.try_reserve(MAX_IDS_PER_MILLISECOND).is_ok());
+        assert_eq!(
+            id_gen.try_reserve(MAX_IDS_PER_MILLISECOND * 2).err(),
+            Some(IdError::DriftExceeded)
+        );
+    }
+
+    #[test]
+    fn test_split() {
+        let mut id_gen = IdGenerator::builder()
+            .build_with_clock(MockClock::new(1_000))
+            .unwrap();
+        let mut ids = id_gen.generate_ids(10);
+
+        let mut parts = id_gen.split(2).unwrap();
+        assert_eq!(parts.len(), 4);
+        let halves = parts.pop().unwrap().split(1).unwrap();
+
+        for mut part in parts.into_iter().chain(halves) {
+            let first = part.generate_id_lazy();
+            assert_eq!(decode(first).timestamp, 1_001);
+            ids.push(first);
+            ids.extend((0..MAX_IDS_PER_MILLISECOND / 4).map(|_| part.generate_id_lazy()));
+        }
+
+        let issued = ids.len();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), issued);
+
+        assert_eq!(
+            IdGenerator::new(1, 1).unwrap().split(13).err(),
+            Some(IdError::InvalidSplit(13))
+        );
+    }
+
+    #[test]
+    fn test_wait_strategies() {
+        let strategies = [
+            WaitStrategy::SpinHint,
+            WaitStrategy::YieldNow,
+            WaitStrategy::SleepMicros(100),
+            WaitStrategy::Hybrid,
+        ];
+
+        for strategy in strategies {
+            let mut id_gen = IdGenerator::builder()
+                .wait_strategy(strategy)
+                .build()
+                .unwrap();
+            let ids: Vec<i64> = (0..MAX_IDS_PER_MILLISECOND * 3)
+                .map(|_| id_gen.generate_id_by_time())
+                .collect();
+
+            assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        }
+    }
+
+    #[test]
+    fn test_clock_before_epoch() {
+        let epoch = SystemTime::now() + Duration::from_secs(3_600);
+        let layout = Layout::default().with_epoch(epoch);
+        let mut id_gen = IdGenerator::with_layout(1, 2, layout).unwrap();
+
+        assert_eq!(
+            id_gen.try_generate_id_by_time(),
+            Err(IdError::ClockBeforeEpoch)
+        );
+
+        id_gen.index = MAX_IDS_PER_MILLISECOND - 1;
+        assert_eq!(id_gen.try_generate_id(), Err(IdError::ClockBeforeEpoch));
+    }
+
+    #[test]
+    fn test_wait_for_id_clock_before_epoch() {
+        let epoch = SystemTime::UNIX_EPOCH + Duration::from_secs(3_600);
+
+        for policy in [ExhaustionPolicy::SpinWait, ExhaustionPolicy::ReturnError] {
+            let mut id_gen = IdGenerator::builder()
+                .epoch(epoch)
+                .exhaustion_policy(policy)
+                .build_with_clock(MockClock::new(1_000))
+                .unwrap();
+            id_gen.index = MAX_IDS_PER_MILLISECOND - 1;
+
+            // waiting doesn't help a clock that reads before the epoch
+            assert_eq!(id_gen.next(), Err(IdError::ClockBeforeEpoch));
+        }
+    }
+
+    fn rolled_back_generator(policy: RollbackPolicy, ahead: i64) -> IdGenerator {
+        let mut id_gen = IdGenerator::builder()
+            .rollback_policy(policy)
+            .build()
+            .unwrap();
+        // pretend the clock jumped back by `ahead` ticks after the last id
+        id_gen.timestamp = id_gen.now() + ahead;
+        id_gen
+    }
+
+    #[test]
+    fn test_rollback_policy_error() {
+        let mut id_gen = rolled_back_generator(RollbackPolicy::Error, 1_000);
+
+        assert!(matches!(
+            id_gen.try_generate_id_by_time(),
+            Err(IdError::ClockRolledBack(ticks)) if ticks > 0
+        ));
+    }
+
+    #[test]
+    fn test_rollback_policy_wait() {
+        let mut id_gen = rolled_back_generator(RollbackPolicy::WaitUntilCaughtUp, 5);
+        let last = id_gen.timestamp;
+
+        let id = id_gen.try_generate_id_by_time().unwrap();
+
+        assert!(decode(id).timestamp >= last);
+        assert!(id_gen.now() >= last);
+    }
+
+    #[test]
+    fn test_rollback_policy_borrow() {
+        let mut id_gen = rolled_back_generator(RollbackPolicy::BorrowFutureSequence, 1_000);
+        let last = id_gen.timestamp;
+
+        let ids: Vec<i64> = (0..MAX_CAPACITY)
+            .map(|_| id_gen.try_generate_id_by_time().unwrap())
+            .collect();
+
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        assert!(decode(ids[0]).timestamp >= last);
+    }
 }
@@ -0,0 +1,69 @@
+use crate::{host, GeneratorPool, IdError, SharedIdGenerator, MAX_MACHINE_ID, MAX_SERVER_ID};
+use std::sync::OnceLock;
+
+struct Generators {
+    pool: GeneratorPool,
+    // shared by the threads that find every worker id of the pool taken
+    overflow: SharedIdGenerator,
+}
+
+/// generate an ID without setting up a generator
+///
+/// Each thread lazily gets its own generator in the order threads first
+/// call this: the first 31 threads get the server ids 0 to 30, all later
+/// threads share the server id 31 behind a lock. The machine id comes from
+/// the host, see [`IdGenerator::from_host`](crate::IdGenerator::from_host).
+/// Applications running several generating processes per host should build
+/// their generators explicitly instead.
+///
+/// # Panics
+///
+/// Panics where [`IdGenerator::generate_id`](crate::IdGenerator::generate_id)
+/// does, e.g. once the timestamp overflows the layout.
+pub fn next_id() -> i64 {
+    static GENERATORS: OnceLock<Generators> = OnceLock::new();
+
+    let generators = GENERATORS.get_or_init(|| {
+        let machine_id = host::machine_id(MAX_MACHINE_ID);
+
+        Generators {
+            pool: GeneratorPool::with_server_ids(machine_id, 0..MAX_SERVER_ID)
+                .expect("host machine id fits the layout"),
+            overflow: SharedIdGenerator::new(machine_id, MAX_SERVER_ID)
+                .expect("host machine id fits the layout"),
+        }
+    });
+
+    match generators.pool.try_generate_id() {
+        Ok(id) => id,
+        Err(IdError::WorkerIdsExhausted) => generators.overflow.generate_id(),
+        Err(e) => panic!("{}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode;
+    use std::thread;
+
+    #[test]
+    fn test_next_id_across_threads() {
+        let handles: Vec<_> = (0..40)
+            .map(|_| thread::spawn(|| (0..1_000).map(|_| next_id()).collect::<Vec<_>>()))
+            .collect();
+
+        let mut ids: Vec<i64> = handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect();
+
+        assert!(ids
+            .windows(2)
+            .all(|w| decode(w[0]).machine_id == decode(w[1]).machine_id));
+
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 40_000);
+    }
+}
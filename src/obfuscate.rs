@@ -0,0 +1,182 @@
+use crate::rng::Rng;
+use crate::{base62, Id, IdError};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+const ROUNDS: usize = 8;
+
+/// The secret of an [`ObfuscatedId`] permutation.
+///
+/// The permutation is a balanced Feistel network over the two 32-bit halves
+/// of an ID, with round keys derived from the secret. It keeps outsiders from
+/// reading the creation time or counting IDs, but it is not encryption: keep
+/// anything that must stay secret out of IDs in the first place.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ObfuscationKey {
+    round_keys: [u32; ROUNDS],
+}
+
+impl ObfuscationKey {
+    pub fn new(secret: u64) -> Self {
+        let mut rng = Rng::with_seed(secret);
+
+        Self {
+            round_keys: [(); ROUNDS].map(|_| rng.next_u64() as u32),
+        }
+    }
+
+    fn permute(&self, value: u64) -> u64 {
+        let (mut left, mut right) = ((value >> 32) as u32, value as u32);
+
+        for &key in &self.round_keys {
+            (left, right) = (right, left ^ round(right, key));
+        }
+
+        (left as u64) << 32 | right as u64
+    }
+
+    fn invert(&self, value: u64) -> u64 {
+        let (mut left, mut right) = ((value >> 32) as u32, value as u32);
+
+        for &key in self.round_keys.iter().rev() {
+            (left, right) = (right ^ round(left, key), left);
+        }
+
+        (left as u64) << 32 | right as u64
+    }
+}
+
+/// keep the round keys out of logs
+impl fmt::Debug for ObfuscationKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ObfuscationKey").finish_non_exhaustive()
+    }
+}
+
+/// the round function, the murmur3 finalizer of the keyed half
+fn round(half: u32, key: u32) -> u32 {
+    let mut h = half ^ key;
+    h = (h ^ (h >> 16)).wrapping_mul(0x85eb_ca6b);
+    h = (h ^ (h >> 13)).wrapping_mul(0xc2b2_ae35);
+    h ^ (h >> 16)
+}
+
+/// An [`Id`] scrambled by a keyed permutation, for showing to the public.
+///
+/// Consecutive IDs become unrelated looking numbers, so their creation time
+/// and the volume in between can't be read off. The server recovers the
+/// original ID with [`ObfuscatedId::reveal`] and the same key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct ObfuscatedId(u64);
+
+impl ObfuscatedId {
+    pub fn new(id: Id, key: &ObfuscationKey) -> Self {
+        ObfuscatedId(key.permute(id.as_u64()))
+    }
+
+    /// recover the original ID; a different key yields a different, valid
+    /// looking ID, so don't trust the result without looking it up
+    pub fn reveal(self, key: &ObfuscationKey) -> Id {
+        Id::from(key.invert(self.0))
+    }
+
+    pub const fn as_u64(self) -> u64 {
+        self.0
+    }
+
+    /// encode as an 11 character base62 string
+    pub fn to_base62(self) -> String {
+        base62::encode(self.0)
+    }
+
+    /// parse a base62 string produced by [`ObfuscatedId::to_base62`]
+    pub fn from_base62(s: &str) -> Result<Self, IdError> {
+        base62::decode(s).map(ObfuscatedId)
+    }
+}
+
+impl Id {
+    /// scramble the ID with `key`, see [`ObfuscatedId`]
+    pub fn obfuscate(self, key: &ObfuscationKey) -> ObfuscatedId {
+        ObfuscatedId::new(self, key)
+    }
+}
+
+impl fmt::Display for ObfuscatedId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl FromStr for ObfuscatedId {
+    type Err = IdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<u64>()
+            .map(ObfuscatedId)
+            .map_err(|_| IdError::InvalidIdString)
+    }
+}
+
+impl From<u64> for ObfuscatedId {
+    fn from(id: u64) -> Self {
+        ObfuscatedId(id)
+    }
+}
+
+impl From<ObfuscatedId> for u64 {
+    fn from(id: ObfuscatedId) -> Self {
+        id.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IdGenerator;
+
+    #[test]
+    fn test_obfuscate_round_trip() {
+        let key = ObfuscationKey::new(0x5eed);
+        let ids = IdGenerator::new(1, 2).unwrap().generate_ids(1_000);
+
+        for raw in ids {
+            let id = Id::try_from(raw).unwrap();
+            let public = id.obfuscate(&key);
+
+            assert_eq!(public.reveal(&key), id);
+            assert_eq!(public.to_string().parse(), Ok(public));
+            assert_eq!(ObfuscatedId::from_base62(&public.to_base62()), Ok(public));
+        }
+
+        for raw in [0, 1, u64::MAX] {
+            assert_eq!(
+                ObfuscatedId::from(raw)
+                    .reveal(&key)
+                    .obfuscate(&key)
+                    .as_u64(),
+                raw
+            );
+        }
+    }
+
+    #[test]
+    fn test_obfuscate_hides_order() {
+        let key = ObfuscationKey::new(42);
+        let public: Vec<u64> = (0..100)
+            .map(|n| Id::from(1_000 << 22 | n).obfuscate(&key).as_u64())
+            .collect();
+
+        assert!(!public.windows(2).all(|w| w[0] < w[1]));
+        assert!(public.iter().all(|&p| p >> 22 != 1_000));
+        assert_ne!(
+            Id::from(7).obfuscate(&ObfuscationKey::new(1)),
+            Id::from(7).obfuscate(&ObfuscationKey::new(2))
+        );
+        assert_eq!(format!("{:?}", key), "ObfuscationKey { .. }");
+    }
+}
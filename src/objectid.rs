@@ -0,0 +1,151 @@
+//! [MongoDB ObjectId](https://www.mongodb.com/docs/manual/reference/method/ObjectId/)
+//! generation.
+//!
+//! An ObjectId is 12 bytes: a 32-bit timestamp in seconds since the unix
+//! epoch, a 5-byte process identifier and a 24-bit counter starting at a
+//! random value. The process identifier is made of the host identity the
+//! generators use for machine ids and the process id, like the drivers
+//! before MongoDB 3.4 did.
+
+use crate::timed_id::{TimedId, TimedIdGenerator};
+use crate::{Clock, IdError, SystemClock};
+use std::fmt;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ObjectId(TimedId);
+
+impl ObjectId {
+    pub fn from_parts(timestamp: u32, process: [u8; 5], counter: u32) -> Self {
+        ObjectId(TimedId::from_parts(timestamp, process, counter))
+    }
+
+    pub const fn from_bytes(bytes: [u8; 12]) -> Self {
+        ObjectId(TimedId::from_bytes(bytes))
+    }
+
+    /// seconds since the unix epoch
+    pub fn timestamp(&self) -> u32 {
+        self.0.timestamp()
+    }
+
+    pub fn created_at(&self) -> SystemTime {
+        self.0.created_at()
+    }
+
+    pub fn process(&self) -> [u8; 5] {
+        self.0.process()
+    }
+
+    pub fn counter(&self) -> u32 {
+        self.0.counter()
+    }
+
+    pub const fn as_bytes(&self) -> &[u8; 12] {
+        self.0.as_bytes()
+    }
+}
+
+/// the 24 lowercase hex digits Mongo shells and drivers print
+impl fmt::Display for ObjectId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_bytes()
+            .iter()
+            .try_for_each(|b| write!(f, "{:02x}", b))
+    }
+}
+
+impl FromStr for ObjectId {
+    type Err = IdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 24 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(IdError::InvalidIdString);
+        }
+
+        let mut bytes = [0u8; 12];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16)
+                .map_err(|_| IdError::InvalidIdString)?;
+        }
+
+        Ok(ObjectId::from_bytes(bytes))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ObjectIdGenerator<C = SystemClock>(TimedIdGenerator<C>);
+
+impl Default for ObjectIdGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ObjectIdGenerator {
+    pub fn new() -> Self {
+        Self::with_clock(SystemClock::default())
+    }
+}
+
+impl<C: Clock> ObjectIdGenerator<C> {
+    pub fn with_clock(clock: C) -> Self {
+        Self(TimedIdGenerator::with_clock(clock))
+    }
+
+    pub fn generate(&mut self) -> ObjectId {
+        ObjectId(self.0.generate())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timed_id::COUNTER_MASK;
+    use std::time::Duration;
+
+    #[test]
+    fn test_objectid_generate() {
+        let mut oid_gen = ObjectIdGenerator::with_clock(|| 1_700_000_000_999);
+
+        let a = oid_gen.generate();
+        let b = oid_gen.generate();
+
+        assert_eq!(a.timestamp(), 1_700_000_000);
+        assert_eq!(a.process(), b.process());
+        assert_eq!(b.counter(), (a.counter() + 1) & COUNTER_MASK);
+        assert_eq!(
+            a.created_at(),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+        );
+    }
+
+    #[test]
+    fn test_objectid_string_round_trip() {
+        // example from the MongoDB manual
+        let oid: ObjectId = "507f1f77bcf86cd799439011".parse().unwrap();
+
+        assert_eq!(oid.timestamp(), 0x507f_1f77);
+        assert_eq!(oid.process(), [0xbc, 0xf8, 0x6c, 0xd7, 0x99]);
+        assert_eq!(oid.counter(), 0x43_9011);
+        assert_eq!(oid.to_string(), "507f1f77bcf86cd799439011");
+        assert_eq!(
+            ObjectId::from_parts(oid.timestamp(), oid.process(), oid.counter()),
+            oid
+        );
+
+        for s in [
+            "507f1f77bcf86cd79943901",
+            "507f1f77bcf86cd79943901g",
+            "+07f1f77bcf86cd799439011",
+        ] {
+            assert_eq!(
+                s.parse::<ObjectId>(),
+                Err(IdError::InvalidIdString),
+                "{}",
+                s
+            );
+        }
+    }
+}
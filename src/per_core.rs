@@ -0,0 +1,157 @@
+use crate::{Clock, IdError, IdGenerator, SystemClock};
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+
+/// Generates IDs with one [`IdGenerator`] per CPU core, so threads on
+/// different cores never share a lock or a cache line.
+///
+/// The generators are [split](IdGenerator::split) off one generator and take
+/// the top bits of its sequence as their core id, so each core issues a
+/// fraction of the sequence per tick. On Linux a thread uses the generator
+/// of the core it runs on. Elsewhere threads are striped across the
+/// generators in the order they first generate an ID. Either way a thread
+/// moved to another core at the wrong moment only costs a contended lock,
+/// never a duplicate ID.
+#[derive(Debug)]
+pub struct PerCoreGenerator<C = SystemClock> {
+    shards: Vec<Shard<C>>,
+}
+
+// a generator on a cache line of its own, so cores don't invalidate each
+// other's
+#[derive(Debug)]
+#[repr(align(128))]
+struct Shard<C>(Mutex<IdGenerator<C>>);
+
+impl PerCoreGenerator {
+    /// create a generator for each core of the machine
+    pub fn new(machine_id: i32, server_id: i32) -> Result<Self, IdError> {
+        let cores = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+
+        Self::with_cores(IdGenerator::new(machine_id, server_id)?, cores)
+    }
+}
+
+impl<C: Clock + Clone> PerCoreGenerator<C> {
+    /// split `id_gen` into a generator for each of `cores` cores, rounded up
+    /// to a power of two
+    ///
+    /// Fails with [`IdError::InvalidSplit`] if the sequence of the layout
+    /// has fewer bits than that takes.
+    pub fn with_cores(id_gen: IdGenerator<C>, cores: usize) -> Result<Self, IdError> {
+        let bits = cores.max(1).next_power_of_two().trailing_zeros();
+
+        Ok(Self {
+            shards: id_gen
+                .split(bits)?
+                .into_iter()
+                .map(|id_gen| Shard(Mutex::new(id_gen)))
+                .collect(),
+        })
+    }
+
+    /// the number of generators, one for each core
+    pub fn cores(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// generate an ID with the generator of the current core, see
+    /// [`IdGenerator::generate_id`]
+    pub fn generate_id(&self) -> i64 {
+        self.lock().generate_id()
+    }
+
+    /// generate an ID with the generator of the current core, see
+    /// [`IdGenerator::try_generate_id`]
+    pub fn try_generate_id(&self) -> Result<i64, IdError> {
+        self.lock().try_generate_id()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, IdGenerator<C>> {
+        // the number of shards is a power of two
+        let shard = &self.shards[current_core() & (self.shards.len() - 1)];
+
+        // the generator state is always consistent, see `SharedIdGenerator::lock`
+        shard.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// the core the calling thread runs on, or its stripe where that is unknown
+fn current_core() -> usize {
+    #[cfg(target_os = "linux")]
+    {
+        extern "C" {
+            fn sched_getcpu() -> std::os::raw::c_int;
+        }
+
+        // SAFETY: `sched_getcpu` takes no arguments and returns -1 on errors
+        if let Ok(core) = usize::try_from(unsafe { sched_getcpu() }) {
+            return core;
+        }
+    }
+
+    thread_stripe()
+}
+
+/// a number handed to every thread in the order it first asks for one
+fn thread_stripe() -> usize {
+    static NEXT_STRIPE: AtomicUsize = AtomicUsize::new(0);
+
+    thread_local! {
+        static STRIPE: usize = NEXT_STRIPE.fetch_add(1, Ordering::Relaxed);
+    }
+
+    STRIPE.with(|stripe| *stripe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+
+    #[test]
+    fn test_per_core_generator_across_threads() {
+        let id_gen = PerCoreGenerator::new(1, 2).unwrap();
+        assert!(id_gen.cores().is_power_of_two());
+
+        let mut ids: Vec<i64> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| {
+                    scope.spawn(|| {
+                        (0..10_000)
+                            .map(|_| id_gen.generate_id())
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect()
+        });
+
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 80_000);
+    }
+
+    #[test]
+    fn test_per_core_generator_rounds_cores_up() {
+        let id_gen = || {
+            IdGenerator::builder()
+                .build_with_clock(MockClock::new(1_000))
+                .unwrap()
+        };
+
+        let per_core = PerCoreGenerator::with_cores(id_gen(), 6).unwrap();
+        assert_eq!(per_core.cores(), 8);
+
+        assert_eq!(
+            PerCoreGenerator::with_cores(id_gen(), 1 << 13).err(),
+            Some(IdError::InvalidSplit(13))
+        );
+    }
+}
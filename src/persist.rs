@@ -0,0 +1,192 @@
+use crate::utils::to_ticks;
+use crate::IdGenerator;
+use std::fs;
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// An [`IdGenerator`] that records how far it has issued IDs in a file, so a
+/// restart can't reissue IDs even if the clock went back in the meantime.
+///
+/// The file holds a timestamp the generator has not yet reached. Whenever an
+/// ID reaches it, the checkpoint is moved `interval` ahead before the ID is
+/// returned, so the file is written at most once per interval. On startup
+/// the generator issues nothing until its clock has passed the checkpoint.
+#[derive(Debug)]
+pub struct PersistentIdGenerator {
+    id_gen: IdGenerator,
+    path: PathBuf,
+    interval: i64,
+    checkpoint: i64,
+}
+
+impl PersistentIdGenerator {
+    /// resume from the checkpoint at `path`, writing a new one every second
+    pub fn open(path: impl AsRef<Path>, id_gen: IdGenerator) -> io::Result<Self> {
+        Self::with_interval(path, id_gen, Duration::from_secs(1))
+    }
+
+    /// resume from the checkpoint at `path`, writing a new one every `interval`
+    pub fn with_interval(
+        path: impl AsRef<Path>,
+        mut id_gen: IdGenerator,
+        interval: Duration,
+    ) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let checkpoint = read_checkpoint(&path)?;
+
+        if let Some(checkpoint) = checkpoint {
+            if id_gen.timestamp <= checkpoint {
+                // the next ID reads the clock and waits until it passes the checkpoint
+                id_gen.timestamp = checkpoint;
+                id_gen.index = id_gen.layout.sequence_capacity() - 1;
+            }
+        }
+
+        Ok(Self {
+            interval: to_ticks(interval, id_gen.layout.tick()).max(1),
+            id_gen,
+            path,
+            checkpoint: checkpoint.unwrap_or(-1),
+        })
+    }
+
+    /// generate an ID with [`IdGenerator::generate_id`], failing only if the
+    /// checkpoint can't be written
+    pub fn generate_id(&mut self) -> io::Result<i64> {
+        let id = self.id_gen.generate_id();
+        self.issued(id)
+    }
+
+    /// generate an ID with [`IdGenerator::generate_id_by_time`]
+    pub fn generate_id_by_time(&mut self) -> io::Result<i64> {
+        let id = self.id_gen.generate_id_by_time();
+        self.issued(id)
+    }
+
+    /// generate an ID with [`IdGenerator::generate_id_lazy`]
+    pub fn generate_id_lazy(&mut self) -> io::Result<i64> {
+        let id = self.id_gen.generate_id_lazy();
+        self.issued(id)
+    }
+
+    /// move the checkpoint ahead once `id` reaches it
+    fn issued(&mut self, id: i64) -> io::Result<i64> {
+        if self.id_gen.timestamp >= self.checkpoint {
+            let checkpoint = self.id_gen.timestamp + self.interval;
+            write_checkpoint(&self.path, checkpoint)?;
+            self.checkpoint = checkpoint;
+        }
+
+        Ok(id)
+    }
+
+    /// the timestamp recorded in the file, below which no ID will be issued after a restart
+    pub fn checkpoint(&self) -> i64 {
+        self.checkpoint
+    }
+}
+
+fn read_checkpoint(path: &Path) -> io::Result<Option<i64>> {
+    match fs::read_to_string(path) {
+        Ok(s) => s
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// replace the file atomically, so a crash leaves either the old or the new checkpoint
+fn write_checkpoint(path: &Path, checkpoint: i64) -> io::Result<()> {
+    let tmp = path.with_extension("tmp");
+
+    let mut file = fs::File::create(&tmp)?;
+    writeln!(file, "{}", checkpoint)?;
+    file.sync_all()?;
+
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode;
+    use crate::rng::Rng;
+
+    fn temp_path() -> PathBuf {
+        std::env::temp_dir().join(format!("unique_id-{:x}.checkpoint", Rng::new().next_u64()))
+    }
+
+    #[test]
+    fn test_persistent_checkpoint_written() {
+        let path = temp_path();
+        let mut id_gen =
+            PersistentIdGenerator::open(&path, IdGenerator::new(1, 2).unwrap()).unwrap();
+
+        let id = id_gen.generate_id().unwrap();
+        let stored = read_checkpoint(&path).unwrap().unwrap();
+
+        assert_eq!(stored, id_gen.checkpoint());
+        assert!(stored > decode(id).timestamp);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_persistent_restart_skips_checkpoint() {
+        let path = temp_path();
+        let mut first = PersistentIdGenerator::with_interval(
+            &path,
+            IdGenerator::new(1, 2).unwrap(),
+            Duration::from_millis(20),
+        )
+        .unwrap();
+        let last = first.generate_id().unwrap();
+        let checkpoint = first.checkpoint();
+        drop(first);
+
+        let mut second =
+            PersistentIdGenerator::open(&path, IdGenerator::new(1, 2).unwrap()).unwrap();
+        let next = second.generate_id().unwrap();
+
+        assert!(decode(next).timestamp > checkpoint);
+        assert!(next > last);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_persistent_lazy_restart() {
+        let path = temp_path();
+        let mut first = PersistentIdGenerator::with_interval(
+            &path,
+            IdGenerator::new(1, 2).unwrap(),
+            Duration::from_millis(20),
+        )
+        .unwrap();
+        // run the lazy timestamp well ahead of the clock
+        let last = (0..200_000)
+            .map(|_| first.generate_id_lazy().unwrap())
+            .last()
+            .unwrap();
+        drop(first);
+
+        let mut second =
+            PersistentIdGenerator::open(&path, IdGenerator::new(1, 2).unwrap()).unwrap();
+
+        assert!(second.generate_id_lazy().unwrap() > last);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_persistent_rejects_corrupt_checkpoint() {
+        let path = temp_path();
+        fs::write(&path, "not a number").unwrap();
+
+        let err = PersistentIdGenerator::open(&path, IdGenerator::new(1, 2).unwrap()).unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,86 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::hint::spin_loop;
+use std::thread;
+use std::time::Duration;
+
+// `WaitStrategy::Hybrid` spins for this many reads of the clock, then yields
+// until `HYBRID_YIELDS` reads and sleeps after that
+const HYBRID_SPINS: u32 = 64;
+const HYBRID_YIELDS: u32 = 128;
+const HYBRID_SLEEP: Duration = Duration::from_micros(50);
+
+/// What a real-time generator does when the clock reads earlier than the
+/// last issued timestamp, e.g. after an NTP step.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RollbackPolicy {
+    /// return [`IdError::ClockRolledBack`](crate::IdError::ClockRolledBack),
+    /// or wait like `WaitUntilCaughtUp` for a
+    /// [monotonic](crate::IdGeneratorBuilder::monotonic) generator
+    Error,
+    /// spin until the clock is back at the last issued timestamp
+    #[default]
+    WaitUntilCaughtUp,
+    /// keep issuing from the last timestamp, moving into future ticks once
+    /// the sequence is exhausted, like `generate_id_lazy`
+    BorrowFutureSequence,
+}
+
+/// What [`IdGenerator::generate_id`](crate::IdGenerator::generate_id) does once
+/// every sequence number of the current millisecond has been issued.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExhaustionPolicy {
+    /// spin until the next millisecond
+    #[default]
+    SpinWait,
+    /// put the thread to sleep until the next millisecond
+    Sleep,
+    /// fail with [`IdError::SequenceExhausted`](crate::IdError::SequenceExhausted)
+    ReturnError,
+    /// continue in the next millisecond right away, dating the id slightly
+    /// into the future, at most by the generator's max drift
+    OverflowIntoNextMs,
+}
+
+/// What lazy generation does once its timestamp would run further ahead of
+/// the clock than the generator's max drift.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DriftPolicy {
+    /// wait until the clock is close enough again, like real-time generation
+    #[default]
+    Wait,
+    /// fail with [`IdError::DriftExceeded`](crate::IdError::DriftExceeded)
+    Error,
+}
+
+/// How a generator waits for the clock to move on, e.g. once the sequence of
+/// the current millisecond is used up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum WaitStrategy {
+    /// busy-wait with a spin loop hint, the lowest latency but a full core
+    #[default]
+    SpinHint,
+    /// give the rest of the time slice to other threads between reads
+    YieldNow,
+    /// sleep this many microseconds between reads
+    SleepMicros(u64),
+    /// spin briefly, then yield, then sleep, so short waits stay fast and
+    /// long ones don't burn a core
+    Hybrid,
+}
+
+impl WaitStrategy {
+    /// back off once after `attempt` earlier reads of the clock
+    pub(crate) fn wait(self, attempt: u32) {
+        match self {
+            WaitStrategy::SpinHint => spin_loop(),
+            WaitStrategy::YieldNow => thread::yield_now(),
+            WaitStrategy::SleepMicros(micros) => thread::sleep(Duration::from_micros(micros)),
+            WaitStrategy::Hybrid if attempt < HYBRID_SPINS => spin_loop(),
+            WaitStrategy::Hybrid if attempt < HYBRID_YIELDS => thread::yield_now(),
+            WaitStrategy::Hybrid => thread::sleep(HYBRID_SLEEP),
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use crate::{IdError, IdGenerator};
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Hands every thread its own [`IdGenerator`], so threads never contend on
+/// a lock.
+///
+/// All generators share the pool's machine id and each takes one of the
+/// server ids as its worker id, so a pool serves up to 32 threads at a time.
+/// When a thread exits its generator goes back to the pool and the next
+/// thread continues its sequence, so a reused worker id can't repeat IDs.
+#[derive(Debug, Clone)]
+pub struct GeneratorPool {
+    inner: Arc<PoolInner>,
+}
+
+#[derive(Debug)]
+struct PoolInner {
+    id: u64,
+    free: Mutex<Vec<IdGenerator>>,
+}
+
+/// a generator leased by the current thread, returned to its pool when the thread exits
+struct Lease {
+    pool: Arc<PoolInner>,
+    id_gen: Option<IdGenerator>,
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        if let Some(id_gen) = self.id_gen.take() {
+            self.pool.lock().push(id_gen);
+        }
+    }
+}
+
+thread_local! {
+    static LEASES: RefCell<Vec<Lease>> = const { RefCell::new(Vec::new()) };
+}
+
+impl GeneratorPool {
+    pub fn new(machine_id: i32) -> Result<Self, IdError> {
+        Self::with_server_ids(machine_id, 0..=crate::MAX_SERVER_ID)
+    }
+
+    /// create a pool whose threads take their worker ids from `server_ids`
+    pub(crate) fn with_server_ids(
+        machine_id: i32,
+        server_ids: impl DoubleEndedIterator<Item = i32>,
+    ) -> Result<Self, IdError> {
+        static NEXT_POOL_ID: AtomicU64 = AtomicU64::new(0);
+
+        // reversed so threads take the server ids in order
+        let free = server_ids
+            .rev()
+            .map(|server_id| IdGenerator::new(machine_id, server_id))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            inner: Arc::new(PoolInner {
+                id: NEXT_POOL_ID.fetch_add(1, Ordering::Relaxed),
+                free: Mutex::new(free),
+            }),
+        })
+    }
+
+    /// generate an ID with the calling thread's generator
+    ///
+    /// # Panics
+    ///
+    /// Panics if every worker id is taken by another thread, or where
+    /// [`IdGenerator::generate_id`] does, use
+    /// [`GeneratorPool::try_generate_id`] to handle those cases.
+    pub fn generate_id(&self) -> i64 {
+        self.try_generate_id().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// generate an ID with the calling thread's generator, or return
+    /// [`IdError::WorkerIdsExhausted`] if this thread has none and every
+    /// worker id is taken
+    ///
+    /// Fails with the errors [`IdGenerator::generate_id`] would panic with
+    /// otherwise.
+    pub fn try_generate_id(&self) -> Result<i64, IdError> {
+        LEASES.with(|leases| {
+            let mut leases = leases.borrow_mut();
+
+            let index = match leases.iter().position(|l| l.pool.id == self.inner.id) {
+                Some(index) => index,
+                None => {
+                    let id_gen = self.inner.lock().pop().ok_or(IdError::WorkerIdsExhausted)?;
+                    leases.push(Lease {
+                        pool: self.inner.clone(),
+                        id_gen: Some(id_gen),
+                    });
+                    leases.len() - 1
+                }
+            };
+
+            let id_gen = leases[index].id_gen.as_mut().expect("leased generator");
+            id_gen.wait_for_id()
+        })
+    }
+}
+
+impl PoolInner {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<IdGenerator>> {
+        // the free list is always consistent, see `SharedIdGenerator::lock`
+        self.free.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode;
+    use std::sync::Barrier;
+    use std::thread;
+
+    #[test]
+    fn test_pool_threads_get_distinct_workers() {
+        let pool = GeneratorPool::new(7).unwrap();
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let pool = pool.clone();
+                thread::spawn(move || (0..10_000).map(|_| pool.generate_id()).collect::<Vec<_>>())
+            })
+            .collect();
+
+        let mut ids: Vec<i64> = handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect();
+
+        assert!(ids.iter().all(|&id| decode(id).machine_id == 7));
+
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 80_000);
+    }
+
+    #[test]
+    fn test_pool_reuses_released_workers() {
+        let pool = GeneratorPool::new(0).unwrap();
+        let first = {
+            let pool = pool.clone();
+            thread::spawn(move || pool.generate_id()).join().unwrap()
+        };
+        let second = {
+            let pool = pool.clone();
+            thread::spawn(move || pool.generate_id()).join().unwrap()
+        };
+
+        assert_eq!(decode(first).server_id, decode(second).server_id);
+        assert!(second > first);
+    }
+
+    #[test]
+    fn test_pool_exhausted() {
+        let pool = GeneratorPool::new(0).unwrap();
+        let barrier = Arc::new(Barrier::new(33));
+
+        let results: Vec<_> = (0..33)
+            .map(|_| {
+                let (pool, barrier) = (pool.clone(), barrier.clone());
+                thread::spawn(move || {
+                    let result = pool.try_generate_id();
+                    // keep every lease until all threads have tried
+                    barrier.wait();
+                    result
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .collect();
+
+        let exhausted = results.iter().filter(|r| r.is_err()).count();
+        assert_eq!(exhausted, 1);
+    }
+}
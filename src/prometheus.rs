@@ -0,0 +1,103 @@
+//! A `/metrics` endpoint in the Prometheus text format for the metrics the
+//! generators record with the `metrics` feature, such as
+//! `unique_id_ids_issued_total`, `unique_id_waits_total` and
+//! `unique_id_spin_wait_seconds`, so a process issuing IDs can be scraped
+//! like any other service.
+
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::Duration;
+
+/// how long a client may take to send its request or read the response
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A background thread serving `GET /metrics`.
+#[derive(Debug)]
+pub struct MetricsServer {
+    addr: SocketAddr,
+}
+
+impl MetricsServer {
+    /// install a Prometheus recorder as the global `metrics` recorder and
+    /// serve it on `addr`
+    ///
+    /// Fails if another global recorder is already installed.
+    pub fn serve(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let handle = PrometheusBuilder::new()
+            .install_recorder()
+            .map_err(io::Error::other)?;
+        let addr = listener.local_addr()?;
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let handle = handle.clone();
+
+                // a slow client or one hanging up early only affects its own
+                // response
+                thread::spawn(move || respond(stream, &handle));
+            }
+        });
+
+        Ok(Self { addr })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+fn respond(mut stream: TcpStream, handle: &PrometheusHandle) -> io::Result<()> {
+    // so a client that never sends its request doesn't keep a thread forever
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+
+    let (status, body) = match request_line.split_whitespace().take(2).collect::<Vec<_>>()[..] {
+        ["GET", "/metrics"] => ("200 OK", handle.render()),
+        _ => ("404 Not Found", String::new()),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IdGenerator;
+    use std::io::Read;
+
+    fn get(addr: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_metrics_endpoint() {
+        let server = MetricsServer::serve("127.0.0.1:0").unwrap();
+        IdGenerator::new(1, 2).unwrap().generate_ids(10);
+
+        // a client that never sends a request doesn't hold up the others
+        let _silent = TcpStream::connect(server.local_addr()).unwrap();
+
+        let metrics = get(server.local_addr(), "/metrics");
+        assert!(metrics.starts_with("HTTP/1.1 200 OK"));
+        assert!(metrics.contains("unique_id_ids_issued_total"));
+
+        assert!(get(server.local_addr(), "/").starts_with("HTTP/1.1 404"));
+    }
+}
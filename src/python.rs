@@ -0,0 +1,64 @@
+//! Python bindings, built with `maturin build --features python`.
+//!
+//! ```python
+//! import unique_id
+//!
+//! id_gen = unique_id.IdGenerator(1, 2)
+//! timestamp, machine_id, server_id, sequence = unique_id.decode(id_gen.generate_id())
+//! ```
+
+use crate::{decode, IdError, IdGenerator};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+/// The generator exported to Python as `unique_id.IdGenerator`.
+#[pyclass(name = "IdGenerator")]
+#[derive(Debug)]
+struct PyIdGenerator {
+    id_gen: IdGenerator,
+}
+
+#[pymethods]
+impl PyIdGenerator {
+    #[new]
+    fn new(machine_id: i32, server_id: i32) -> PyResult<Self> {
+        IdGenerator::new(machine_id, server_id)
+            .map(|id_gen| Self { id_gen })
+            .map_err(value_error)
+    }
+
+    // errors are raised as `ValueError`, not as a panic, which Python code
+    // can't catch as an `Exception`
+    fn generate_id(&mut self) -> PyResult<i64> {
+        self.id_gen.wait_for_id().map_err(value_error)
+    }
+
+    fn generate_ids(&mut self, n: usize) -> PyResult<Vec<i64>> {
+        (0..n)
+            .map(|_| self.id_gen.wait_for_id().map_err(value_error))
+            .collect()
+    }
+}
+
+fn value_error(e: IdError) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+/// split an ID into `(timestamp, machine_id, server_id, sequence)`
+#[pyfunction(name = "decode")]
+fn py_decode(id: i64) -> (i64, i32, i32, usize) {
+    let parts = decode(id);
+    (
+        parts.timestamp,
+        parts.machine_id,
+        parts.server_id,
+        parts.sequence,
+    )
+}
+
+#[pymodule]
+fn unique_id(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyIdGenerator>()?;
+    m.add_function(wrap_pyfunction!(py_decode, m)?)?;
+    Ok(())
+}
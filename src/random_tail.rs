@@ -0,0 +1,133 @@
+use crate::rng::Rng;
+use crate::{IdError, Layout, MAX_IDS_PER_MILLISECOND};
+
+/// Generates IDs like [`IdGenerator`](crate::IdGenerator), but with a random
+/// sequence instead of a counter, so IDs don't reveal how many were issued
+/// in a millisecond.
+///
+/// A value already used in the current millisecond is drawn again. IDs are
+/// still sorted by millisecond but not within one.
+#[derive(Debug, Clone)]
+pub struct RandomTailIdGenerator {
+    layout: Layout,
+    machine_id: i32,
+    server_id: i32,
+    timestamp: i64,
+    // one bit for each sequence value issued in `timestamp`
+    used: [u64; MAX_IDS_PER_MILLISECOND / 64],
+    issued: usize,
+    rng: Rng,
+}
+
+impl RandomTailIdGenerator {
+    pub fn new(machine_id: i32, server_id: i32) -> Result<Self, IdError> {
+        let layout = Layout::default();
+        layout.validate_ids(machine_id, server_id)?;
+
+        Ok(Self {
+            layout,
+            machine_id,
+            server_id,
+            timestamp: -1,
+            used: [0; MAX_IDS_PER_MILLISECOND / 64],
+            issued: 0,
+            rng: Rng::new(),
+        })
+    }
+
+    /// generate an ID, waiting for the next millisecond once all 4096
+    /// sequence values of the current one are used
+    ///
+    /// # Panics
+    ///
+    /// Panics once the timestamp overflows the layout, use
+    /// [`RandomTailIdGenerator::try_generate_id`] to handle that case.
+    pub fn generate_id(&mut self) -> i64 {
+        self.try_generate_id().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// generate an ID like [`RandomTailIdGenerator::generate_id`], or return
+    /// [`IdError::TimestampOverflow`] once the timestamp no longer fits the
+    /// layout
+    pub fn try_generate_id(&mut self) -> Result<i64, IdError> {
+        let mut now = self.layout.now();
+
+        // after the clock moved back, keep drawing from the last issued millisecond
+        if now <= self.timestamp && self.issued == MAX_IDS_PER_MILLISECOND {
+            now = self.layout.wait_next(self.timestamp);
+        }
+
+        if now > self.timestamp {
+            if now > self.layout.max_timestamp() {
+                return Err(IdError::TimestampOverflow);
+            }
+
+            self.timestamp = now;
+            self.used = [0; MAX_IDS_PER_MILLISECOND / 64];
+            self.issued = 0;
+        }
+
+        let sequence = loop {
+            let sequence = self.rng.next_u64() as usize % MAX_IDS_PER_MILLISECOND;
+            let (word, bit) = (sequence / 64, 1 << (sequence % 64));
+
+            if self.used[word] & bit == 0 {
+                self.used[word] |= bit;
+                break sequence;
+            }
+        };
+        self.issued += 1;
+
+        Ok(self
+            .layout
+            .pack(self.timestamp, self.machine_id, self.server_id, sequence))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_random_tail_unique() {
+        let mut id_gen = RandomTailIdGenerator::new(3, 4).unwrap();
+
+        let mut ids: Vec<i64> = (0..MAX_IDS_PER_MILLISECOND * 3)
+            .map(|_| id_gen.generate_id())
+            .collect();
+
+        assert!(ids
+            .windows(2)
+            .all(|w| decode(w[0]).timestamp <= decode(w[1]).timestamp));
+        assert!(ids.iter().all(|&id| decode(id).machine_id == 3));
+
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), MAX_IDS_PER_MILLISECOND * 3);
+    }
+
+    #[test]
+    fn test_random_tail_is_not_a_counter() {
+        let mut id_gen = RandomTailIdGenerator::new(0, 0).unwrap();
+        let sequences: Vec<usize> = (0..16)
+            .map(|_| decode(id_gen.generate_id()).sequence)
+            .collect();
+
+        assert!(sequences.windows(2).any(|w| w[0] + 1 != w[1]));
+    }
+
+    #[test]
+    fn test_random_tail_timestamp_overflow() {
+        // an epoch so far back that the timestamp has already overflowed
+        let epoch = SystemTime::UNIX_EPOCH - Duration::from_secs(20 * 365 * 86_400);
+        let mut id_gen = RandomTailIdGenerator {
+            layout: Layout::default().with_epoch(epoch),
+            ..RandomTailIdGenerator::new(0, 0).unwrap()
+        };
+
+        assert_eq!(id_gen.try_generate_id(), Err(IdError::TimestampOverflow));
+        assert!(std::panic::catch_unwind(move || id_gen.generate_id()).is_err());
+    }
+}
@@ -0,0 +1,67 @@
+use std::num::NonZeroU32;
+use std::time::Duration;
+
+// the burst a limiter admits at once: one second worth of ids
+const BURST_NANOS: i128 = 1_000_000_000;
+
+/// A token bucket admitting a number of IDs per second, in bursts of up to
+/// one second's worth.
+///
+/// This is the generic cell rate algorithm: instead of counting tokens it
+/// keeps the time at which the bucket is full again, so it needs no timer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RateLimiter {
+    // the nanoseconds one id takes out of the bucket
+    cost: i128,
+    // when the bucket is full again, in nanoseconds since the unix epoch
+    full_at: i128,
+}
+
+impl RateLimiter {
+    pub fn per_second(rate: NonZeroU32) -> Self {
+        Self {
+            cost: (BURST_NANOS / rate.get() as i128).max(1),
+            full_at: i128::MIN,
+        }
+    }
+
+    /// how long to wait from `now` until `n` ids may be taken
+    pub fn delay(&self, now: i128, n: usize) -> Duration {
+        let cost = self.cost * n as i128;
+        // a batch larger than the burst is admitted once the bucket is full
+        let burst = BURST_NANOS.max(cost);
+        let full_at = self.full_at.max(now) + cost;
+
+        Duration::from_nanos((full_at - now - burst).max(0) as u64)
+    }
+
+    /// take `n` issued ids out of the bucket
+    pub fn take(&mut self, now: i128, n: usize) {
+        self.full_at = self.full_at.max(now) + self.cost * n as i128;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: i128 = 1_000_000_000;
+
+    #[test]
+    fn test_rate_limiter_bursts_then_refills() {
+        let mut limiter = RateLimiter::per_second(NonZeroU32::new(4).unwrap());
+        let now = 1_000 * SECOND;
+
+        for _ in 0..4 {
+            assert_eq!(limiter.delay(now, 1), Duration::ZERO);
+            limiter.take(now, 1);
+        }
+        assert_eq!(limiter.delay(now, 1), Duration::from_millis(250));
+        assert_eq!(limiter.delay(now + SECOND / 4, 1), Duration::ZERO);
+        assert_eq!(limiter.delay(now + SECOND, 4), Duration::ZERO);
+
+        // a batch beyond the burst waits for a full bucket
+        assert_eq!(limiter.delay(now, 10), Duration::from_secs(1));
+        assert_eq!(limiter.delay(now + SECOND, 10), Duration::ZERO);
+    }
+}
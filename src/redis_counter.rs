@@ -0,0 +1,157 @@
+use crate::worker::{redis_connect, redis_connection};
+use redis::{Client, Connection, ErrorKind, RedisError, RedisResult};
+use std::fmt;
+use std::num::NonZeroU32;
+use std::ops::Range;
+use std::time::Duration;
+
+/// Hands out IDs from a Redis counter, for when the clocks can't be trusted
+/// to order snowflake IDs.
+///
+/// Every generator sharing `key` reserves a block of `block` values at a
+/// time with a single `INCRBY`, and hands them out locally, so the IDs are
+/// unique across all of them and ascending within one. The counter starts
+/// at 1, and IDs left in the block of a dropped generator are never used.
+/// The counters sort far below snowflake IDs, so don't mix both in one
+/// column.
+///
+/// A request Redis doesn't answer within the [timeout](Self::timeout)
+/// fails, and the next one connects again. A block whose reply was lost
+/// that way is skipped, so no ID is ever handed out twice.
+pub struct RedisCounterGenerator {
+    client: Client,
+    timeout: Duration,
+    // none after an error, until the next request connects again
+    conn: Option<Connection>,
+    key: String,
+    block: NonZeroU32,
+    cached: Range<i64>,
+}
+
+impl RedisCounterGenerator {
+    /// create a generator for the counter `key` on the server at `url`, with
+    /// a timeout of 5 seconds
+    pub fn new(url: &str, key: &str, block: NonZeroU32) -> RedisResult<Self> {
+        let client = Client::open(url)?;
+        let timeout = Duration::from_secs(5);
+        let conn = redis_connect(&client, timeout)?;
+
+        Ok(Self {
+            client,
+            timeout,
+            conn: Some(conn),
+            key: key.to_string(),
+            block,
+            cached: 0..0,
+        })
+    }
+
+    /// set how long to wait for Redis to take or answer a request (defaults
+    /// to 5 seconds)
+    pub fn timeout(mut self, timeout: Duration) -> RedisResult<Self> {
+        self.timeout = timeout;
+        if let Some(conn) = &self.conn {
+            conn.set_read_timeout(Some(timeout))?;
+            conn.set_write_timeout(Some(timeout))?;
+        }
+
+        Ok(self)
+    }
+
+    /// the next id, reserving a new block when the cached one is used up
+    pub fn next_id(&mut self) -> RedisResult<i64> {
+        if self.cached.is_empty() {
+            self.cached = self.reserve().inspect_err(|_| self.conn = None)?;
+        }
+
+        Ok(self.cached.next().expect("the block is not empty"))
+    }
+
+    fn reserve(&mut self) -> RedisResult<Range<i64>> {
+        let block = self.block.get() as i64;
+        let conn = redis_connection(&self.client, self.timeout, &mut self.conn)?;
+        let end: i64 = redis::cmd("INCRBY").arg(&self.key).arg(block).query(conn)?;
+
+        // Redis itself refuses to increment past `i64::MAX`
+        match end.checked_add(1) {
+            Some(next) => Ok(end - block + 1..next),
+            None => Err(RedisError::from((
+                ErrorKind::ResponseError,
+                "the counter reached the largest id",
+            ))),
+        }
+    }
+}
+
+// `redis::Connection` isn't `Debug`
+impl fmt::Debug for RedisCounterGenerator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedisCounterGenerator")
+            .field("client", &self.client)
+            .field("timeout", &self.timeout)
+            .field("key", &self.key)
+            .field("block", &self.block)
+            .field("cached", &self.cached)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    fn redis_url() -> String {
+        std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1/".to_string())
+    }
+
+    #[test]
+    fn test_redis_counter_reconnects_after_errors() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+        use std::thread;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("redis://{}/", listener.local_addr().unwrap());
+        // no answer, then a counter at the largest id, then a working one
+        let server = thread::spawn(move || {
+            let mut buf = [0; 1_024];
+            let mut hung = listener.accept().unwrap().0;
+            assert!(hung.read(&mut buf).unwrap() > 0);
+
+            for reply in [format!(":{}\r\n", i64::MAX), ":10\r\n".to_string()] {
+                let (mut stream, _) = listener.accept().unwrap();
+                assert!(stream.read(&mut buf).unwrap() > 0);
+                stream.write_all(reply.as_bytes()).unwrap();
+            }
+        });
+
+        let mut counter = RedisCounterGenerator::new(&url, "test", NonZeroU32::new(10).unwrap())
+            .unwrap()
+            .timeout(Duration::from_millis(50))
+            .unwrap();
+
+        assert!(counter.next_id().is_err());
+        assert_eq!(
+            counter.next_id().unwrap_err().kind(),
+            ErrorKind::ResponseError
+        );
+        assert_eq!(counter.next_id().unwrap(), 1);
+        server.join().unwrap();
+    }
+
+    #[test]
+    #[ignore = "needs a redis server at REDIS_URL"]
+    fn test_redis_counters_are_unique() {
+        let key = format!("unique_id:test:{:x}", Rng::new().next_u64());
+        let block = NonZeroU32::new(10).unwrap();
+
+        let mut first = RedisCounterGenerator::new(&redis_url(), &key, block).unwrap();
+        let mut second = RedisCounterGenerator::new(&redis_url(), &key, block).unwrap();
+
+        assert_eq!(first.next_id().unwrap(), 1);
+        assert_eq!(second.next_id().unwrap(), 11);
+        let ids: Vec<i64> = (0..15).map(|_| first.next_id().unwrap()).collect();
+        assert_eq!(ids, (2..11).chain(21..27).collect::<Vec<_>>());
+    }
+}
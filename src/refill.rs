@@ -0,0 +1,195 @@
+use crate::{IdError, IdGenerator};
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError};
+use std::thread;
+
+/// Lazy generation, held back by the max drift of the generator.
+#[derive(Debug)]
+pub(crate) struct LazySource {
+    id_gen: IdGenerator,
+}
+
+impl LazySource {
+    pub fn new(id_gen: IdGenerator) -> Self {
+        Self { id_gen }
+    }
+
+    /// append `n` ids to `bucket`, failing only if not a single id could be
+    /// generated, i.e. once the timestamp overflows the layout
+    fn generate_batch(&mut self, bucket: &mut VecDeque<i64>, n: usize) -> Result<(), IdError> {
+        for i in 0..n {
+            match self.id_gen.wait_for_lazy_id() {
+                Ok(id) => bucket.push_back(id),
+                Err(e) if i == 0 => return Err(e),
+                Err(_) => break,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// like `generate_batch`, but stops instead of waiting for the clock,
+    /// failing only if not a single id could be generated
+    fn try_generate_batch(&mut self, bucket: &mut VecDeque<i64>, n: usize) -> Result<(), IdError> {
+        for i in 0..n {
+            match self.id_gen.try_generate_id_lazy() {
+                Ok(id) => bucket.push_back(id),
+                Err(e) if i == 0 => return Err(e),
+                Err(_) => break,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// How an [`IdGeneratorBucket`](crate::IdGeneratorBucket) gets new IDs.
+#[derive(Debug)]
+pub(crate) enum Refill {
+    /// generate on the calling thread when the bucket runs empty
+    Inline(Box<LazySource>),
+    /// let a background thread generate the next batch ahead of time
+    Background(BackgroundRefill),
+}
+
+impl Refill {
+    /// append up to `n` ids to `bucket`, at least one unless it fails
+    pub fn fill(&mut self, bucket: &mut VecDeque<i64>, n: usize) -> Result<(), IdError> {
+        match self {
+            Refill::Inline(source) => source.generate_batch(bucket, n),
+            Refill::Background(background) => background.fill(bucket, n),
+        }
+    }
+
+    /// append up to `n` ids to `bucket` without blocking
+    pub fn try_fill(&mut self, bucket: &mut VecDeque<i64>, n: usize) -> Result<(), IdError> {
+        match self {
+            Refill::Inline(source) => source.try_generate_batch(bucket, n),
+            Refill::Background(background) => background.try_fill(bucket, n),
+        }
+    }
+
+    /// called after ids were taken out of a bucket that now holds
+    /// `remaining` ids and has room for `n` more
+    pub fn taken(&mut self, remaining: usize, n: usize) {
+        if let Refill::Background(background) = self {
+            if remaining < background.low_watermark {
+                background.request(n);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct BackgroundRefill {
+    low_watermark: usize,
+    pending: bool,
+    requests: SyncSender<usize>,
+    batches: Receiver<Result<VecDeque<i64>, IdError>>,
+}
+
+impl BackgroundRefill {
+    /// move `source` to a new thread that generates a batch on every request
+    ///
+    /// The thread exits once the owning bucket is dropped.
+    pub fn spawn(mut source: LazySource, low_watermark: usize, n: usize) -> Self {
+        let (requests, request_rx) = mpsc::sync_channel::<usize>(1);
+        let (batch_tx, batches) = mpsc::sync_channel(1);
+
+        thread::spawn(move || {
+            for n in request_rx {
+                let mut batch = VecDeque::with_capacity(n);
+                let batch = source.generate_batch(&mut batch, n).map(|()| batch);
+
+                if batch_tx.send(batch).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut background = Self {
+            low_watermark,
+            pending: false,
+            requests,
+            batches,
+        };
+        background.request(n);
+
+        background
+    }
+
+    fn request(&mut self, n: usize) {
+        if !self.pending && n > 0 {
+            // the worker only stops after the bucket is gone
+            self.pending = self.requests.send(n).is_ok();
+        }
+    }
+
+    fn fill(&mut self, bucket: &mut VecDeque<i64>, n: usize) -> Result<(), IdError> {
+        // a pending batch was sized when the bucket held at least as many
+        // ids as now, so it still fits
+        self.request(n);
+        let batch = self.batches.recv();
+        self.pending = false;
+
+        // the worker only stops after the bucket is gone, or if it panicked
+        bucket.extend(batch.map_err(|_| IdError::BackendUnavailable)??);
+        Ok(())
+    }
+
+    fn try_fill(&mut self, bucket: &mut VecDeque<i64>, n: usize) -> Result<(), IdError> {
+        self.request(n);
+
+        match self.batches.try_recv() {
+            Ok(batch) => {
+                self.pending = false;
+                bucket.extend(batch?);
+                Ok(())
+            }
+            Err(TryRecvError::Empty) => Err(IdError::SequenceExhausted),
+            // the worker panicked, it won't send another batch
+            Err(TryRecvError::Disconnected) => {
+                self.pending = false;
+                Err(IdError::BackendUnavailable)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_background_refill_fails_once_worker_is_gone() {
+        let (requests, request_rx) = mpsc::sync_channel::<usize>(1);
+        let (batch_tx, batches) = mpsc::sync_channel::<Result<VecDeque<i64>, IdError>>(1);
+
+        // a worker that dies on its first request
+        let worker = thread::spawn(move || {
+            let _batch_tx = batch_tx;
+            request_rx.recv().unwrap();
+            panic!("worker died");
+        });
+
+        let mut background = BackgroundRefill {
+            low_watermark: 0,
+            pending: false,
+            requests,
+            batches,
+        };
+        background.request(10);
+        assert!(worker.join().is_err());
+
+        let mut bucket = VecDeque::new();
+        assert_eq!(
+            background.try_fill(&mut bucket, 10),
+            Err(IdError::BackendUnavailable)
+        );
+        assert_eq!(
+            background.fill(&mut bucket, 10),
+            Err(IdError::BackendUnavailable)
+        );
+        assert!(bucket.is_empty());
+    }
+}
@@ -0,0 +1,151 @@
+use crate::{Clock, IdError, IdGenerator, SystemClock};
+use std::collections::HashSet;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Tracks the (machine id, server id) pairs of the live generators created
+/// through it and refuses a second generator with the same pair, since two
+/// generators with the same identity issue the same IDs.
+///
+/// A pair is released when its [`RegisteredGenerator`] is dropped. Clones
+/// share the same set of pairs; most processes use [`GeneratorRegistry::global`].
+#[derive(Debug, Clone, Default)]
+pub struct GeneratorRegistry {
+    live: Arc<Mutex<HashSet<(i32, i32)>>>,
+}
+
+impl GeneratorRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// the registry shared by the whole process
+    pub fn global() -> &'static GeneratorRegistry {
+        static GLOBAL: OnceLock<GeneratorRegistry> = OnceLock::new();
+
+        GLOBAL.get_or_init(GeneratorRegistry::new)
+    }
+
+    /// create a generator, or return [`IdError::DuplicateGenerator`] if a
+    /// live generator of this registry has the same ids
+    pub fn create(&self, machine_id: i32, server_id: i32) -> Result<RegisteredGenerator, IdError> {
+        self.register(IdGenerator::new(machine_id, server_id)?)
+    }
+
+    /// take over a generator built elsewhere, e.g. with
+    /// [`IdGenerator::builder`], checking its ids like [`GeneratorRegistry::create`]
+    pub fn register<C: Clock>(
+        &self,
+        id_gen: IdGenerator<C>,
+    ) -> Result<RegisteredGenerator<C>, IdError> {
+        let identity = (id_gen.machine_id, id_gen.server_id);
+
+        if !self.lock().insert(identity) {
+            return Err(IdError::DuplicateGenerator(identity.0, identity.1));
+        }
+
+        Ok(RegisteredGenerator {
+            id_gen,
+            identity,
+            registry: self.clone(),
+        })
+    }
+
+    /// whether a live generator of this registry has these ids
+    pub fn contains(&self, machine_id: i32, server_id: i32) -> bool {
+        self.lock().contains(&(machine_id, server_id))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashSet<(i32, i32)>> {
+        // the set is always consistent, see `SharedIdGenerator::lock`
+        self.live.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// An [`IdGenerator`] whose ids are held in a [`GeneratorRegistry`] until it
+/// is dropped. It derefs to the generator.
+#[derive(Debug)]
+pub struct RegisteredGenerator<C = SystemClock> {
+    id_gen: IdGenerator<C>,
+    // the ids it was registered with, as `restore` may change those of the generator
+    identity: (i32, i32),
+    registry: GeneratorRegistry,
+}
+
+impl<C> Deref for RegisteredGenerator<C> {
+    type Target = IdGenerator<C>;
+
+    fn deref(&self) -> &IdGenerator<C> {
+        &self.id_gen
+    }
+}
+
+impl<C> DerefMut for RegisteredGenerator<C> {
+    fn deref_mut(&mut self) -> &mut IdGenerator<C> {
+        &mut self.id_gen
+    }
+}
+
+impl<C> Drop for RegisteredGenerator<C> {
+    fn drop(&mut self) {
+        self.registry.lock().remove(&self.identity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_registry_refuses_duplicates() {
+        let registry = GeneratorRegistry::new();
+        let mut first = registry.create(1, 2).unwrap();
+
+        assert_eq!(
+            registry.create(1, 2).err(),
+            Some(IdError::DuplicateGenerator(1, 2))
+        );
+        assert_eq!(
+            registry
+                .register(
+                    IdGenerator::builder()
+                        .machine_id(1)
+                        .server_id(2)
+                        .build()
+                        .unwrap()
+                )
+                .err(),
+            Some(IdError::DuplicateGenerator(1, 2))
+        );
+        assert!(registry.create(1, 3).is_ok());
+        assert_eq!(
+            registry.create(32, 0).err(),
+            Some(IdError::InvalidMachineId(32))
+        );
+        assert!(first.generate_id() > 0);
+
+        drop(first);
+        assert!(!registry.contains(1, 2));
+        assert!(registry.create(1, 2).is_ok());
+    }
+
+    #[test]
+    fn test_registry_across_threads() {
+        let registry = GeneratorRegistry::new();
+
+        let created: usize = thread::scope(|s| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| s.spawn(|| registry.create(4, 4).map(std::mem::forget).is_ok()))
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap() as usize)
+                .sum()
+        });
+
+        assert_eq!(created, 1);
+        assert!(registry.contains(4, 4));
+    }
+}
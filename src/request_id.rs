@@ -0,0 +1,151 @@
+use crate::{Id, SharedIdGenerator};
+use http::header::HeaderName;
+use http::{HeaderValue, Request};
+use std::fmt;
+use std::task::{Context, Poll};
+use tower_layer::Layer;
+use tower_service::Service;
+
+const DEFAULT_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// The ID a [`RequestIdLayer`] gave a request, stored in its extensions, e.g.
+/// for an axum handler taking `Extension(request_id): Extension<RequestId>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RequestId(Id);
+
+impl RequestId {
+    pub fn id(self) -> Id {
+        self.0
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// A tower [`Layer`] that gives every request a freshly generated ID, for
+/// axum or any other tower based server.
+///
+/// The ID is stored as a [`RequestId`] extension and set as the
+/// `x-request-id` header, replacing one sent by the client, so a client can't
+/// pick the ID its request is logged under.
+#[derive(Debug, Clone)]
+pub struct RequestIdLayer {
+    id_gen: SharedIdGenerator,
+    header: HeaderName,
+}
+
+impl RequestIdLayer {
+    pub fn new(id_gen: SharedIdGenerator) -> Self {
+        Self {
+            id_gen,
+            header: DEFAULT_HEADER,
+        }
+    }
+
+    /// set the header the ID is sent in (defaults to `x-request-id`)
+    pub fn header(mut self, header: HeaderName) -> Self {
+        self.header = header;
+        self
+    }
+}
+
+impl<S> Layer<S> for RequestIdLayer {
+    type Service = RequestIdService<S>;
+
+    fn layer(&self, inner: S) -> RequestIdService<S> {
+        RequestIdService {
+            inner,
+            id_gen: self.id_gen.clone(),
+            header: self.header.clone(),
+        }
+    }
+}
+
+/// The service created by a [`RequestIdLayer`].
+#[derive(Debug, Clone)]
+pub struct RequestIdService<S> {
+    inner: S,
+    id_gen: SharedIdGenerator,
+    header: HeaderName,
+}
+
+impl<S, B> Service<Request<B>> for RequestIdService<S>
+where
+    S: Service<Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<B>) -> S::Future {
+        // generated ids are never negative
+        let id = Id::from(self.id_gen.generate_id() as u64);
+        let value = HeaderValue::from(id.as_u64());
+
+        request.headers_mut().insert(self.header.clone(), value);
+        request.extensions_mut().insert(RequestId(id));
+
+        self.inner.call(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+    use std::future::{ready, Ready};
+
+    /// echoes the request back as the response
+    #[derive(Clone)]
+    struct Echo;
+
+    impl Service<Request<()>> for Echo {
+        type Response = Request<()>;
+        type Error = Infallible;
+        type Future = Ready<Result<Request<()>, Infallible>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: Request<()>) -> Self::Future {
+            ready(Ok(request))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_id_layer() {
+        let id_gen = SharedIdGenerator::new(1, 2).unwrap();
+        let mut service = RequestIdLayer::new(id_gen).layer(Echo);
+
+        let spoofed = Request::builder()
+            .header("x-request-id", "42")
+            .body(())
+            .unwrap();
+        let first = service.call(spoofed).await.unwrap();
+        let second = service.call(Request::new(())).await.unwrap();
+
+        let id = first.extensions().get::<RequestId>().copied().unwrap();
+        assert_eq!(first.headers()["x-request-id"], id.to_string().as_str());
+        assert_eq!(id.id().parts().machine_id, 1);
+        assert!(second.extensions().get::<RequestId>().unwrap().id() > id.id());
+    }
+
+    #[tokio::test]
+    async fn test_request_id_custom_header() {
+        let id_gen = SharedIdGenerator::new(1, 2).unwrap();
+        let layer = RequestIdLayer::new(id_gen).header(HeaderName::from_static("request-id"));
+
+        let request = layer.layer(Echo).call(Request::new(())).await.unwrap();
+
+        assert!(request.headers().contains_key("request-id"));
+        assert!(!request.headers().contains_key("x-request-id"));
+    }
+}
@@ -0,0 +1,251 @@
+use crate::{IdError, IdGenerator, MAX_IDS_PER_MILLISECOND};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, TryLockError};
+
+/// Keeps a value on a cache line of its own, so the producer and consumer
+/// cursors don't invalidate each other's cache lines.
+#[derive(Debug, Default)]
+#[repr(align(64))]
+struct CachePadded<T>(T);
+
+/// A slot of the ring, ready while it holds an id that hasn't been taken.
+#[derive(Debug, Default)]
+struct Slot {
+    id: AtomicI64,
+    ready: AtomicBool,
+}
+
+/// Hands out IDs from a ring of pre-generated ones, after Baidu's
+/// `CachedUidGenerator`.
+///
+/// Taking an ID only moves the consumer cursor with a compare-and-swap, so
+/// any number of threads can share one generator through its clones. Once
+/// fewer than half of the ring are left, the thread that notices refills it
+/// with lazily generated IDs while the others keep taking from the rest. Only
+/// a thread finding the ring empty waits for a refill.
+#[derive(Debug, Clone)]
+pub struct RingBufferGenerator {
+    inner: Arc<Ring>,
+}
+
+#[derive(Debug)]
+struct Ring {
+    slots: Box<[Slot]>,
+    // the cursors count up forever, the slot of a cursor is `cursor & mask`
+    mask: usize,
+    // the next slot the producer fills
+    tail: CachePadded<AtomicUsize>,
+    // the next slot a consumer takes
+    cursor: CachePadded<AtomicUsize>,
+    id_gen: Mutex<IdGenerator>,
+}
+
+impl RingBufferGenerator {
+    /// create a generator with a ring of 8 ticks of IDs
+    pub fn new(machine_id: i32, server_id: i32) -> Result<Self, IdError> {
+        let id_gen = IdGenerator::new(machine_id, server_id)?;
+
+        Self::with_capacity(id_gen, 8 * MAX_IDS_PER_MILLISECOND)
+    }
+
+    /// create a generator filling a ring of `capacity` slots, a power of two,
+    /// with [`IdGenerator::generate_id_lazy`] from `id_gen`
+    ///
+    /// Set a [max drift](crate::IdGeneratorBuilder::max_drift) on `id_gen` to
+    /// bound how far ahead of the clock the IDs may be dated.
+    pub fn with_capacity(id_gen: IdGenerator, capacity: usize) -> Result<Self, IdError> {
+        if !capacity.is_power_of_two() {
+            return Err(IdError::InvalidBucketConfig(
+                "capacity must be a power of two",
+            ));
+        }
+
+        let ring = Ring {
+            slots: (0..capacity).map(|_| Slot::default()).collect(),
+            mask: capacity - 1,
+            tail: CachePadded::default(),
+            cursor: CachePadded::default(),
+            id_gen: Mutex::new(id_gen),
+        };
+        // an error shows again on the first id taken from the empty ring
+        let _ = ring.fill(&mut ring.lock());
+
+        Ok(Self {
+            inner: Arc::new(ring),
+        })
+    }
+
+    /// take the next id of the ring
+    ///
+    /// # Panics
+    ///
+    /// Panics if a refill fails like [`IdGenerator::generate_id_lazy`]
+    /// does, use [`RingBufferGenerator::try_generate_id`] to handle that.
+    pub fn generate_id(&self) -> i64 {
+        self.try_generate_id().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// take the next id of the ring, or the error the refill of an empty
+    /// ring failed with
+    pub fn try_generate_id(&self) -> Result<i64, IdError> {
+        let ring = &self.inner;
+
+        loop {
+            let cursor = ring.cursor.0.load(Ordering::Acquire);
+            let tail = ring.tail.0.load(Ordering::Acquire);
+
+            if cursor == tail {
+                let mut id_gen = ring.lock();
+                ring.fill(&mut id_gen)?;
+                continue;
+            }
+
+            if ring
+                .cursor
+                .0
+                .compare_exchange_weak(cursor, cursor + 1, Ordering::AcqRel, Ordering::Relaxed)
+                .is_err()
+            {
+                continue;
+            }
+
+            // the producer marks a slot ready before it publishes the tail
+            // past it, and doesn't refill it until it is no longer ready
+            let slot = &ring.slots[cursor & ring.mask];
+            let id = slot.id.load(Ordering::Relaxed);
+            slot.ready.store(false, Ordering::Release);
+
+            if tail - cursor <= ring.slots.len() / 2 {
+                ring.try_fill();
+            }
+
+            return Ok(id);
+        }
+    }
+
+    /// fill `out` with ids, like [`IdGenerator::fill_ids`]
+    pub fn fill_ids(&self, out: &mut [i64]) {
+        for id in out.iter_mut() {
+            *id = self.generate_id();
+        }
+    }
+}
+
+impl Ring {
+    fn lock(&self) -> MutexGuard<'_, IdGenerator> {
+        // the generator state is always consistent, see `SharedIdGenerator::lock`
+        self.id_gen.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// refill the ring unless another thread already is
+    ///
+    /// A failed refill is left to the consumer that finds the ring empty.
+    fn try_fill(&self) {
+        let _ = match self.id_gen.try_lock() {
+            Ok(mut id_gen) => self.fill(&mut id_gen),
+            Err(TryLockError::Poisoned(e)) => self.fill(&mut e.into_inner()),
+            Err(TryLockError::WouldBlock) => Ok(()),
+        };
+    }
+
+    /// fill every free slot, called with the generator locked so there is
+    /// only one producer
+    ///
+    /// Fails only if not a single slot could be filled.
+    fn fill(&self, id_gen: &mut IdGenerator) -> Result<(), IdError> {
+        let start = self.tail.0.load(Ordering::Relaxed);
+        let mut tail = self.tail.0.load(Ordering::Relaxed);
+
+        while tail - self.cursor.0.load(Ordering::Acquire) < self.slots.len() {
+            let slot = &self.slots[tail & self.mask];
+
+            // a consumer moved past the slot but hasn't read it yet
+            if slot.ready.load(Ordering::Acquire) {
+                break;
+            }
+
+            let id = match id_gen.wait_for_lazy_id() {
+                Ok(id) => id,
+                Err(e) if tail == start => return Err(e),
+                Err(_) => break,
+            };
+
+            slot.id.store(id, Ordering::Relaxed);
+            slot.ready.store(true, Ordering::Release);
+            tail += 1;
+            self.tail.0.store(tail, Ordering::Release);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::thread;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_ring_buffer_across_threads() {
+        let ring = RingBufferGenerator::new(1, 2).unwrap();
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let ring = ring.clone();
+                thread::spawn(move || {
+                    let mut ids = vec![0; 50_000];
+                    ring.fill_ids(&mut ids);
+                    ids
+                })
+            })
+            .collect();
+
+        let mut seen = HashSet::new();
+        for handle in handles {
+            let ids = handle.join().unwrap();
+
+            assert!(ids.windows(2).all(|w| w[0] < w[1]));
+            assert!(ids.into_iter().all(|id| seen.insert(id)));
+        }
+
+        assert_eq!(seen.len(), 200_000);
+    }
+
+    #[test]
+    fn test_ring_buffer_wraps_around() {
+        let ring = RingBufferGenerator::with_capacity(IdGenerator::new(1, 2).unwrap(), 16).unwrap();
+
+        let mut ids = vec![0; 10_000];
+        ring.fill_ids(&mut ids);
+
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        // the ring was refilled about 1,250 times
+        let tail = ring.inner.tail.0.load(Ordering::Relaxed);
+        assert!((10_000..=10_016).contains(&tail));
+    }
+
+    #[test]
+    fn test_ring_buffer_overflow() {
+        // an epoch so far back that the timestamp has already overflowed
+        let epoch = SystemTime::UNIX_EPOCH - Duration::from_secs(20 * 365 * 86_400);
+        let id_gen = IdGenerator::builder().epoch(epoch).build().unwrap();
+        let ring = RingBufferGenerator::with_capacity(id_gen, 16).unwrap();
+
+        assert_eq!(ring.try_generate_id(), Err(IdError::TimestampOverflow));
+        assert!(std::panic::catch_unwind(|| ring.generate_id()).is_err());
+    }
+
+    #[test]
+    fn test_ring_buffer_capacity_must_be_power_of_two() {
+        let id_gen = IdGenerator::new(1, 2).unwrap();
+
+        assert_eq!(
+            RingBufferGenerator::with_capacity(id_gen, 100).err(),
+            Some(IdError::InvalidBucketConfig(
+                "capacity must be a power of two"
+            ))
+        );
+    }
+}
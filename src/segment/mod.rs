@@ -0,0 +1,291 @@
+//! IDs allocated in segments from a database, after Meituan's Leaf.
+//!
+//! Unlike the snowflake generators these IDs carry no timestamp: each key
+//! owns a counter in the database, and every allocator reserves a segment of
+//! the next `step` values at a time and hands them out locally. The IDs of a
+//! key are unique across every allocator sharing the database, and ascending
+//! within one allocator.
+
+#[cfg(all(feature = "sqlx", feature = "tokio"))]
+mod sqlx;
+
+#[cfg(all(feature = "sqlx", feature = "tokio"))]
+pub use self::sqlx::SqlxSegmentStore;
+
+use std::fmt;
+use std::num::NonZeroU32;
+use std::ops::Range;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+
+/// The database keeping the counter of each key.
+///
+/// `next_segment` must advance the counter of `key` by `step` atomically and
+/// return the values it skipped, so no two calls ever return overlapping
+/// ranges, not even from different processes.
+pub trait SegmentStore: Send + Sync + 'static {
+    fn next_segment(&self, key: &str, step: NonZeroU32) -> Result<Range<i64>, SegmentError>;
+}
+
+/// Errors returned while reserving a segment.
+#[derive(Debug)]
+pub enum SegmentError {
+    /// The store has no counter for the key.
+    UnknownKey(String),
+    /// The database failed.
+    Backend(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl fmt::Display for SegmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SegmentError::UnknownKey(key) => write!(f, "no segment counter for key {}", key),
+            SegmentError::Backend(e) => write!(f, "segment backend failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SegmentError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SegmentError::UnknownKey(_) => None,
+            SegmentError::Backend(e) => Some(e.as_ref()),
+        }
+    }
+}
+
+/// Hands out the IDs of one key from segments reserved in a
+/// [`SegmentStore`].
+///
+/// The allocator keeps two buffers: once a tenth of the current segment is
+/// used, a background thread reserves the next one, so the swap to it
+/// doesn't wait for the database unless the current segment runs out before
+/// the store answers. Clones share the buffers. IDs left in the segments of
+/// a dropped allocator are never handed out.
+#[derive(Debug)]
+pub struct SegmentAllocator<S> {
+    inner: Arc<Shared<S>>,
+}
+
+impl<S> Clone for SegmentAllocator<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Shared<S> {
+    store: S,
+    key: String,
+    step: NonZeroU32,
+    buffers: Mutex<Buffers>,
+    // signalled whenever a prefetch finishes
+    prefetched: Condvar,
+}
+
+#[derive(Debug)]
+struct Buffers {
+    current: Range<i64>,
+    next: Option<Range<i64>>,
+    // whether the next segment was asked for since `current` was swapped in,
+    // so a failing store is tried once per segment rather than once per id
+    prefetch_started: bool,
+    prefetching: bool,
+}
+
+impl<S: SegmentStore> SegmentAllocator<S> {
+    /// create an allocator for `key`, reserving its first segment of `step`
+    /// ids right away
+    pub fn new(store: S, key: &str, step: NonZeroU32) -> Result<Self, SegmentError> {
+        let current = store.next_segment(key, step)?;
+
+        Ok(Self {
+            inner: Arc::new(Shared {
+                store,
+                key: key.to_string(),
+                step,
+                buffers: Mutex::new(Buffers {
+                    current,
+                    next: None,
+                    prefetch_started: false,
+                    prefetching: false,
+                }),
+                prefetched: Condvar::new(),
+            }),
+        })
+    }
+
+    /// the next id, waiting for the store when both segments are used up
+    pub fn next_id(&self) -> Result<i64, SegmentError> {
+        let mut buffers = self.inner.lock();
+
+        loop {
+            if let Some(id) = buffers.current.next() {
+                let left = buffers.current.end - buffers.current.start;
+                if !buffers.prefetch_started && left < self.inner.step.get() as i64 * 9 / 10 {
+                    buffers.prefetch_started = true;
+                    buffers.prefetching = true;
+                    self.prefetch();
+                }
+                return Ok(id);
+            }
+
+            if let Some(next) = buffers.next.take() {
+                buffers.current = next;
+                buffers.prefetch_started = false;
+            } else if buffers.prefetching {
+                buffers = self
+                    .inner
+                    .prefetched
+                    .wait(buffers)
+                    .unwrap_or_else(|e| e.into_inner());
+            } else {
+                // the prefetch failed, so ask the store ourselves
+                buffers.current = self.inner.reserve()?;
+                buffers.prefetch_started = false;
+            }
+        }
+    }
+
+    /// reserve the next segment on a background thread
+    fn prefetch(&self) {
+        let inner = self.inner.clone();
+
+        thread::spawn(move || {
+            // a failure is reported by the next id that needs the segment
+            let next = inner.reserve().ok();
+
+            let mut buffers = inner.lock();
+            buffers.next = next;
+            buffers.prefetching = false;
+            inner.prefetched.notify_all();
+        });
+    }
+}
+
+impl<S: SegmentStore> Shared<S> {
+    fn lock(&self) -> MutexGuard<'_, Buffers> {
+        // the buffers are always consistent, see `SharedIdGenerator::lock`
+        self.buffers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn reserve(&self) -> Result<Range<i64>, SegmentError> {
+        self.store.next_segment(&self.key, self.step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{HashMap, HashSet};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    #[derive(Debug, Default)]
+    struct MemoryStore {
+        counters: Mutex<HashMap<String, i64>>,
+        calls: AtomicUsize,
+        failing: AtomicBool,
+    }
+
+    impl MemoryStore {
+        fn with_key(key: &str) -> Self {
+            let store = Self::default();
+            store.counters.lock().unwrap().insert(key.to_string(), 1);
+            store
+        }
+    }
+
+    impl SegmentStore for Arc<MemoryStore> {
+        fn next_segment(&self, key: &str, step: NonZeroU32) -> Result<Range<i64>, SegmentError> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            if self.failing.load(Ordering::Relaxed) {
+                return Err(SegmentError::Backend("database is down".into()));
+            }
+
+            let mut counters = self.counters.lock().unwrap();
+            let counter = counters
+                .get_mut(key)
+                .ok_or_else(|| SegmentError::UnknownKey(key.to_string()))?;
+            let start = *counter;
+            *counter += step.get() as i64;
+
+            Ok(start..*counter)
+        }
+    }
+
+    fn step(n: u32) -> NonZeroU32 {
+        NonZeroU32::new(n).unwrap()
+    }
+
+    #[test]
+    fn test_segments_across_threads() {
+        let store = Arc::new(MemoryStore::with_key("orders"));
+        let first = SegmentAllocator::new(store.clone(), "orders", step(100)).unwrap();
+        let second = SegmentAllocator::new(store.clone(), "orders", step(100)).unwrap();
+
+        let handles: Vec<_> = [first.clone(), first, second.clone(), second]
+            .into_iter()
+            .map(|allocator| {
+                thread::spawn(move || {
+                    let ids: Vec<i64> = (0..5_000).map(|_| allocator.next_id().unwrap()).collect();
+                    ids
+                })
+            })
+            .collect();
+
+        let mut seen = HashSet::new();
+        for handle in handles {
+            let ids = handle.join().unwrap();
+
+            assert!(ids.windows(2).all(|w| w[0] < w[1]));
+            assert!(ids.into_iter().all(|id| seen.insert(id)));
+        }
+
+        assert_eq!(seen.len(), 20_000);
+        assert!(seen.iter().all(|&id| id >= 1));
+    }
+
+    #[test]
+    fn test_segment_prefetched_before_running_out() {
+        let store = Arc::new(MemoryStore::with_key("orders"));
+        let allocator = SegmentAllocator::new(store.clone(), "orders", step(10)).unwrap();
+
+        assert_eq!(allocator.next_id().unwrap(), 1);
+        assert_eq!(allocator.next_id().unwrap(), 2);
+
+        // the second id started the prefetch
+        let buffers = allocator
+            .inner
+            .prefetched
+            .wait_while(allocator.inner.lock(), |b| b.prefetching);
+        assert_eq!(buffers.unwrap().next, Some(11..21));
+        assert_eq!(store.calls.load(Ordering::Relaxed), 2);
+
+        let ids: Vec<i64> = (0..18).map(|_| allocator.next_id().unwrap()).collect();
+        assert_eq!(ids, (3..21).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_segment_store_failure() {
+        let store = Arc::new(MemoryStore::with_key("orders"));
+        let allocator = SegmentAllocator::new(store.clone(), "orders", step(10)).unwrap();
+
+        store.failing.store(true, Ordering::Relaxed);
+        for id in 1..=10 {
+            assert_eq!(allocator.next_id().unwrap(), id);
+        }
+        assert!(matches!(allocator.next_id(), Err(SegmentError::Backend(_))));
+        // the failed prefetch wasn't retried for every id
+        assert_eq!(store.calls.load(Ordering::Relaxed), 3);
+
+        store.failing.store(false, Ordering::Relaxed);
+        assert!(allocator.next_id().unwrap() > 10);
+
+        assert!(matches!(
+            SegmentAllocator::new(store, "users", step(10)),
+            Err(SegmentError::UnknownKey(key)) if key == "users"
+        ));
+    }
+}
@@ -0,0 +1,148 @@
+use super::{SegmentError, SegmentStore};
+use sqlx::{ColumnIndex, Database, Decode, Encode, Executor, IntoArguments, Pool, Row, Type};
+use std::num::NonZeroU32;
+use std::ops::Range;
+use tokio::runtime::Handle;
+
+/// Keeps the segment counters in a table of any database `sqlx` supports.
+///
+/// The table has a row per key and defaults to Leaf's `leaf_alloc`:
+///
+/// ```sql
+/// CREATE TABLE leaf_alloc (
+///     biz_tag VARCHAR(128) PRIMARY KEY NOT NULL,
+///     max_id BIGINT NOT NULL
+/// );
+/// ```
+///
+/// Each segment bumps `max_id` and reads it back in one transaction. The
+/// queries run on the multi-threaded tokio runtime behind `runtime`, blocking
+/// the calling thread, so use the allocator from blocking code, e.g. in
+/// `spawn_blocking`, rather than from inside an async task.
+#[derive(Debug)]
+pub struct SqlxSegmentStore<DB: Database> {
+    pool: Pool<DB>,
+    runtime: Handle,
+    table: String,
+}
+
+impl<DB: Database> SqlxSegmentStore<DB> {
+    pub fn new(pool: Pool<DB>, runtime: Handle) -> Self {
+        Self {
+            pool,
+            runtime,
+            table: "leaf_alloc".to_string(),
+        }
+    }
+
+    /// set the table the counters are kept in (defaults to `leaf_alloc`)
+    pub fn table(mut self, table: &str) -> Self {
+        self.table = table.to_string();
+        self
+    }
+}
+
+impl<DB> SegmentStore for SqlxSegmentStore<DB>
+where
+    DB: Database,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'a> DB::Arguments<'a>: IntoArguments<'a, DB>,
+    for<'q> i64: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> &'q str: Encode<'q, DB> + Type<DB>,
+    usize: ColumnIndex<DB::Row>,
+{
+    fn next_segment(&self, key: &str, step: NonZeroU32) -> Result<Range<i64>, SegmentError> {
+        let step = step.get() as i64;
+
+        let update = format!(
+            "UPDATE {} SET max_id = max_id + {} WHERE biz_tag = {}",
+            self.table,
+            placeholder::<DB>(1),
+            placeholder::<DB>(2)
+        );
+        let select = format!(
+            "SELECT max_id FROM {} WHERE biz_tag = {}",
+            self.table,
+            placeholder::<DB>(1)
+        );
+
+        let max_id = self.runtime.block_on(async {
+            let mut tx = self.pool.begin().await?;
+            sqlx::query(&update)
+                .bind(step)
+                .bind(key)
+                .execute(&mut *tx)
+                .await?;
+            let row = sqlx::query(&select)
+                .bind(key)
+                .fetch_optional(&mut *tx)
+                .await?;
+            tx.commit().await?;
+
+            row.map(|row| row.try_get::<i64, _>(0)).transpose()
+        });
+
+        match max_id {
+            Ok(Some(max_id)) => Ok(max_id - step..max_id),
+            Ok(None) => Err(SegmentError::UnknownKey(key.to_string())),
+            Err(e) => Err(SegmentError::Backend(e.into())),
+        }
+    }
+}
+
+/// the `n`th bind parameter, numbered on Postgres and positional elsewhere
+fn placeholder<DB: Database>(n: usize) -> String {
+    if DB::NAME == "PostgreSQL" {
+        format!("${}", n)
+    } else {
+        "?".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::segment::SegmentAllocator;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::Sqlite;
+
+    #[test]
+    fn test_sqlx_segment_store() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .unwrap();
+
+        // one connection, as every connection opens its own in-memory database
+        let pool = runtime.block_on(async {
+            let pool = SqlitePoolOptions::new()
+                .max_connections(1)
+                .connect("sqlite::memory:")
+                .await
+                .unwrap();
+            sqlx::query("CREATE TABLE leaf_alloc (biz_tag TEXT PRIMARY KEY NOT NULL, max_id BIGINT NOT NULL)")
+                .execute(&pool)
+                .await
+                .unwrap();
+            sqlx::query("INSERT INTO leaf_alloc (biz_tag, max_id) VALUES ('orders', 0)")
+                .execute(&pool)
+                .await
+                .unwrap();
+            pool
+        });
+
+        let store = SqlxSegmentStore::<Sqlite>::new(pool, runtime.handle().clone());
+        let step = NonZeroU32::new(10).unwrap();
+
+        assert_eq!(store.next_segment("orders", step).unwrap(), 0..10);
+        assert!(matches!(
+            store.next_segment("users", step),
+            Err(SegmentError::UnknownKey(_))
+        ));
+
+        let allocator = SegmentAllocator::new(store, "orders", step).unwrap();
+        let ids: Vec<i64> = (0..25).map(|_| allocator.next_id().unwrap()).collect();
+        assert_eq!(ids, (10..35).collect::<Vec<_>>());
+    }
+}
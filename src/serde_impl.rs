@@ -0,0 +1,100 @@
+use crate::Id;
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+
+/// `Id` serializes as a number by default. It deserializes from either a
+/// number or a decimal string, so both forms round-trip.
+impl Serialize for Id {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.as_u64())
+    }
+}
+
+impl<'de> Deserialize<'de> for Id {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(IdVisitor)
+    }
+}
+
+struct IdVisitor;
+
+impl<'de> Visitor<'de> for IdVisitor {
+    type Value = Id;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an id as a non-negative integer or a decimal string")
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Id, E> {
+        Ok(Id::from(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Id, E> {
+        Id::try_from(v).map_err(E::custom)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Id, E> {
+        v.parse().map_err(E::custom)
+    }
+}
+
+/// Serialize an [`Id`] as a decimal string, for JSON consumers that can't
+/// represent 64-bit integers exactly.
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use unique_id::Id;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Order {
+///     #[serde(with = "unique_id::id_as_string")]
+///     id: Id,
+/// }
+/// ```
+pub mod id_as_string {
+    use crate::Id;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(id: &Id, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(id)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Id, D::Error> {
+        Id::deserialize(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Record {
+        id: Id,
+        #[serde(with = "id_as_string")]
+        public_id: Id,
+    }
+
+    #[test]
+    fn test_id_serde_round_trip() {
+        let record = Record {
+            id: Id::from(9_007_199_254_740_993),
+            public_id: Id::from(9_007_199_254_740_993),
+        };
+
+        let json = serde_json::to_string(&record).unwrap();
+
+        assert_eq!(
+            json,
+            r#"{"id":9007199254740993,"public_id":"9007199254740993"}"#
+        );
+        assert_eq!(serde_json::from_str::<Record>(&json).unwrap(), record);
+    }
+
+    #[test]
+    fn test_id_deserialize_rejects_negative() {
+        assert!(serde_json::from_str::<Id>("-1").is_err());
+        assert!(serde_json::from_str::<Id>(r#""abc""#).is_err());
+    }
+}
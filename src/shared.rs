@@ -0,0 +1,106 @@
+use crate::{GeneratorStats, IdBlock, IdError, IdGenerator};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// A cloneable handle to one [`IdGenerator`] that can be shared across threads.
+///
+/// Every clone draws from the same sequence, so IDs stay unique no matter
+/// which handle issued them.
+#[derive(Debug, Clone)]
+pub struct SharedIdGenerator {
+    inner: Arc<Mutex<IdGenerator>>,
+}
+
+impl SharedIdGenerator {
+    pub fn new(machine_id: i32, server_id: i32) -> Result<Self, IdError> {
+        IdGenerator::new(machine_id, server_id).map(Self::from)
+    }
+
+    pub fn generate_id(&self) -> i64 {
+        self.lock().generate_id()
+    }
+
+    /// generate an ID like [`SharedIdGenerator::generate_id`], but return
+    /// the errors it would panic with, see [`IdGenerator::generate_id`]
+    pub fn try_generate_id(&self) -> Result<i64, IdError> {
+        self.lock().wait_for_id()
+    }
+
+    pub fn generate_id_by_time(&self) -> i64 {
+        self.lock().generate_id_by_time()
+    }
+
+    pub fn generate_id_lazy(&self) -> i64 {
+        self.lock().generate_id_lazy()
+    }
+
+    /// reserve `n` ids at once, see [`IdGenerator::reserve`]
+    pub fn reserve(&self, n: usize) -> IdBlock {
+        self.lock().reserve(n)
+    }
+
+    /// reserve `n` ids at once, see [`IdGenerator::try_reserve`]
+    pub fn try_reserve(&self, n: usize) -> Result<IdBlock, IdError> {
+        self.lock().try_reserve(n)
+    }
+
+    /// the stats of the shared generator, see [`IdGenerator::stats`]
+    pub fn stats(&self) -> GeneratorStats {
+        self.lock().stats()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, IdGenerator> {
+        // the generator state is always consistent between calls,
+        // so a panic in another thread doesn't invalidate it.
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl From<IdGenerator> for SharedIdGenerator {
+    fn from(id_gen: IdGenerator) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(id_gen)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_shared_generator_across_threads() {
+        let id_gen = SharedIdGenerator::new(1, 2).unwrap();
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let id_gen = id_gen.clone();
+                thread::spawn(move || {
+                    (0..10_000)
+                        .map(|_| id_gen.generate_id())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        let mut ids: Vec<i64> = handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect();
+
+        ids.sort();
+        ids.dedup();
+
+        assert_eq!(ids.len(), 40_000);
+    }
+
+    #[test]
+    fn test_shared_generator_overflow() {
+        // an epoch so far back that the timestamp has already overflowed
+        let epoch = SystemTime::UNIX_EPOCH - Duration::from_secs(20 * 365 * 86_400);
+        let id_gen = SharedIdGenerator::from(IdGenerator::builder().epoch(epoch).build().unwrap());
+
+        assert_eq!(id_gen.try_generate_id(), Err(IdError::TimestampOverflow));
+    }
+}
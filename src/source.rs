@@ -0,0 +1,245 @@
+use crate::ksuid::{Ksuid, KsuidGenerator};
+use crate::objectid::{ObjectId, ObjectIdGenerator};
+use crate::ulid::{Ulid, UlidGenerator};
+use crate::xid::{Xid, XidGenerator};
+use crate::{
+    AtomicIdBlocks, AtomicIdGenerator, Clock, GeneratorPool, HlcIdGenerator, Id, Id128Generator,
+    IdError, IdGenerator, IdGeneratorBucket, PerCoreGenerator, RandomTailIdGenerator,
+    RegisteredGenerator, RingBufferGenerator, SharedIdGenerator, Uuid7, Uuid7Generator,
+};
+
+/// A source of unique IDs, so applications can swap the algorithm behind one
+/// interface or a `Box<dyn IdSource<Id = Id>>`.
+///
+/// Sources wait instead of failing whenever the algorithm allows it, so an
+/// error means no ID can be produced without outside help, e.g. the timestamp
+/// overflowed or the exhaustion policy asks for an error.
+pub trait IdSource {
+    /// the type of the IDs this source produces
+    type Id;
+
+    /// produce the next ID
+    fn next(&mut self) -> Result<Self::Id, IdError>;
+}
+
+impl<S: IdSource + ?Sized> IdSource for Box<S> {
+    type Id = S::Id;
+
+    fn next(&mut self) -> Result<S::Id, IdError> {
+        (**self).next()
+    }
+}
+
+impl<C: Clock> IdSource for IdGenerator<C> {
+    type Id = Id;
+
+    fn next(&mut self) -> Result<Id, IdError> {
+        self.wait_for_id().and_then(Id::try_from)
+    }
+}
+
+impl<C: Clock> IdSource for RegisteredGenerator<C> {
+    type Id = Id;
+
+    fn next(&mut self) -> Result<Id, IdError> {
+        (**self).next()
+    }
+}
+
+impl IdSource for IdGeneratorBucket {
+    type Id = Id;
+
+    fn next(&mut self) -> Result<Id, IdError> {
+        self.wait_for_id().and_then(Id::try_from)
+    }
+}
+
+#[cfg(feature = "crossbeam")]
+impl IdSource for crate::ConcurrentIdBucket {
+    type Id = Id;
+
+    fn next(&mut self) -> Result<Id, IdError> {
+        self.wait_for_id().and_then(Id::try_from)
+    }
+}
+
+impl<C: Clock> IdSource for HlcIdGenerator<C> {
+    type Id = Id;
+
+    fn next(&mut self) -> Result<Id, IdError> {
+        self.try_generate_id().and_then(Id::try_from)
+    }
+}
+
+impl IdSource for AtomicIdGenerator {
+    type Id = Id;
+
+    fn next(&mut self) -> Result<Id, IdError> {
+        self.try_generate_id().and_then(Id::try_from)
+    }
+}
+
+impl<C: Clock + Clone> IdSource for PerCoreGenerator<C> {
+    type Id = Id;
+
+    fn next(&mut self) -> Result<Id, IdError> {
+        self.try_generate_id().and_then(Id::try_from)
+    }
+}
+
+impl IdSource for AtomicIdBlocks<'_> {
+    type Id = Id;
+
+    fn next(&mut self) -> Result<Id, IdError> {
+        self.try_generate_id().and_then(Id::try_from)
+    }
+}
+
+#[cfg(feature = "redis")]
+impl IdSource for crate::RedisCounterGenerator {
+    type Id = Id;
+
+    /// fails with [`IdError::BackendUnavailable`] when Redis can't be
+    /// reached; call `next_id` to see the Redis error
+    fn next(&mut self) -> Result<Id, IdError> {
+        self.next_id()
+            .map_err(|_| IdError::BackendUnavailable)
+            .and_then(Id::try_from)
+    }
+}
+
+impl IdSource for RingBufferGenerator {
+    type Id = Id;
+
+    fn next(&mut self) -> Result<Id, IdError> {
+        self.try_generate_id().and_then(Id::try_from)
+    }
+}
+
+impl IdSource for SharedIdGenerator {
+    type Id = Id;
+
+    fn next(&mut self) -> Result<Id, IdError> {
+        self.try_generate_id().and_then(Id::try_from)
+    }
+}
+
+impl IdSource for GeneratorPool {
+    type Id = Id;
+
+    fn next(&mut self) -> Result<Id, IdError> {
+        self.try_generate_id().and_then(Id::try_from)
+    }
+}
+
+impl IdSource for RandomTailIdGenerator {
+    type Id = Id;
+
+    fn next(&mut self) -> Result<Id, IdError> {
+        self.try_generate_id().and_then(Id::try_from)
+    }
+}
+
+impl IdSource for Id128Generator {
+    type Id = u128;
+
+    fn next(&mut self) -> Result<u128, IdError> {
+        Ok(self.generate_id())
+    }
+}
+
+impl IdSource for UlidGenerator {
+    type Id = Ulid;
+
+    fn next(&mut self) -> Result<Ulid, IdError> {
+        Ok(self.generate())
+    }
+}
+
+impl IdSource for KsuidGenerator {
+    type Id = Ksuid;
+
+    fn next(&mut self) -> Result<Ksuid, IdError> {
+        Ok(self.generate())
+    }
+}
+
+impl<C: Clock> IdSource for ObjectIdGenerator<C> {
+    type Id = ObjectId;
+
+    fn next(&mut self) -> Result<ObjectId, IdError> {
+        Ok(self.generate())
+    }
+}
+
+impl<C: Clock> IdSource for XidGenerator<C> {
+    type Id = Xid;
+
+    fn next(&mut self) -> Result<Xid, IdError> {
+        Ok(self.generate())
+    }
+}
+
+impl IdSource for Uuid7Generator {
+    type Id = Uuid7;
+
+    fn next(&mut self) -> Result<Uuid7, IdError> {
+        Ok(self.generate())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn take<S: IdSource>(source: &mut S, n: usize) -> Vec<S::Id> {
+        (0..n).map(|_| source.next().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_boxed_sources() {
+        let mut sources: Vec<Box<dyn IdSource<Id = Id>>> = vec![
+            Box::new(IdGenerator::new(1, 1).unwrap()),
+            Box::new(IdGeneratorBucket::new(1, 2).unwrap()),
+            Box::new(AtomicIdGenerator::new(1, 3).unwrap()),
+            Box::new(SharedIdGenerator::new(1, 4).unwrap()),
+            Box::new(GeneratorPool::new(2).unwrap()),
+            Box::new(RandomTailIdGenerator::new(1, 5).unwrap()),
+        ];
+
+        let mut ids: Vec<Id> = sources
+            .iter_mut()
+            .flat_map(|source| take(source, 1_000))
+            .collect();
+
+        ids.sort();
+        ids.dedup();
+
+        assert_eq!(ids.len(), 6_000);
+    }
+
+    #[test]
+    fn test_other_id_types() {
+        let ulids = take(&mut UlidGenerator::new(), 100);
+
+        assert!(ulids.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(take(&mut KsuidGenerator::new(), 10).len(), 10);
+    }
+
+    #[test]
+    fn test_exhaustion_policy_error() {
+        let mut id_gen = IdGenerator::builder()
+            .exhaustion_policy(crate::ExhaustionPolicy::ReturnError)
+            .build_with_clock(|| 1_000)
+            .unwrap();
+
+        let results: Vec<_> = (0..=crate::MAX_IDS_PER_MILLISECOND)
+            .map(|_| id_gen.next())
+            .collect();
+
+        assert_eq!(
+            results.last().unwrap().err(),
+            Some(IdError::SequenceExhausted)
+        );
+    }
+}
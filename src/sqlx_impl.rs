@@ -0,0 +1,75 @@
+use crate::Id;
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::{Database, Decode, Encode, Type};
+
+/// `Id` binds and reads like the `i64` of a `BIGINT` column on every
+/// database `sqlx` supports; IDs that don't fit a non-negative `i64` are
+/// rejected rather than wrapped.
+impl<DB: Database> Type<DB> for Id
+where
+    i64: Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <i64 as Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <i64 as Type<DB>>::compatible(ty)
+    }
+}
+
+impl<'q, DB: Database> Encode<'q, DB> for Id
+where
+    i64: Encode<'q, DB>,
+{
+    fn encode_by_ref(
+        &self,
+        buf: &mut <DB as Database>::ArgumentBuffer<'q>,
+    ) -> Result<IsNull, BoxDynError> {
+        i64::try_from(*self)?.encode_by_ref(buf)
+    }
+}
+
+impl<'r, DB: Database> Decode<'r, DB> for Id
+where
+    i64: Decode<'r, DB>,
+{
+    fn decode(value: <DB as Database>::ValueRef<'r>) -> Result<Self, BoxDynError> {
+        Ok(Id::try_from(<i64 as Decode<DB>>::decode(value)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Id, IdGenerator};
+    use sqlx::{Connection, SqliteConnection};
+
+    #[tokio::test]
+    async fn test_sqlx_round_trip() {
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        sqlx::query("CREATE TABLE events (id BIGINT PRIMARY KEY NOT NULL)")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        let id = IdGenerator::new(1, 2).unwrap().iter().next().unwrap();
+        sqlx::query("INSERT INTO events (id) VALUES (?)")
+            .bind(id)
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        let (stored,): (Id,) = sqlx::query_as("SELECT id FROM events")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(stored, id);
+
+        assert!(sqlx::query("INSERT INTO events (id) VALUES (?)")
+            .bind(Id::from(u64::MAX))
+            .execute(&mut conn)
+            .await
+            .is_err());
+    }
+}
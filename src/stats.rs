@@ -0,0 +1,83 @@
+use crate::clock::LayoutClock;
+use crate::{Clock, IdGenerator};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// What an [`IdGenerator`] did since it was created, taken with
+/// [`IdGenerator::stats`], e.g. to export on a health endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GeneratorStats {
+    /// number of ids issued, including those of reserved blocks
+    pub generated: u64,
+    /// sequence of the last issued id
+    pub sequence: usize,
+    /// timestamp of the last issued id, in ticks of the layout
+    pub timestamp: i64,
+    /// number of times the generator waited for the clock to move on
+    pub waits: u64,
+    /// how far the last issued timestamp runs ahead of the clock, which only
+    /// lazy generation and reserved blocks do
+    pub drift: Duration,
+}
+
+impl<C: Clock> IdGenerator<C> {
+    pub fn stats(&self) -> GeneratorStats {
+        let ahead = (self.timestamp - self.clock.ticks(&self.layout)).max(0);
+
+        GeneratorStats {
+            generated: self.generated,
+            sequence: self.index,
+            timestamp: self.timestamp,
+            waits: self.waits,
+            drift: Duration::from_nanos(self.layout.tick().as_nanos() as u64 * ahead as u64),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::MAX_IDS_PER_MILLISECOND;
+    use std::thread;
+
+    #[test]
+    fn test_stats() {
+        let clock = MockClock::new(1_000);
+        let mut id_gen = IdGenerator::builder()
+            .build_with_clock(clock.clone())
+            .unwrap();
+
+        id_gen.generate_ids(10);
+        assert_eq!(
+            id_gen.stats(),
+            GeneratorStats {
+                generated: 10,
+                sequence: 10,
+                timestamp: 1_000,
+                waits: 0,
+                drift: Duration::ZERO,
+            }
+        );
+
+        for _ in 0..MAX_IDS_PER_MILLISECOND * 2 {
+            id_gen.generate_id_lazy();
+        }
+        let stats = id_gen.stats();
+        assert_eq!(stats.generated, 10 + MAX_IDS_PER_MILLISECOND as u64 * 2);
+        assert_eq!(stats.drift, Duration::from_millis(2));
+
+        // the next generated id waits for the clock to reach the lazy ones
+        let advance = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            clock.set_millis(1_003);
+        });
+        id_gen.generate_ids(MAX_IDS_PER_MILLISECOND);
+        advance.join().unwrap();
+
+        assert_eq!(id_gen.stats().waits, 1);
+        assert_eq!(id_gen.stats().drift, Duration::ZERO);
+    }
+}
@@ -0,0 +1,110 @@
+//! The 12 bytes [xids](crate::xid) and [ObjectIds](crate::objectid) share:
+//! a 32-bit timestamp in seconds since the unix epoch, 5 bytes identifying
+//! the process and a 24-bit counter starting at a random value. Only their
+//! string forms differ.
+
+use crate::host::host_identity;
+use crate::rng::Rng;
+use crate::Clock;
+use std::time::{Duration, SystemTime};
+
+pub(crate) const COUNTER_MASK: u32 = 0xff_ffff;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct TimedId([u8; 12]);
+
+impl TimedId {
+    pub fn from_parts(timestamp: u32, process: [u8; 5], counter: u32) -> Self {
+        let mut bytes = [0u8; 12];
+        bytes[..4].copy_from_slice(&timestamp.to_be_bytes());
+        bytes[4..9].copy_from_slice(&process);
+        bytes[9..].copy_from_slice(&counter.to_be_bytes()[1..]);
+
+        TimedId(bytes)
+    }
+
+    pub const fn from_bytes(bytes: [u8; 12]) -> Self {
+        TimedId(bytes)
+    }
+
+    pub fn timestamp(&self) -> u32 {
+        u32::from_be_bytes([self.0[0], self.0[1], self.0[2], self.0[3]])
+    }
+
+    pub fn created_at(&self) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(self.timestamp() as u64)
+    }
+
+    pub fn process(&self) -> [u8; 5] {
+        let mut process = [0u8; 5];
+        process.copy_from_slice(&self.0[4..9]);
+        process
+    }
+
+    pub fn counter(&self) -> u32 {
+        u32::from_be_bytes([0, self.0[9], self.0[10], self.0[11]])
+    }
+
+    pub const fn as_bytes(&self) -> &[u8; 12] {
+        &self.0
+    }
+}
+
+/// Issues [`TimedId`]s for this process: the last 3 bytes of the host
+/// identity and the low 16 bits of the process id, then the counter.
+#[derive(Debug, Clone)]
+pub(crate) struct TimedIdGenerator<C> {
+    clock: C,
+    process: [u8; 5],
+    counter: u32,
+}
+
+impl<C: Clock> TimedIdGenerator<C> {
+    pub fn with_clock(clock: C) -> Self {
+        let host = host_identity().to_be_bytes();
+        let pid = (std::process::id() as u16).to_be_bytes();
+
+        Self {
+            clock,
+            process: [host[5], host[6], host[7], pid[0], pid[1]],
+            counter: Rng::new().next_u64() as u32 & COUNTER_MASK,
+        }
+    }
+
+    pub fn generate(&mut self) -> TimedId {
+        let seconds = (self.clock.now_millis() / 1_000).clamp(0, u32::MAX as i64) as u32;
+        self.counter = (self.counter + 1) & COUNTER_MASK;
+
+        TimedId::from_parts(seconds, self.process, self.counter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timed_id_generate() {
+        let mut id_gen = TimedIdGenerator::with_clock(|| 1_700_000_000_999);
+
+        let a = id_gen.generate();
+        let b = id_gen.generate();
+
+        assert_eq!(a.timestamp(), 1_700_000_000);
+        assert_eq!(a.process(), b.process());
+        assert_eq!(b.counter(), (a.counter() + 1) & COUNTER_MASK);
+        assert_eq!(
+            TimedId::from_parts(a.timestamp(), a.process(), a.counter()),
+            a
+        );
+    }
+
+    #[test]
+    fn test_timed_id_counter_wraps() {
+        let mut id_gen = TimedIdGenerator::with_clock(|| 0);
+        id_gen.counter = COUNTER_MASK - 1;
+
+        assert_eq!(id_gen.generate().counter(), COUNTER_MASK);
+        assert_eq!(id_gen.generate().counter(), 0);
+    }
+}
@@ -0,0 +1,180 @@
+use crate::host::fnv1a;
+use crate::rng::Rng;
+use crate::{Id, IdError, ObfuscatedId, ObfuscationKey};
+
+const DEFAULT_ALPHABET: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+const MIN_ALPHABET_LEN: usize = 16;
+
+/// Encodes IDs as short alphanumeric tokens for public URLs, in the spirit of
+/// Hashids and Sqids, e.g. `/orders/4vQm0c8XZrT`.
+///
+/// The salt shuffles the alphabet and keys an [`ObfuscationKey`] permutation
+/// of the ID, so consecutive IDs give unrelated tokens and a different salt
+/// gives different tokens. Tokens are at most 11 characters with the default
+/// alphabet of 62.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenEncoder {
+    alphabet: Vec<u8>,
+    key: ObfuscationKey,
+}
+
+impl TokenEncoder {
+    /// an encoder over the digits and ASCII letters, shuffled by `salt`
+    pub fn with_salt(salt: &str) -> Self {
+        Self::new(DEFAULT_ALPHABET, salt).expect("the default alphabet is valid")
+    }
+
+    /// an encoder over `alphabet`, which needs at least 16 distinct printable
+    /// ASCII characters, shuffled by `salt`
+    pub fn new(alphabet: &str, salt: &str) -> Result<Self, IdError> {
+        let mut alphabet = alphabet.as_bytes().to_vec();
+
+        if !alphabet.iter().all(u8::is_ascii_graphic) {
+            return Err(IdError::InvalidAlphabet("only printable ASCII is allowed"));
+        }
+
+        if alphabet.len() < MIN_ALPHABET_LEN {
+            return Err(IdError::InvalidAlphabet(
+                "at least 16 characters are needed",
+            ));
+        }
+
+        if alphabet
+            .iter()
+            .enumerate()
+            .any(|(i, c)| alphabet[i + 1..].contains(c))
+        {
+            return Err(IdError::InvalidAlphabet("characters must not repeat"));
+        }
+
+        let secret = fnv1a(salt.as_bytes());
+        let mut rng = Rng::with_seed(secret);
+
+        // Fisher-Yates
+        for i in (1..alphabet.len()).rev() {
+            alphabet.swap(i, (rng.next_u64() % (i as u64 + 1)) as usize);
+        }
+
+        Ok(Self {
+            alphabet,
+            key: ObfuscationKey::new(!secret),
+        })
+    }
+
+    pub fn encode(&self, id: Id) -> String {
+        let base = self.alphabet.len() as u64;
+        let mut value = id.obfuscate(&self.key).as_u64();
+        let mut token = Vec::new();
+
+        loop {
+            token.push(self.alphabet[(value % base) as usize]);
+            value /= base;
+
+            if value == 0 {
+                break;
+            }
+        }
+
+        token.iter().rev().map(|&b| b as char).collect()
+    }
+
+    /// decode a token produced by [`TokenEncoder::encode`] with the same
+    /// alphabet and salt
+    pub fn decode(&self, token: &str) -> Result<Id, IdError> {
+        let base = self.alphabet.len() as u64;
+
+        // a leading zero digit would give a second token for the same id
+        if token.is_empty() || (token.len() > 1 && token.as_bytes()[0] == self.alphabet[0]) {
+            return Err(IdError::InvalidIdString);
+        }
+
+        let value = token.bytes().try_fold(0u64, |acc, b| {
+            let digit = self
+                .alphabet
+                .iter()
+                .position(|&c| c == b)
+                .ok_or(IdError::InvalidIdString)?;
+
+            acc.checked_mul(base)
+                .and_then(|acc| acc.checked_add(digit as u64))
+                .ok_or(IdError::InvalidIdString)
+        })?;
+
+        Ok(ObfuscatedId::from(value).reveal(&self.key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IdGenerator;
+
+    #[test]
+    fn test_token_round_trip() {
+        let encoder = TokenEncoder::with_salt("orders");
+        let ids = IdGenerator::new(1, 2).unwrap().generate_ids(1_000);
+
+        let tokens: Vec<String> = ids
+            .iter()
+            .map(|&raw| encoder.encode(Id::try_from(raw).unwrap()))
+            .collect();
+
+        assert!(tokens.iter().all(|t| t.len() <= 11));
+        assert!(!tokens.windows(2).all(|w| w[0] < w[1]));
+        assert!(ids
+            .iter()
+            .zip(&tokens)
+            .all(|(&raw, t)| encoder.decode(t).map(i64::try_from) == Ok(Ok(raw))));
+
+        for raw in [0, 1, u64::MAX] {
+            let id = Id::from(raw);
+            assert_eq!(encoder.decode(&encoder.encode(id)), Ok(id));
+        }
+    }
+
+    #[test]
+    fn test_token_salt_and_alphabet() {
+        let id = Id::from(1_234_567_890);
+        let encoder = TokenEncoder::new("abcdefghjkmnpqrstuvwxyz23456789", "s").unwrap();
+        let token = encoder.encode(id);
+
+        assert!(token
+            .bytes()
+            .all(|b| b"abcdefghjkmnpqrstuvwxyz23456789".contains(&b)));
+        assert_eq!(encoder.decode(&token), Ok(id));
+        assert_ne!(
+            TokenEncoder::with_salt("a").encode(id),
+            TokenEncoder::with_salt("b").encode(id)
+        );
+        assert_eq!(
+            TokenEncoder::with_salt("a").encode(id),
+            TokenEncoder::with_salt("a").encode(id)
+        );
+    }
+
+    #[test]
+    fn test_token_rejects_invalid() {
+        let encoder = TokenEncoder::with_salt("");
+
+        assert_eq!(encoder.decode(""), Err(IdError::InvalidIdString));
+        assert_eq!(encoder.decode("ab-c"), Err(IdError::InvalidIdString));
+        assert_eq!(
+            encoder.decode(&"z".repeat(12)),
+            Err(IdError::InvalidIdString)
+        );
+        assert_eq!(
+            TokenEncoder::new("0123456789", "").err(),
+            Some(IdError::InvalidAlphabet(
+                "at least 16 characters are needed"
+            ))
+        );
+        assert_eq!(
+            TokenEncoder::new("0123456789abcdeff", "").err(),
+            Some(IdError::InvalidAlphabet("characters must not repeat"))
+        );
+        assert_eq!(
+            TokenEncoder::new("0123456789 abcdef", "").err(),
+            Some(IdError::InvalidAlphabet("only printable ASCII is allowed"))
+        );
+    }
+}
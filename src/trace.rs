@@ -0,0 +1,152 @@
+//! Trace and span IDs for distributed tracing, in the format of W3C Trace
+//! Context and OpenTelemetry.
+//!
+//! Trace IDs are UUIDv7 values and span IDs snowflake IDs, so both sort by
+//! the time they were created, and neither is ever the invalid all-zero ID.
+//! To use them with the OpenTelemetry SDK, implement its `IdGenerator` trait
+//! over a `Mutex<TraceIdGenerator>`, converting with `TraceId::to_bytes` and
+//! `SpanId::to_bytes`.
+
+use crate::{IdError, IdGenerator, Uuid7Generator};
+use std::fmt;
+use std::num::{NonZeroU128, NonZeroU64};
+
+/// A 16-byte trace ID, printed as 32 lowercase hex digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TraceId(NonZeroU128);
+
+impl TraceId {
+    pub const fn as_u128(self) -> u128 {
+        self.0.get()
+    }
+
+    pub const fn to_bytes(self) -> [u8; 16] {
+        self.0.get().to_be_bytes()
+    }
+}
+
+impl fmt::Display for TraceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:032x}", self.0)
+    }
+}
+
+/// An 8-byte span ID, printed as 16 lowercase hex digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SpanId(NonZeroU64);
+
+impl SpanId {
+    pub const fn as_u64(self) -> u64 {
+        self.0.get()
+    }
+
+    pub const fn to_bytes(self) -> [u8; 8] {
+        self.0.get().to_be_bytes()
+    }
+}
+
+impl fmt::Display for SpanId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// Generates [`TraceId`]s and [`SpanId`]s.
+///
+/// The last 62 bits of a trace ID are random, so they also meet the
+/// randomness the W3C `random` trace flag asks for. Span IDs carry the
+/// machine and server id of the generator, like any other snowflake ID.
+///
+/// A generator isn't `Clone`, as a copy would issue the same span IDs. Use
+/// [`TraceIdGenerator::split`] to hand generators out to several threads.
+#[derive(Debug)]
+pub struct TraceIdGenerator {
+    traces: Uuid7Generator,
+    spans: IdGenerator,
+}
+
+impl TraceIdGenerator {
+    pub fn new(machine_id: i32, server_id: i32) -> Result<Self, IdError> {
+        Ok(Self {
+            traces: Uuid7Generator::new(),
+            spans: IdGenerator::new(machine_id, server_id)?,
+        })
+    }
+
+    /// split this generator into `2^new_machine_bits` generators whose span
+    /// IDs never collide, see [`IdGenerator::split`]
+    pub fn split(self, new_machine_bits: u32) -> Result<Vec<Self>, IdError> {
+        Ok(self
+            .spans
+            .split(new_machine_bits)?
+            .into_iter()
+            .map(|spans| Self {
+                traces: Uuid7Generator::new(),
+                spans,
+            })
+            .collect())
+    }
+
+    pub fn new_trace_id(&mut self) -> TraceId {
+        let uuid = self.traces.generate().as_u128();
+
+        TraceId(NonZeroU128::new(uuid).expect("the version bits of a UUIDv7 are set"))
+    }
+
+    pub fn new_span_id(&mut self) -> SpanId {
+        loop {
+            // only the first id at the epoch with machine and server id 0 is
+            // zero, and the next one isn't
+            if let Some(id) = NonZeroU64::new(self.spans.generate_id() as u64) {
+                return SpanId(id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_and_span_ids() {
+        let mut id_gen = TraceIdGenerator::new(1, 2).unwrap();
+
+        let traces: Vec<TraceId> = (0..1_000).map(|_| id_gen.new_trace_id()).collect();
+        let spans: Vec<SpanId> = (0..10_000).map(|_| id_gen.new_span_id()).collect();
+
+        assert!(traces.windows(2).all(|w| w[0] < w[1]));
+        assert!(spans.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(crate::Id::from(spans[0].as_u64()).parts().server_id, 2);
+    }
+
+    #[test]
+    fn test_split_span_ids_are_unique() {
+        let mut spans: Vec<SpanId> = TraceIdGenerator::new(1, 2)
+            .unwrap()
+            .split(2)
+            .unwrap()
+            .into_iter()
+            .flat_map(|mut id_gen| (0..1_000).map(move |_| id_gen.new_span_id()))
+            .collect();
+
+        spans.sort();
+        spans.dedup();
+        assert_eq!(spans.len(), 4_000);
+    }
+
+    #[test]
+    fn test_trace_id_format() {
+        let trace = TraceId(NonZeroU128::new(0x4bf9_2f35_77b3_4da6_a3ce_929d_0e0e_4736).unwrap());
+        let span = SpanId(NonZeroU64::new(0x00f0_67aa_0ba9_02b7).unwrap());
+
+        // the example IDs of the W3C Trace Context spec
+        assert_eq!(trace.to_string(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(span.to_string(), "00f067aa0ba902b7");
+        assert_eq!(trace.to_bytes()[0], 0x4b);
+        assert_eq!(
+            span.to_bytes(),
+            [0x00, 0xf0, 0x67, 0xaa, 0x0b, 0xa9, 0x02, 0xb7]
+        );
+    }
+}
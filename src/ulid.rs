@@ -0,0 +1,172 @@
+//! [ULID](https://github.com/ulid/spec) generation.
+//!
+//! A ULID is 128 bits: a 48-bit unix timestamp in milliseconds followed by
+//! 80 random bits. IDs from one [`UlidGenerator`] are strictly increasing,
+//! since within a millisecond the random part is incremented instead of
+//! being drawn again.
+
+use crate::crockford::{decode_symbol, ALPHABET};
+use crate::rng::Rng;
+use crate::utils::*;
+use crate::IdError;
+use std::fmt;
+use std::str::FromStr;
+
+const RANDOM_BITS: u32 = 80;
+const RANDOM_MASK: u128 = (1 << RANDOM_BITS) - 1;
+const ENCODED_LEN: usize = 26;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Ulid(u128);
+
+impl Ulid {
+    pub const fn from_parts(timestamp: u64, random: u128) -> Self {
+        Ulid((timestamp as u128) << RANDOM_BITS | (random & RANDOM_MASK))
+    }
+
+    /// milliseconds since the unix epoch
+    pub const fn timestamp(self) -> u64 {
+        (self.0 >> RANDOM_BITS) as u64
+    }
+
+    pub const fn random(self) -> u128 {
+        self.0 & RANDOM_MASK
+    }
+
+    pub const fn as_u128(self) -> u128 {
+        self.0
+    }
+}
+
+impl From<u128> for Ulid {
+    fn from(value: u128) -> Self {
+        Ulid(value)
+    }
+}
+
+impl From<Ulid> for u128 {
+    fn from(ulid: Ulid) -> Self {
+        ulid.0
+    }
+}
+
+impl fmt::Display for Ulid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let encoded: String = (0..ENCODED_LEN)
+            .rev()
+            .map(|i| ALPHABET[((self.0 >> (i * 5)) & 0x1f) as usize] as char)
+            .collect();
+
+        f.write_str(&encoded)
+    }
+}
+
+impl FromStr for Ulid {
+    type Err = IdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != ENCODED_LEN {
+            return Err(IdError::InvalidIdString);
+        }
+
+        let value = s.bytes().try_fold(0u128, |acc, b| {
+            let digit = decode_symbol(b)
+                .filter(|&d| d < 32)
+                .ok_or(IdError::InvalidIdString)?;
+
+            Ok(acc << 5 | digit as u128)
+        })?;
+
+        // 26 symbols carry 130 bits, so the first one must be at most '7'
+        if decode_symbol(s.as_bytes()[0]) > Some(7) {
+            return Err(IdError::InvalidIdString);
+        }
+
+        Ok(Ulid(value))
+    }
+}
+
+/// Generates monotonically increasing ULIDs.
+#[derive(Debug, Clone)]
+pub struct UlidGenerator {
+    last: Ulid,
+    rng: Rng,
+}
+
+impl Default for UlidGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UlidGenerator {
+    pub fn new() -> Self {
+        Self {
+            last: Ulid(0),
+            rng: Rng::new(),
+        }
+    }
+
+    pub fn generate(&mut self) -> Ulid {
+        let epoch = get_epoch();
+        let last_timestamp = self.last.timestamp() as i64;
+        let now = get_timestamp(epoch);
+
+        // a clock that went backwards is treated like the same millisecond
+        // so the ordering guarantee holds
+        if now <= last_timestamp {
+            if self.last.random() < RANDOM_MASK {
+                self.last = Ulid(self.last.0 + 1);
+                return self.last;
+            }
+
+            let now = bind_time(last_timestamp, epoch);
+            self.last = Ulid::from_parts(now as u64, self.rng.next_u128());
+            return self.last;
+        }
+
+        self.last = Ulid::from_parts(now as u64, self.rng.next_u128());
+        self.last
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ulid_monotonic() {
+        let mut ulid_gen = UlidGenerator::new();
+
+        let ulids: Vec<Ulid> = (0..10_000).map(|_| ulid_gen.generate()).collect();
+
+        assert!(ulids.windows(2).all(|w| w[0] < w[1]));
+        assert!(ulids[0].timestamp() <= get_timestamp(get_epoch()) as u64);
+    }
+
+    #[test]
+    fn test_ulid_random_overflow_moves_to_next_millisecond() {
+        let mut ulid_gen = UlidGenerator::new();
+        let now = get_timestamp(get_epoch()) as u64;
+        ulid_gen.last = Ulid::from_parts(now, RANDOM_MASK);
+
+        assert!(ulid_gen.generate().timestamp() > now);
+    }
+
+    #[test]
+    fn test_ulid_string_round_trip() {
+        let ulid = Ulid::from_parts(1_469_918_176_385, 0x1234_5678_9abc_def0_1234);
+
+        assert_eq!(ulid.to_string(), "01ARYZ6S4128T5CY4TQKFF04HM");
+        assert_eq!("01ARYZ6S4128T5CY4TQKFF04HM".parse(), Ok(ulid));
+        assert_eq!("01aryz6s4128t5cy4tqkff04hm".parse(), Ok(ulid));
+        assert_eq!(
+            Ulid::from(u128::MAX).to_string(),
+            "7ZZZZZZZZZZZZZZZZZZZZZZZZZ"
+        );
+        assert_eq!(
+            "8ZZZZZZZZZZZZZZZZZZZZZZZZZ".parse::<Ulid>(),
+            Err(IdError::InvalidIdString)
+        );
+    }
+}
@@ -1,13 +1,46 @@
-use std::{
-    hint::spin_loop,
-    time::{Duration, SystemTime},
-};
+use crate::{IdError, WaitStrategy};
+use std::time::{Duration, Instant, SystemTime};
 
-pub fn get_timestamp(epoch: SystemTime) -> i64 {
+const MILLISECOND: Duration = Duration::from_millis(1);
+
+/// the current system time, read from `Date.now()` in the browser where
+/// `SystemTime::now` isn't available
+pub fn system_now() -> SystemTime {
+    #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+    return SystemTime::UNIX_EPOCH + Duration::from_millis(js_sys::Date::now() as u64);
+
+    #[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
     SystemTime::now()
+}
+
+pub fn get_timestamp(epoch: SystemTime) -> i64 {
+    get_ticks(epoch, MILLISECOND)
+}
+
+/// number of whole `tick`s elapsed since `epoch`, or 0 if the clock lies before it
+pub fn get_ticks(epoch: SystemTime, tick: Duration) -> i64 {
+    try_get_ticks(epoch, tick).unwrap_or_default()
+}
+
+/// number of whole `tick`s elapsed since `epoch`
+pub fn try_get_ticks(epoch: SystemTime, tick: Duration) -> Result<i64, IdError> {
+    system_now()
         .duration_since(epoch)
-        .unwrap_or(Duration::default())
-        .as_millis() as i64
+        .map(|elapsed| to_ticks(elapsed, tick))
+        .map_err(|_| IdError::ClockBeforeEpoch)
+}
+
+pub fn to_ticks(elapsed: Duration, tick: Duration) -> i64 {
+    i64::try_from(elapsed.as_nanos() / tick.as_nanos()).unwrap_or(i64::MAX)
+}
+
+pub fn from_ticks(ticks: i64, tick: Duration) -> Duration {
+    let nanos = tick.as_nanos() * ticks.max(0) as u128;
+
+    Duration::new(
+        (nanos / 1_000_000_000) as u64,
+        (nanos % 1_000_000_000) as u32,
+    )
 }
 
 pub fn get_epoch() -> SystemTime {
@@ -15,14 +48,45 @@ pub fn get_epoch() -> SystemTime {
 }
 
 pub fn bind_time(timestamp: i64, epoch: SystemTime) -> i64 {
+    bind_ticks(timestamp, epoch, MILLISECOND)
+}
+
+pub fn bind_ticks(timestamp: i64, epoch: SystemTime, tick: Duration) -> i64 {
+    wait_until_after(timestamp, || get_ticks(epoch, tick))
+}
+
+/// spin until `now` returns a value greater than `timestamp`
+pub fn wait_until_after(timestamp: i64, now: impl Fn() -> i64) -> i64 {
+    wait_until_after_with(timestamp, WaitStrategy::SpinHint, now)
+}
+
+/// wait until `now` returns a value greater than `timestamp`, backing off
+/// between the reads as `strategy` says
+pub fn wait_until_after_with(timestamp: i64, strategy: WaitStrategy, now: impl Fn() -> i64) -> i64 {
+    wait_until_after_or_deadline(timestamp, strategy, None, now).expect("no deadline to pass")
+}
+
+/// like `wait_until_after_with`, but give up with `None` once `deadline` has passed
+pub fn wait_until_after_or_deadline(
+    timestamp: i64,
+    strategy: WaitStrategy,
+    deadline: Option<Instant>,
+    now: impl Fn() -> i64,
+) -> Option<i64> {
     let mut very_last_time: i64;
+    let mut attempt: u32 = 0;
 
     loop {
-        very_last_time = get_timestamp(epoch);
+        very_last_time = now();
         if very_last_time > timestamp {
-            return very_last_time;
+            return Some(very_last_time);
+        }
+
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return None;
         }
 
-        spin_loop();
+        strategy.wait(attempt);
+        attempt = attempt.saturating_add(1);
     }
 }
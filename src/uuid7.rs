@@ -0,0 +1,152 @@
+use crate::rng::Rng;
+use crate::utils::*;
+use crate::MAX_IDS_PER_MILLISECOND;
+use std::fmt;
+
+const VERSION: u128 = 0x7;
+const VARIANT: u128 = 0b10;
+const RAND_B_MASK: u128 = (1 << 62) - 1;
+
+/// An RFC 9562 version 7 UUID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Uuid7(u128);
+
+impl Uuid7 {
+    /// milliseconds since the unix epoch
+    pub const fn timestamp(self) -> u64 {
+        (self.0 >> 80) as u64
+    }
+
+    /// the 12-bit counter stored in `rand_a`
+    pub const fn sequence(self) -> usize {
+        ((self.0 >> 64) & 0xfff) as usize
+    }
+
+    pub const fn as_u128(self) -> u128 {
+        self.0
+    }
+
+    pub const fn to_bytes(self) -> [u8; 16] {
+        self.0.to_be_bytes()
+    }
+}
+
+impl From<Uuid7> for u128 {
+    fn from(uuid: Uuid7) -> Self {
+        uuid.0
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl From<Uuid7> for uuid::Uuid {
+    fn from(uuid: Uuid7) -> Self {
+        uuid::Uuid::from_u128(uuid.0)
+    }
+}
+
+impl fmt::Display for Uuid7 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let v = self.0;
+
+        write!(
+            f,
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            v >> 96,
+            (v >> 80) & 0xffff,
+            (v >> 64) & 0xffff,
+            (v >> 48) & 0xffff,
+            v & 0xffff_ffff_ffff
+        )
+    }
+}
+
+/// Generates time-ordered UUIDv7 values.
+///
+/// The 12-bit `rand_a` field holds a per-millisecond sequence, the same
+/// scheme [`IdGenerator`](crate::IdGenerator) uses, so UUIDs from one
+/// generator are strictly increasing; `rand_b` is random.
+#[derive(Debug, Clone)]
+pub struct Uuid7Generator {
+    timestamp: i64,
+    index: usize,
+    rng: Rng,
+}
+
+impl Default for Uuid7Generator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Uuid7Generator {
+    pub fn new() -> Self {
+        Self {
+            timestamp: get_timestamp(get_epoch()),
+            index: 0,
+            rng: Rng::new(),
+        }
+    }
+
+    pub fn generate(&mut self) -> Uuid7 {
+        let epoch = get_epoch();
+        let now = get_timestamp(epoch);
+
+        if now > self.timestamp {
+            self.timestamp = now;
+            self.index = 0;
+        } else {
+            self.index = (self.index + 1) % MAX_IDS_PER_MILLISECOND;
+
+            if self.index == 0 {
+                self.timestamp = bind_time(self.timestamp, epoch);
+            }
+        }
+
+        let rand_b = self.rng.next_u128() & RAND_B_MASK;
+
+        Uuid7(
+            (self.timestamp as u128) << 80
+                | VERSION << 76
+                | (self.index as u128) << 64
+                | VARIANT << 62
+                | rand_b,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uuid7_layout() {
+        let uuid = Uuid7Generator::new().generate();
+        let s = uuid.to_string();
+
+        assert_eq!(s.len(), 36);
+        assert_eq!(&s[14..15], "7");
+        assert!(matches!(&s[19..20], "8" | "9" | "a" | "b"));
+        assert!(uuid.timestamp() <= get_timestamp(get_epoch()) as u64);
+    }
+
+    #[test]
+    fn test_uuid7_monotonic() {
+        let mut uuid_gen = Uuid7Generator::new();
+
+        let uuids: Vec<Uuid7> = (0..MAX_IDS_PER_MILLISECOND * 3)
+            .map(|_| uuid_gen.generate())
+            .collect();
+
+        assert!(uuids.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn test_uuid7_interop() {
+        let uuid = Uuid7Generator::new().generate();
+        let converted = uuid::Uuid::from(uuid);
+
+        assert_eq!(converted.get_version_num(), 7);
+        assert_eq!(converted.to_string(), uuid.to_string());
+    }
+}
@@ -0,0 +1,55 @@
+//! JavaScript bindings, built with `cargo rustc --release --features wasm
+//! --target wasm32-unknown-unknown --crate-type cdylib`.
+//!
+//! The generator reads the time from `Date.now()`. Threads aren't available
+//! in the browser, so background bucket refills can't be used there, and
+//! [`ClockMode::Monotonic`](crate::ClockMode::Monotonic) needs a runtime
+//! that provides `Instant`.
+
+use crate::IdGenerator;
+use wasm_bindgen::prelude::*;
+
+/// An ID generator exported to JavaScript as `JsIdGenerator`.
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct JsIdGenerator {
+    id_gen: IdGenerator,
+}
+
+#[wasm_bindgen]
+impl JsIdGenerator {
+    #[wasm_bindgen(constructor)]
+    pub fn new(machine_id: i32, server_id: i32) -> Result<JsIdGenerator, JsError> {
+        IdGenerator::new(machine_id, server_id)
+            .map(|id_gen| Self { id_gen })
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    /// generate an ID as a decimal string, since a JS number can't hold
+    /// more than 53 bits exactly
+    #[wasm_bindgen(js_name = generateId)]
+    pub fn generate_id(&mut self) -> String {
+        self.id_gen.generate_id().to_string()
+    }
+
+    /// generate an ID as a `BigInt`
+    #[wasm_bindgen(js_name = generateIdBigInt)]
+    pub fn generate_id_bigint(&mut self) -> i64 {
+        self.id_gen.generate_id()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_js_generator_string_ids() {
+        let mut id_gen = JsIdGenerator::new(1, 2).unwrap();
+
+        let first: i64 = id_gen.generate_id().parse().unwrap();
+        let second = id_gen.generate_id_bigint();
+
+        assert!(0 < first && first < second);
+    }
+}
@@ -0,0 +1,230 @@
+use super::{WorkerIdError, WorkerIdProvider};
+use crate::MAX_MACHINE_ID;
+use etcd_client::{Client, Compare, CompareOp, PutOptions, Txn, TxnOp};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tokio::time::timeout;
+
+/// Leases a machine id through etcd.
+///
+/// Each id `n` is a key `{prefix}/{n}` created only if it doesn't exist yet
+/// and attached to a lease, so etcd deletes it when the lease expires. A
+/// background thread keeps the lease alive, and dropping the allocator
+/// revokes it. If keep-alives stop arriving for longer than the TTL,
+/// [`WorkerIdProvider::is_held`] turns false.
+#[derive(Debug)]
+pub struct EtcdWorkerIdAllocator {
+    machine_id: i32,
+    held: Arc<AtomicBool>,
+    stop: Option<oneshot::Sender<()>>,
+    keeper: Option<JoinHandle<()>>,
+}
+
+impl EtcdWorkerIdAllocator {
+    /// lease the lowest free machine id below `prefix` on the cluster at `endpoints`
+    pub fn new(endpoints: &[&str], prefix: &str, ttl: Duration) -> Result<Self, WorkerIdError> {
+        let endpoints: Vec<String> = endpoints.iter().map(|e| e.to_string()).collect();
+        let prefix = prefix.to_string();
+        let ttl = ttl.as_secs().max(1) as i64;
+
+        let held = Arc::new(AtomicBool::new(true));
+        let (stop, stopped) = oneshot::channel();
+        let (acquired, result) = mpsc::channel();
+
+        let keeper = {
+            let held = held.clone();
+            thread::spawn(move || {
+                let runtime = match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(runtime) => runtime,
+                    Err(e) => return drop(acquired.send(Err(backend(e)))),
+                };
+
+                runtime.block_on(async move {
+                    // the TTL counts from before the lease was granted
+                    let granted_at = Instant::now();
+                    let (mut client, lease) = match acquire(&endpoints, &prefix, ttl).await {
+                        Ok((client, lease, machine_id)) => {
+                            let _ = acquired.send(Ok(machine_id));
+                            (client, lease)
+                        }
+                        Err(e) => return drop(acquired.send(Err(e))),
+                    };
+
+                    tokio::select! {
+                        _ = stopped => {}
+                        _ = keep_alive(&mut client, lease, ttl, granted_at, &held) => {}
+                    }
+
+                    // the lease expires on its own after the TTL, so waiting
+                    // longer for an unreachable etcd would only hold up `drop`
+                    let revoke = client.lease_revoke(lease);
+                    let _ = timeout(Duration::from_secs(ttl as u64), revoke).await;
+                })
+            })
+        };
+
+        match result.recv() {
+            Ok(Ok(machine_id)) => Ok(Self {
+                machine_id,
+                held,
+                stop: Some(stop),
+                keeper: Some(keeper),
+            }),
+            Ok(Err(e)) => {
+                let _ = keeper.join();
+                Err(e)
+            }
+            Err(_) => {
+                let _ = keeper.join();
+                Err(WorkerIdError::Backend("etcd lease thread panicked".into()))
+            }
+        }
+    }
+}
+
+impl WorkerIdProvider for EtcdWorkerIdAllocator {
+    fn machine_id(&self) -> i32 {
+        self.machine_id
+    }
+
+    fn is_held(&self) -> bool {
+        self.held.load(Ordering::Acquire)
+    }
+}
+
+impl Drop for EtcdWorkerIdAllocator {
+    fn drop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+
+        if let Some(keeper) = self.keeper.take() {
+            let _ = keeper.join();
+        }
+    }
+}
+
+/// grant a lease and attach it to the first machine id key nobody holds
+async fn acquire(
+    endpoints: &[String],
+    prefix: &str,
+    ttl: i64,
+) -> Result<(Client, i64, i32), WorkerIdError> {
+    let mut client = Client::connect(endpoints, None).await.map_err(backend)?;
+    let lease = client.lease_grant(ttl, None).await.map_err(backend)?.id();
+
+    for machine_id in 0..=MAX_MACHINE_ID {
+        let key = format!("{}/{}", prefix, machine_id);
+        let txn = Txn::new()
+            .when([Compare::create_revision(key.as_str(), CompareOp::Equal, 0)])
+            .and_then([TxnOp::put(
+                key.as_str(),
+                lease.to_string(),
+                Some(PutOptions::new().with_lease(lease)),
+            )]);
+
+        if client.txn(txn).await.map_err(backend)?.succeeded() {
+            return Ok((client, lease, machine_id));
+        }
+    }
+
+    let _ = client.lease_revoke(lease).await;
+    Err(WorkerIdError::Exhausted)
+}
+
+/// renew the lease every third of its TTL until it is lost
+///
+/// A failed keep-alive is retried on a new stream at the next interval, and
+/// the lease only counts as lost once etcd reports it expired or the TTL
+/// passed since the last renewal.
+async fn keep_alive(
+    client: &mut Client,
+    lease: i64,
+    ttl: i64,
+    mut renewed_at: Instant,
+    held: &AtomicBool,
+) {
+    let interval = Duration::from_millis((ttl as u64 * 1_000 / 3).max(1));
+    let ttl = Duration::from_secs(ttl as u64);
+    // dropped after an error, and opened again for the next renewal
+    let mut stream = None;
+
+    loop {
+        tokio::time::sleep(interval).await;
+
+        // a stalled request counts as a failed one, so the TTL check below
+        // is reached even when etcd stops answering
+        if stream.is_none() {
+            stream = timeout(interval, client.lease_keep_alive(lease))
+                .await
+                .ok()
+                .and_then(Result::ok);
+        }
+
+        let renewed = match stream {
+            Some((ref mut keeper, ref mut responses)) => {
+                match timeout(interval, keeper.keep_alive()).await {
+                    Ok(Ok(())) => timeout(interval, responses.message())
+                        .await
+                        .ok()
+                        .and_then(|message| message.ok().flatten()),
+                    _ => None,
+                }
+            }
+            None => None,
+        };
+
+        match renewed {
+            // a lease etcd reports with a TTL of 0 has already expired
+            Some(response) if response.ttl() <= 0 => break,
+            Some(_) => renewed_at = Instant::now(),
+            None => stream = None,
+        }
+
+        if renewed_at.elapsed() >= ttl {
+            break;
+        }
+    }
+
+    held.store(false, Ordering::Release);
+}
+
+fn backend(e: impl std::error::Error + Send + Sync + 'static) -> WorkerIdError {
+    WorkerIdError::Backend(Box::new(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    fn etcd_endpoint() -> String {
+        std::env::var("ETCD_ENDPOINT").unwrap_or_else(|_| "http://127.0.0.1:2379".to_string())
+    }
+
+    #[test]
+    #[ignore = "needs an etcd server at ETCD_ENDPOINT"]
+    fn test_etcd_worker_ids_are_unique_and_released() {
+        let endpoint = etcd_endpoint();
+        let prefix = format!("/unique_id/test/{:x}", Rng::new().next_u64());
+        let ttl = Duration::from_secs(5);
+
+        let first = EtcdWorkerIdAllocator::new(&[&endpoint], &prefix, ttl).unwrap();
+        let second = EtcdWorkerIdAllocator::new(&[&endpoint], &prefix, ttl).unwrap();
+        assert_ne!(first.machine_id(), second.machine_id());
+
+        let released = first.machine_id();
+        drop(first);
+
+        let third = EtcdWorkerIdAllocator::new(&[&endpoint], &prefix, ttl).unwrap();
+        assert_eq!(third.machine_id(), released);
+        assert!(third.is_held() && second.is_held());
+    }
+}
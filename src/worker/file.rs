@@ -0,0 +1,95 @@
+use super::{WorkerIdError, WorkerIdProvider};
+use crate::MAX_MACHINE_ID;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::path::Path;
+
+const DEFAULT_DIR: &str = "/var/run/uniqueid";
+
+/// Leases a machine id among the processes of one host through lock files.
+///
+/// Each id `n` is an exclusive lock on `worker-{n}.lock` in a directory,
+/// `/var/run/uniqueid` by default. The operating system releases the lock
+/// when the allocator is dropped or the process exits, even when it crashes,
+/// so an id is never leaked. The files themselves are left in place.
+#[derive(Debug)]
+pub struct FileWorkerIdAllocator {
+    machine_id: i32,
+    // holds the lock for as long as the allocator lives
+    _lock: File,
+}
+
+impl FileWorkerIdAllocator {
+    /// lock the lowest free machine id in `/var/run/uniqueid`
+    pub fn new() -> Result<Self, WorkerIdError> {
+        Self::in_dir(DEFAULT_DIR)
+    }
+
+    /// lock the lowest free machine id in `dir`, creating it if needed
+    pub fn in_dir(dir: impl AsRef<Path>) -> Result<Self, WorkerIdError> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir).map_err(backend)?;
+
+        for machine_id in 0..=MAX_MACHINE_ID {
+            let lock = OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(dir.join(format!("worker-{}.lock", machine_id)))
+                .map_err(backend)?;
+
+            match lock.try_lock() {
+                Ok(()) => {
+                    return Ok(Self {
+                        machine_id,
+                        _lock: lock,
+                    })
+                }
+                Err(TryLockError::WouldBlock) => continue,
+                Err(TryLockError::Error(e)) => return Err(backend(e)),
+            }
+        }
+
+        Err(WorkerIdError::Exhausted)
+    }
+}
+
+impl WorkerIdProvider for FileWorkerIdAllocator {
+    fn machine_id(&self) -> i32 {
+        self.machine_id
+    }
+}
+
+fn backend(e: std::io::Error) -> WorkerIdError {
+    WorkerIdError::Backend(Box::new(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    #[test]
+    fn test_file_allocators_get_distinct_ids() {
+        let dir = std::env::temp_dir().join(format!("uniqueid-{:x}", Rng::new().next_u64()));
+
+        let first = FileWorkerIdAllocator::in_dir(&dir).unwrap();
+        let second = FileWorkerIdAllocator::in_dir(&dir).unwrap();
+        assert_eq!((first.machine_id(), second.machine_id()), (0, 1));
+
+        // a dropped allocator frees its id for the next one
+        drop(first);
+        let first = FileWorkerIdAllocator::in_dir(&dir).unwrap();
+        assert_eq!(first.machine_id(), 0);
+
+        let rest: Vec<_> = (2..=MAX_MACHINE_ID)
+            .map(|_| FileWorkerIdAllocator::in_dir(&dir).unwrap())
+            .collect();
+        assert!(matches!(
+            FileWorkerIdAllocator::in_dir(&dir),
+            Err(WorkerIdError::Exhausted)
+        ));
+
+        drop((first, second, rest));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,85 @@
+//! Leasing machine ids from shared infrastructure, so a fleet of
+//! generators can pick non-colliding ids without manual bookkeeping.
+
+#[cfg(feature = "etcd")]
+mod etcd;
+mod file;
+mod pod;
+#[cfg(feature = "redis")]
+mod redis;
+
+#[cfg(feature = "etcd")]
+pub use self::etcd::EtcdWorkerIdAllocator;
+pub use self::file::FileWorkerIdAllocator;
+pub use self::pod::PodOrdinal;
+#[cfg(feature = "redis")]
+pub use self::redis::WorkerIdAllocator;
+#[cfg(feature = "redis")]
+pub(crate) use self::redis::{connect as redis_connect, connection as redis_connection};
+
+use std::fmt;
+
+/// A leased machine id.
+///
+/// Implemented by the allocators of each coordination backend. The id stays
+/// reserved for as long as the provider is alive, so keep it around for the
+/// lifetime of the generators built from it.
+pub trait WorkerIdProvider {
+    fn machine_id(&self) -> i32;
+
+    /// whether the lease is still known to be ours; a generator should stop
+    /// issuing IDs once this turns false
+    fn is_held(&self) -> bool {
+        true
+    }
+}
+
+/// Errors returned while leasing a machine id.
+#[derive(Debug)]
+pub enum WorkerIdError {
+    /// Every machine id is leased by another worker.
+    Exhausted,
+    /// The coordination backend failed.
+    Backend(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl fmt::Display for WorkerIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorkerIdError::Exhausted => write!(f, "every machine id is leased"),
+            WorkerIdError::Backend(e) => write!(f, "worker id backend failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for WorkerIdError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WorkerIdError::Exhausted => None,
+            WorkerIdError::Backend(e) => Some(e.as_ref()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode, IdGenerator};
+
+    struct Fixed(i32);
+
+    impl WorkerIdProvider for Fixed {
+        fn machine_id(&self) -> i32 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_generator_with_provider() {
+        let mut id_gen = IdGenerator::with_provider(&Fixed(9), 4).unwrap();
+        let parts = decode(id_gen.generate_id());
+
+        assert_eq!((parts.machine_id, parts.server_id), (9, 4));
+        assert!(IdGenerator::with_provider(&Fixed(32), 0).is_err());
+    }
+}
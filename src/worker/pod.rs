@@ -0,0 +1,120 @@
+use super::WorkerIdProvider;
+use crate::{EnvError, IdError, Layout};
+
+const POD_ORDINAL_VAR: &str = "UNIQUEID_POD_ORDINAL";
+const HOSTNAME_VAR: &str = "HOSTNAME";
+
+/// The machine id of a Kubernetes StatefulSet pod, taken from its ordinal.
+///
+/// The ordinal is read from `UNIQUEID_POD_ORDINAL`, which the downward API
+/// can fill from the `apps.kubernetes.io/pod-index` label, or else from the
+/// suffix of the pod's hostname, e.g. 3 for `idgen-3`. Ordinals are unique
+/// within a StatefulSet for as long as the pod exists, so nothing needs to
+/// be leased.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PodOrdinal {
+    ordinal: i32,
+}
+
+impl PodOrdinal {
+    /// read the ordinal from the environment, checking that it fits the
+    /// machine id field of `layout`
+    pub fn from_env(layout: &Layout) -> Result<Self, EnvError> {
+        Self::from_vars(layout, |var| std::env::var(var).ok())
+    }
+
+    fn from_vars(layout: &Layout, var: impl Fn(&str) -> Option<String>) -> Result<Self, EnvError> {
+        let (name, value, ordinal) = match var(POD_ORDINAL_VAR) {
+            Some(value) => {
+                let ordinal = value.trim().parse().ok();
+                (POD_ORDINAL_VAR, value, ordinal)
+            }
+            None => {
+                let value = var(HOSTNAME_VAR).unwrap_or_default();
+                let ordinal = value
+                    .trim()
+                    .rsplit_once('-')
+                    .and_then(|(_, suffix)| suffix.parse().ok());
+                (HOSTNAME_VAR, value, ordinal)
+            }
+        };
+
+        let ordinal: i32 = ordinal.ok_or(EnvError::Invalid { var: name, value })?;
+
+        if !(0..=layout.max_machine_id()).contains(&ordinal) {
+            return Err(IdError::InvalidMachineId(ordinal).into());
+        }
+
+        Ok(Self { ordinal })
+    }
+}
+
+impl WorkerIdProvider for PodOrdinal {
+    fn machine_id(&self) -> i32 {
+        self.ordinal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &'static [(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        move |var| {
+            pairs
+                .iter()
+                .find(|(k, _)| *k == var)
+                .map(|(_, v)| v.to_string())
+        }
+    }
+
+    #[test]
+    fn test_pod_ordinal() {
+        let layout = Layout::default();
+        let from = |pairs| PodOrdinal::from_vars(&layout, vars(pairs)).map(|p| p.machine_id());
+
+        assert_eq!(from(&[(HOSTNAME_VAR, "idgen-7")]), Ok(7));
+        assert_eq!(from(&[(HOSTNAME_VAR, "id-gen-0\n")]), Ok(0));
+        assert_eq!(
+            from(&[(POD_ORDINAL_VAR, "12"), (HOSTNAME_VAR, "idgen-7")]),
+            Ok(12)
+        );
+    }
+
+    #[test]
+    fn test_pod_ordinal_errors() {
+        let layout = Layout::default();
+        let from = |pairs| PodOrdinal::from_vars(&layout, vars(pairs)).map(|p| p.machine_id());
+
+        assert_eq!(
+            from(&[(HOSTNAME_VAR, "idgen")]),
+            Err(EnvError::Invalid {
+                var: HOSTNAME_VAR,
+                value: "idgen".to_string()
+            })
+        );
+        assert_eq!(
+            from(&[]),
+            Err(EnvError::Invalid {
+                var: HOSTNAME_VAR,
+                value: String::new()
+            })
+        );
+        assert_eq!(
+            from(&[(POD_ORDINAL_VAR, "x")]),
+            Err(EnvError::Invalid {
+                var: POD_ORDINAL_VAR,
+                value: "x".to_string()
+            })
+        );
+        assert_eq!(
+            from(&[(HOSTNAME_VAR, "idgen-32")]),
+            Err(EnvError::Config(IdError::InvalidMachineId(32)))
+        );
+        assert_eq!(
+            PodOrdinal::from_vars(&Layout::sonyflake(), vars(&[(HOSTNAME_VAR, "idgen-32")]))
+                .map(|p| p.machine_id()),
+            Ok(32)
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use super::{WorkerIdError, WorkerIdProvider};
+use crate::rng::Rng;
+use crate::MAX_MACHINE_ID;
+use redis::{Client, Connection, Script};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// extend the lease only while we still own it
+const RENEW: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("PEXPIRE", KEYS[1], ARGV[2])
+end
+return 0
+"#;
+
+/// delete the lease only while we still own it
+const RELEASE: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+end
+return 0
+"#;
+
+/// Leases a machine id through Redis.
+///
+/// Each id `n` is a key `{prefix}:{n}` set with `SET NX PX`, holding a random
+/// token of the owner. A background thread renews the lease every third of
+/// its TTL, and dropping the allocator releases it. If the lease lapses, for
+/// example because Redis was unreachable for longer than the TTL, another
+/// worker may take the id and [`WorkerIdProvider::is_held`] turns false.
+#[derive(Debug)]
+pub struct WorkerIdAllocator {
+    machine_id: i32,
+    held: Arc<AtomicBool>,
+    stop: Option<Sender<()>>,
+    renewer: Option<JoinHandle<()>>,
+}
+
+impl WorkerIdAllocator {
+    /// lease the lowest free machine id below `prefix` on the server at `url`
+    pub fn new(url: &str, prefix: &str, ttl: Duration) -> Result<Self, WorkerIdError> {
+        let client = Client::open(url).map_err(backend)?;
+        let token = format!("{:016x}", Rng::new().next_u64());
+        let ttl_ms = ttl.as_millis().max(1) as u64;
+        let mut conn = connect(&client, renew_interval(ttl_ms)).map_err(backend)?;
+
+        for machine_id in 0..=MAX_MACHINE_ID {
+            let key = format!("{}:{}", prefix, machine_id);
+            // the TTL counts from before the request reached the server
+            let leased_at = Instant::now();
+            let acquired: Option<String> = redis::cmd("SET")
+                .arg(&key)
+                .arg(&token)
+                .arg("NX")
+                .arg("PX")
+                .arg(ttl_ms)
+                .query(&mut conn)
+                .map_err(backend)?;
+
+            if acquired.is_some() {
+                let lease = Lease {
+                    key,
+                    token,
+                    ttl_ms,
+                    leased_at,
+                };
+                return Ok(Self::start(client, conn, machine_id, lease));
+            }
+        }
+
+        Err(WorkerIdError::Exhausted)
+    }
+
+    fn start(client: Client, conn: Connection, machine_id: i32, lease: Lease) -> Self {
+        let held = Arc::new(AtomicBool::new(true));
+        let (stop, stopped) = mpsc::channel();
+
+        let renewer = {
+            let held = held.clone();
+            thread::spawn(move || {
+                let Lease {
+                    key,
+                    token,
+                    ttl_ms,
+                    leased_at: mut renewed_at,
+                } = lease;
+                let renew = Script::new(RENEW);
+                let ttl = Duration::from_millis(ttl_ms);
+                let interval = renew_interval(ttl_ms);
+                // dropped after an error, and opened again for the next renewal
+                let mut conn = Some(conn);
+
+                while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                    let renewed = connection(&client, interval, &mut conn).and_then(|conn| {
+                        renew.key(&key).arg(&token).arg(ttl_ms).invoke::<i64>(conn)
+                    });
+
+                    match renewed {
+                        Ok(0) => held.store(false, Ordering::Release),
+                        Ok(_) => renewed_at = Instant::now(),
+                        Err(_) => conn = None,
+                    }
+
+                    // a failed request doesn't lose the lease until the TTL runs out
+                    if renewed_at.elapsed() >= ttl {
+                        held.store(false, Ordering::Release);
+                    }
+                }
+
+                if let Ok(conn) = connection(&client, interval, &mut conn) {
+                    let _: redis::RedisResult<i64> =
+                        Script::new(RELEASE).key(&key).arg(&token).invoke(conn);
+                }
+            })
+        };
+
+        Self {
+            machine_id,
+            held,
+            stop: Some(stop),
+            renewer: Some(renewer),
+        }
+    }
+}
+
+impl WorkerIdProvider for WorkerIdAllocator {
+    fn machine_id(&self) -> i32 {
+        self.machine_id
+    }
+
+    /// whether the last renewal confirmed that we still own the lease
+    fn is_held(&self) -> bool {
+        self.held.load(Ordering::Acquire)
+    }
+}
+
+impl Drop for WorkerIdAllocator {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+
+        if let Some(renewer) = self.renewer.take() {
+            let _ = renewer.join();
+        }
+    }
+}
+
+/// the key and token of a lease, and when it was last known to be ours
+struct Lease {
+    key: String,
+    token: String,
+    ttl_ms: u64,
+    leased_at: Instant,
+}
+
+/// renew every third of the TTL, so two renewals may fail before it runs out
+fn renew_interval(ttl_ms: u64) -> Duration {
+    Duration::from_millis((ttl_ms / 3).max(1))
+}
+
+/// a connection whose requests fail after `timeout`, so a hung server can't
+/// block the renewer past the TTL
+pub(crate) fn connect(client: &Client, timeout: Duration) -> redis::RedisResult<Connection> {
+    let conn = client.get_connection_with_timeout(timeout)?;
+    conn.set_read_timeout(Some(timeout))?;
+    conn.set_write_timeout(Some(timeout))?;
+
+    Ok(conn)
+}
+
+/// the open connection, or a new one from `client` after the last one broke
+pub(crate) fn connection<'a>(
+    client: &Client,
+    timeout: Duration,
+    conn: &'a mut Option<Connection>,
+) -> redis::RedisResult<&'a mut Connection> {
+    if conn.is_none() {
+        *conn = Some(connect(client, timeout)?);
+    }
+
+    Ok(conn.as_mut().expect("connected"))
+}
+
+fn backend(e: redis::RedisError) -> WorkerIdError {
+    WorkerIdError::Backend(Box::new(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redis_url() -> String {
+        std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1/".to_string())
+    }
+
+    #[test]
+    fn test_lease_lapses_when_server_hangs() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("redis://{}/", listener.local_addr().unwrap());
+        // answers the `SET` that takes the lease, then never again
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 1_024];
+            assert!(stream.read(&mut buf).unwrap() > 0);
+            stream.write_all(b"+OK\r\n").unwrap();
+            while stream.read(&mut buf).is_ok_and(|n| n > 0) {}
+            listener
+        });
+
+        let allocator = WorkerIdAllocator::new(&url, "test", Duration::from_millis(300)).unwrap();
+        assert!(allocator.is_held());
+
+        let started = Instant::now();
+        while allocator.is_held() {
+            assert!(started.elapsed() < Duration::from_secs(5));
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        // the renewer isn't stuck in a request, so dropping doesn't hang
+        drop(allocator);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        drop(server.join().unwrap());
+    }
+
+    #[test]
+    #[ignore = "needs a redis server at REDIS_URL"]
+    fn test_worker_ids_are_unique_and_released() {
+        let prefix = format!("unique_id:test:{:x}", Rng::new().next_u64());
+        let ttl = Duration::from_secs(5);
+
+        let first = WorkerIdAllocator::new(&redis_url(), &prefix, ttl).unwrap();
+        let second = WorkerIdAllocator::new(&redis_url(), &prefix, ttl).unwrap();
+        assert_ne!(first.machine_id(), second.machine_id());
+
+        let released = first.machine_id();
+        drop(first);
+
+        let third = WorkerIdAllocator::new(&redis_url(), &prefix, ttl).unwrap();
+        assert_eq!(third.machine_id(), released);
+        assert!(third.is_held() && second.is_held());
+    }
+}
@@ -0,0 +1,185 @@
+//! [xid](https://github.com/rs/xid) generation.
+//!
+//! An xid is 12 bytes: a 32-bit timestamp in seconds since the unix epoch, a
+//! 3-byte machine id, a 2-byte process id and a 24-bit counter starting at a
+//! random value. Its string form is 20 lowercase base32hex digits, which sort
+//! like the bytes.
+
+use crate::timed_id::{TimedId, TimedIdGenerator};
+use crate::{Clock, IdError, SystemClock};
+use std::fmt;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+const ALPHABET: &[u8; 32] = b"0123456789abcdefghijklmnopqrstuv";
+const ENCODED_LEN: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Xid(TimedId);
+
+impl Xid {
+    pub fn from_parts(timestamp: u32, machine: [u8; 3], pid: u16, counter: u32) -> Self {
+        let [pid_hi, pid_lo] = pid.to_be_bytes();
+        let process = [machine[0], machine[1], machine[2], pid_hi, pid_lo];
+
+        Xid(TimedId::from_parts(timestamp, process, counter))
+    }
+
+    pub const fn from_bytes(bytes: [u8; 12]) -> Self {
+        Xid(TimedId::from_bytes(bytes))
+    }
+
+    /// seconds since the unix epoch
+    pub fn timestamp(&self) -> u32 {
+        self.0.timestamp()
+    }
+
+    pub fn created_at(&self) -> SystemTime {
+        self.0.created_at()
+    }
+
+    pub fn machine(&self) -> [u8; 3] {
+        let [a, b, c, _, _] = self.0.process();
+        [a, b, c]
+    }
+
+    pub fn pid(&self) -> u16 {
+        let [_, _, _, hi, lo] = self.0.process();
+        u16::from_be_bytes([hi, lo])
+    }
+
+    pub fn counter(&self) -> u32 {
+        self.0.counter()
+    }
+
+    pub const fn as_bytes(&self) -> &[u8; 12] {
+        self.0.as_bytes()
+    }
+}
+
+impl fmt::Display for Xid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // 96 bits padded with 4 zero bits to 20 digits of 5 bits
+        let value = u128::from_be_bytes({
+            let mut buf = [0u8; 16];
+            buf[..12].copy_from_slice(self.as_bytes());
+            buf
+        }) >> 28;
+
+        (0..ENCODED_LEN as u32).rev().try_for_each(|i| {
+            write!(
+                f,
+                "{}",
+                ALPHABET[(value >> (i * 5) & 0x1f) as usize] as char
+            )
+        })
+    }
+}
+
+impl FromStr for Xid {
+    type Err = IdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != ENCODED_LEN {
+            return Err(IdError::InvalidIdString);
+        }
+
+        let value = s.bytes().try_fold(0u128, |acc, b| {
+            let digit = ALPHABET
+                .iter()
+                .position(|&c| c == b)
+                .ok_or(IdError::InvalidIdString)?;
+
+            Ok(acc << 5 | digit as u128)
+        })?;
+
+        // the padding bits must be zero, so every xid has one string form
+        if value & 0xf != 0 {
+            return Err(IdError::InvalidIdString);
+        }
+
+        let mut bytes = [0u8; 12];
+        bytes.copy_from_slice(&(value << 28).to_be_bytes()[..12]);
+
+        Ok(Xid::from_bytes(bytes))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct XidGenerator<C = SystemClock>(TimedIdGenerator<C>);
+
+impl Default for XidGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl XidGenerator {
+    pub fn new() -> Self {
+        Self::with_clock(SystemClock::default())
+    }
+}
+
+impl<C: Clock> XidGenerator<C> {
+    pub fn with_clock(clock: C) -> Self {
+        Self(TimedIdGenerator::with_clock(clock))
+    }
+
+    pub fn generate(&mut self) -> Xid {
+        Xid(self.0.generate())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timed_id::COUNTER_MASK;
+
+    #[test]
+    fn test_xid_generate() {
+        let mut xid_gen = XidGenerator::with_clock(|| 1_300_816_219_000);
+
+        let a = xid_gen.generate();
+        let b = xid_gen.generate();
+
+        assert_eq!(a.timestamp(), 1_300_816_219);
+        assert_eq!((a.machine(), a.pid()), (b.machine(), b.pid()));
+        assert_eq!(b.counter(), (a.counter() + 1) & COUNTER_MASK);
+        assert_eq!(a.to_string().parse(), Ok(a));
+    }
+
+    #[test]
+    fn test_xid_string_round_trip() {
+        // example from the Go implementation's tests
+        let xid = Xid::from_bytes([
+            0x4d, 0x88, 0xe1, 0x5b, 0x60, 0xf4, 0x86, 0xe4, 0x28, 0x41, 0x2d, 0xc9,
+        ]);
+
+        assert_eq!(xid.to_string(), "9m4e2mr0ui3e8a215n4g");
+        assert_eq!("9m4e2mr0ui3e8a215n4g".parse(), Ok(xid));
+        assert_eq!(xid.timestamp(), 1_300_816_219);
+        assert_eq!(xid.machine(), [0x60, 0xf4, 0x86]);
+        assert_eq!(xid.pid(), 0xe428);
+        assert_eq!(xid.counter(), 4_271_561);
+        assert_eq!(
+            Xid::from_parts(xid.timestamp(), xid.machine(), xid.pid(), xid.counter()),
+            xid
+        );
+    }
+
+    #[test]
+    fn test_xid_string_sorts_like_bytes() {
+        let xids = [[0; 12], [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1], [0xff; 12]].map(Xid::from_bytes);
+
+        assert_eq!(xids[2].to_string(), "vvvvvvvvvvvvvvvvvvvg");
+        assert!(xids.windows(2).all(|w| w[0].to_string() < w[1].to_string()));
+
+        for s in [
+            "9m4e2mr0ui3e8a215n4",
+            "9m4e2mr0ui3e8a215n4h",
+            "9M4E2MR0UI3E8A215N4G",
+        ] {
+            assert_eq!(s.parse::<Xid>(), Err(IdError::InvalidIdString), "{}", s);
+        }
+    }
+}
@@ -0,0 +1,18 @@
+/// A source of the current time.
+pub trait Clock {
+    /// milliseconds since the unix epoch
+    fn now_millis(&self) -> i64;
+
+    /// microseconds since the unix epoch, for layouts with ticks shorter
+    /// than a millisecond
+    fn now_micros(&self) -> i64 {
+        self.now_millis() * 1_000
+    }
+}
+
+/// any `Fn() -> i64` can be used as a clock, e.g. a function reading an RTC
+impl<F: Fn() -> i64> Clock for F {
+    fn now_millis(&self) -> i64 {
+        self()
+    }
+}
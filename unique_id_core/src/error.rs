@@ -0,0 +1,76 @@
+use thiserror::Error;
+
+/// Errors returned when a generator is configured with values that do not
+/// fit the ID layout, or when a value can't be converted into an [`Id`](crate::Id).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum IdError {
+    /// `machine_id` is negative or does not fit its bit field.
+    #[error("machine id {0} is out of range")]
+    InvalidMachineId(i32),
+    /// `server_id` is negative or does not fit its bit field.
+    #[error("server id {0} is out of range")]
+    InvalidServerId(i32),
+    /// The initial sequence doesn't fit the layout's sequence field.
+    #[error("sequence {0} is out of range")]
+    InvalidSequence(usize),
+    /// A split asked for more bits than the sequence field has.
+    #[error("cannot split {0} bits off the sequence")]
+    InvalidSplit(u32),
+    /// The bucket sizing is inconsistent.
+    #[error("invalid bucket config: {0}")]
+    InvalidBucketConfig(&'static str),
+    /// The custom epoch lies after the current system time.
+    #[error("epoch is later than the current time")]
+    EpochInFuture,
+    /// The system clock reads earlier than the epoch.
+    #[error("clock is earlier than the epoch")]
+    ClockBeforeEpoch,
+    /// Every sequence number of the current tick has been issued.
+    #[error("sequence exhausted for the current tick")]
+    SequenceExhausted,
+    /// The clock reads this many ticks earlier than the last issued timestamp.
+    #[error("clock moved backwards by {0} ticks")]
+    ClockRolledBack(i64),
+    /// A negative `i64` can't be an ID.
+    #[error("id {0} is negative")]
+    NegativeId(i64),
+    /// The ID doesn't fit into a non-negative `i64`.
+    #[error("id {0} does not fit into an i64")]
+    IdOutOfRange(u64),
+    /// The string is not a valid ID.
+    #[error("invalid id string")]
+    InvalidIdString,
+    /// The alphabet of a token encoder can't encode IDs unambiguously.
+    #[error("invalid alphabet: {0}")]
+    InvalidAlphabet(&'static str),
+    /// The check symbol of an encoded ID doesn't match its value.
+    #[error("check symbol does not match the id")]
+    ChecksumMismatch,
+    /// More threads use a generator pool than it has worker ids.
+    #[error("every worker id of the pool is in use")]
+    WorkerIdsExhausted,
+    /// A live generator of the registry already has this machine and server id.
+    #[error("a generator with machine id {0} and server id {1} already exists")]
+    DuplicateGenerator(i32, i32),
+    /// The process-wide generator was already set up.
+    #[error("the global generator is already initialized")]
+    AlreadyInitialized,
+    /// The process-wide generator wasn't set up yet.
+    #[error("the global generator is not initialized")]
+    NotInitialized,
+    /// The timestamp no longer fits the layout without reaching the sign bit.
+    #[error("timestamp overflows the id layout")]
+    TimestampOverflow,
+    /// Lazy generation would run further ahead of the clock than allowed.
+    #[error("lazy generation is too far ahead of the clock")]
+    DriftExceeded,
+    /// The clock didn't move on before the deadline.
+    #[error("timed out waiting for the clock")]
+    Timeout,
+    /// The generator issued as many IDs as its rate limit allows for now.
+    #[error("the rate limit of the generator is reached")]
+    RateLimited,
+    /// The shared backend an ID source draws from can't be reached.
+    #[error("the id backend is unavailable")]
+    BackendUnavailable,
+}
@@ -0,0 +1,39 @@
+//! The bit packing and sequence logic of `unique_id`, without `std`.
+//!
+//! Devices without an OS provide the time through a [`Clock`], for example
+//! from their RTC, and get IDs laid out exactly like the default layout of
+//! `unique_id::IdGenerator`: a millisecond timestamp with 41 usable bits, a
+//! 5-bit machine id, a 5-bit server id and a 12-bit sequence.
+
+#![no_std]
+
+mod clock;
+mod error;
+mod sequencer;
+
+pub use clock::Clock;
+pub use error::IdError;
+pub use sequencer::{pack, Sequencer};
+
+// The default layout, from the most significant bit down. Every other
+// constant is derived from these widths, so the fields can't overlap. The
+// timestamp field includes the sign bit, which stays clear, so only 41 of
+// its bits are usable, see `MAX_TIMESTAMP`.
+pub const TIMESTAMP_BITS: u32 = 42;
+pub const MACHINE_ID_BITS: u32 = 5;
+pub const SERVER_ID_BITS: u32 = 5;
+pub const SEQUENCE_BITS: u32 = 12;
+
+pub const SERVER_ID_SHIFT: u32 = SEQUENCE_BITS;
+pub const MACHINE_ID_SHIFT: u32 = SERVER_ID_SHIFT + SERVER_ID_BITS;
+pub const TIMESTAMP_SHIFT: u32 = MACHINE_ID_SHIFT + MACHINE_ID_BITS;
+
+/// number of sequence values in one millisecond
+pub const MAX_IDS_PER_MILLISECOND: usize = 1 << SEQUENCE_BITS;
+pub const MAX_MACHINE_ID: i32 = (1 << MACHINE_ID_BITS) - 1;
+pub const MAX_SERVER_ID: i32 = (1 << SERVER_ID_BITS) - 1;
+/// the largest timestamp that keeps the sign bit clear, so IDs stay
+/// non-negative in signed `BIGINT` columns
+pub const MAX_TIMESTAMP: i64 = i64::MAX >> TIMESTAMP_SHIFT;
+
+const _: () = assert!(TIMESTAMP_SHIFT + TIMESTAMP_BITS <= i64::BITS);
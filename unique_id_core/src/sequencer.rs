@@ -0,0 +1,167 @@
+use crate::{
+    Clock, IdError, MACHINE_ID_SHIFT, MAX_IDS_PER_MILLISECOND, MAX_MACHINE_ID, MAX_SERVER_ID,
+    MAX_TIMESTAMP, SERVER_ID_SHIFT, TIMESTAMP_SHIFT,
+};
+use core::hint::spin_loop;
+
+/// pack the fields of an ID with the default layout, masking each value to
+/// its field so an out of range value can't corrupt its neighbours
+///
+/// The timestamp is masked to [`MAX_TIMESTAMP`] rather than to its full 42
+/// bits, so the ID is never negative.
+pub const fn pack(timestamp: i64, machine_id: i32, server_id: i32, sequence: usize) -> i64 {
+    (timestamp & MAX_TIMESTAMP) << TIMESTAMP_SHIFT
+        | ((machine_id & MAX_MACHINE_ID) as i64) << MACHINE_ID_SHIFT
+        | ((server_id & MAX_SERVER_ID) as i64) << SERVER_ID_SHIFT
+        | (sequence & (MAX_IDS_PER_MILLISECOND - 1)) as i64
+}
+
+/// Issues IDs from the time of a [`Clock`], counting up a sequence within
+/// each millisecond.
+#[derive(Debug, Clone)]
+pub struct Sequencer<C> {
+    clock: C,
+    machine_id: i32,
+    server_id: i32,
+    timestamp: i64,
+    sequence: usize,
+}
+
+impl<C: Clock> Sequencer<C> {
+    /// create a sequencer, rejecting ids that don't fit their bit fields
+    pub fn new(clock: C, machine_id: i32, server_id: i32) -> Result<Self, IdError> {
+        if !(0..=MAX_MACHINE_ID).contains(&machine_id) {
+            return Err(IdError::InvalidMachineId(machine_id));
+        }
+
+        if !(0..=MAX_SERVER_ID).contains(&server_id) {
+            return Err(IdError::InvalidServerId(server_id));
+        }
+
+        Ok(Self {
+            clock,
+            machine_id,
+            server_id,
+            timestamp: -1,
+            sequence: 0,
+        })
+    }
+
+    /// issue an ID for the current millisecond without waiting
+    pub fn try_next_id(&mut self) -> Result<i64, IdError> {
+        let now = self.clock.now_millis();
+
+        if now < 0 {
+            return Err(IdError::ClockBeforeEpoch);
+        }
+
+        if now < self.timestamp {
+            return Err(IdError::ClockRolledBack(self.timestamp - now));
+        }
+
+        if now > MAX_TIMESTAMP {
+            return Err(IdError::TimestampOverflow);
+        }
+
+        if now == self.timestamp {
+            if self.sequence + 1 == MAX_IDS_PER_MILLISECOND {
+                return Err(IdError::SequenceExhausted);
+            }
+            self.sequence += 1;
+        } else {
+            self.timestamp = now;
+            self.sequence = 0;
+        }
+
+        Ok(pack(
+            self.timestamp,
+            self.machine_id,
+            self.server_id,
+            self.sequence,
+        ))
+    }
+
+    /// issue an ID, spinning while the sequence is exhausted or the clock
+    /// reads earlier than the last issued ID
+    ///
+    /// # Panics
+    ///
+    /// Panics once the timestamp overflows.
+    pub fn next_id(&mut self) -> i64 {
+        loop {
+            match self.try_next_id() {
+                Ok(id) => return id,
+                Err(IdError::TimestampOverflow) => panic!("timestamp overflow"),
+                Err(_) => spin_loop(),
+            }
+        }
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+
+    #[test]
+    fn test_sequencer_counts_within_millisecond() {
+        let now = Cell::new(1_000);
+        let mut seq = Sequencer::new(|| now.get(), 3, 4).unwrap();
+
+        assert_eq!(seq.try_next_id(), Ok(pack(1_000, 3, 4, 0)));
+        assert_eq!(seq.try_next_id(), Ok(pack(1_000, 3, 4, 1)));
+
+        now.set(1_001);
+        assert_eq!(seq.try_next_id(), Ok(pack(1_001, 3, 4, 0)));
+    }
+
+    #[test]
+    fn test_pack_never_negative() {
+        for timestamp in [0, MAX_TIMESTAMP, MAX_TIMESTAMP + 1, i64::MAX, -1, i64::MIN] {
+            assert!(pack(timestamp, -1, -1, usize::MAX) >= 0, "{}", timestamp);
+        }
+        assert_eq!(pack(MAX_TIMESTAMP + 1, 0, 0, 0), 0);
+
+        let now = Cell::new(MAX_TIMESTAMP);
+        let mut seq = Sequencer::new(|| now.get(), 31, 31).unwrap();
+        assert!(seq.try_next_id().unwrap() >= 0);
+
+        now.set(MAX_TIMESTAMP + 1);
+        assert_eq!(seq.try_next_id(), Err(IdError::TimestampOverflow));
+    }
+
+    #[test]
+    fn test_sequencer_errors() {
+        let now = Cell::new(1_000);
+        let mut seq = Sequencer::new(|| now.get(), 0, 0).unwrap();
+
+        for _ in 0..MAX_IDS_PER_MILLISECOND {
+            seq.try_next_id().unwrap();
+        }
+        assert_eq!(seq.try_next_id(), Err(IdError::SequenceExhausted));
+
+        now.set(990);
+        assert_eq!(seq.try_next_id(), Err(IdError::ClockRolledBack(10)));
+
+        now.set(-1);
+        assert_eq!(seq.try_next_id(), Err(IdError::ClockBeforeEpoch));
+
+        assert_eq!(
+            Sequencer::new(|| 0, 32, 0).err(),
+            Some(IdError::InvalidMachineId(32))
+        );
+    }
+
+    #[test]
+    fn test_sequencer_matches_layout_constants() {
+        assert_eq!(pack(1, 1, 1, 1), 1 << 22 | 1 << 17 | 1 << 12 | 1);
+        assert_eq!(
+            pack(0, MAX_MACHINE_ID, MAX_SERVER_ID, 0) >> SERVER_ID_SHIFT,
+            0x3ff
+        );
+    }
+}